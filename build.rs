@@ -0,0 +1,78 @@
+//! Bakes the board defaults from the environment into `presets::BOARD_DEFAULT` and provides
+//! the linker memory layout for the feature-gated MCU examples.
+
+use std::{env, fs, path::PathBuf};
+
+/// Flash time-outs selectable in the Configuration Register, in ms.
+const FLASH_TIMEOUTS_MS: [u32; 16] = [
+    40, 80, 120, 160, 200, 240, 280, 320, 360, 400, 600, 800, 1000, 1200, 1400, 1600,
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    board_default(&out);
+    example_memory(&out);
+}
+
+/// Reads a numeric board default from the environment.
+fn env_u16(name: &str, default: u16) -> u16 {
+    println!("cargo:rerun-if-env-changed={name}");
+
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("{name}={value:?} is not a number in the range 0-65535")),
+        Err(_) => default,
+    }
+}
+
+/// Writes `board_default.rs` with the `BOARD_DEFAULT` preset.
+fn board_default(out: &std::path::Path) {
+    let torch_ma = env_u16("LM36011_DEFAULT_TORCH_MA", 2);
+    let flash_ma = env_u16("LM36011_DEFAULT_FLASH_MA", 10);
+    let timeout_ms = env_u16("LM36011_DEFAULT_TIMEOUT_MS", 600);
+    let max_flash_ma = env_u16("LM36011_MAX_FLASH_MA", 1500);
+    let max_torch_ma = env_u16("LM36011_MAX_TORCH_MA", 376);
+
+    // the shortest time-out covering the requested one
+    let timeout = FLASH_TIMEOUTS_MS
+        .into_iter()
+        .find(|ms| *ms >= timeout_ms as u32)
+        .unwrap_or_else(|| panic!("LM36011_DEFAULT_TIMEOUT_MS={timeout_ms} is above 1600 ms"));
+
+    let preset = format!(
+        "/// The board preset baked in from the `LM36011_*` build environment variables.\n\
+         pub const BOARD_DEFAULT: BoardPreset = BoardPreset {{\n    \
+             torch_ma: {torch_ma},\n    \
+             flash_ma: {flash_ma},\n    \
+             timeout: FlashTimeout::Ms{timeout},\n    \
+             max_flash_ma: {max_flash_ma},\n    \
+             max_torch_ma: {max_torch_ma},\n\
+         }};\n"
+    );
+    fs::write(out.join("board_default.rs"), preset).unwrap();
+}
+
+/// Copies the selected example's `memory.x` next to the build output.
+fn example_memory(out: &std::path::Path) {
+    let memory_x = if env::var_os("CARGO_FEATURE_RP2040_EXAMPLES").is_some()
+        || env::var_os("CARGO_FEATURE_EMBASSY_EXAMPLES").is_some()
+        || env::var_os("CARGO_FEATURE_RTIC_EXAMPLES").is_some()
+    {
+        "examples/rp2040/memory.x"
+    } else if env::var_os("CARGO_FEATURE_STM32F4_EXAMPLES").is_some() {
+        "examples/stm32f4/memory.x"
+    } else {
+        return;
+    };
+    println!("cargo:rerun-if-changed={memory_x}");
+
+    // cortex-m-rt's link.x includes memory.x from the linker search path
+    fs::copy(memory_x, out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rustc-link-arg-examples=--nmagic");
+    println!("cargo:rustc-link-arg-examples=-Tlink.x");
+}
@@ -0,0 +1,67 @@
+//! Fires a 30 ms, 500 mA flash from the STROBE pin every 500 ms, from an Embassy task.
+//!
+//! The async driver arms the flash over I2C without blocking the executor, and the flash itself
+//! is timed by the STROBE pin alone: firing and ending it are GPIO writes with no bus
+//! transaction in between, so its start and length follow the `Ticker` and `Timer` deadlines.
+//! The strobe input is level triggered, with the 200 ms hardware time-out as a backstop.
+//!
+//! Wiring on a Raspberry Pi Pico: GPIO4 - SDA, GPIO5 - SCL, GPIO6 - STROBE.
+//!
+//! `cargo build --example embassy_strobe_flash --features embassy-examples --target thumbv6m-none-eabi`
+
+#![no_std]
+#![no_main]
+
+use embassy_executor::Spawner;
+use embassy_rp::bind_interrupts;
+use embassy_rp::gpio::{Level, Output};
+use embassy_rp::i2c::{self, I2c, InterruptHandler};
+use embassy_rp::peripherals::I2C0;
+use embassy_time::{Duration, Ticker, Timer};
+use lm36011::asynch::LM36011Async;
+use lm36011::eh1::Pin;
+use lm36011::{FlashTimeout, TriggerMode};
+use panic_halt as _;
+
+bind_interrupts!(struct Irqs {
+    I2C0_IRQ => InterruptHandler<I2C0>;
+});
+
+/// The async I2C bus the LM36011 is connected to.
+type Bus = I2c<'static, I2C0, i2c::Async>;
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_rp::init(Default::default());
+
+    let mut config = i2c::Config::default();
+    config.frequency = 400_000;
+    let i2c = I2c::new_async(p.I2C0, p.PIN_5, p.PIN_4, Irqs, config);
+    let strobe = Output::new(p.PIN_6, Level::Low);
+
+    spawner
+        .spawn(flash_task(LM36011Async::new(i2c), strobe))
+        .unwrap();
+}
+
+#[embassy_executor::task]
+async fn flash_task(mut flash: LM36011Async<Bus>, strobe: Output<'static>) {
+    assert!(flash.verify_device_id().await.unwrap());
+    flash.set_flash_current_ma(500).await.unwrap();
+    flash.set_flash_timeout(FlashTimeout::Ms200).await.unwrap();
+
+    let mut trigger = TriggerMode::HardwareStrobe {
+        pin: Pin::new(strobe),
+    };
+    let mut ticker = Ticker::every(Duration::from_millis(500));
+
+    loop {
+        // the device drops back to standby after each pulse, so re-arm first
+        flash.apply(|d| d.arm_flash(&mut trigger)).await.unwrap();
+
+        ticker.next().await;
+        flash.apply(|d| d.fire_flash(&mut trigger)).await.unwrap();
+        Timer::after(Duration::from_millis(30)).await;
+        flash.apply(|d| d.end_flash(&mut trigger)).await.unwrap();
+    }
+}
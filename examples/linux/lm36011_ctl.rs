@@ -0,0 +1,139 @@
+//! `lm36011-ctl`, a bring-up tool for an LM36011 on a Linux I2C bus, e.g. a Raspberry Pi.
+//!
+//! ```text
+//! lm36011-ctl [--bus /dev/i2c-1] <command>
+//!
+//! commands:
+//!   status                 mode, currents, time-out and pending faults
+//!   torch <ma>             turns the torch on at <ma>, `torch 0` turns it off
+//!   flash <ma> <timeout>   fires one flash at <ma>, ended by the hardware after <timeout> ms
+//!   reset                  software reset
+//!   dump                   raw register values
+//! ```
+//!
+//! `cargo run --example lm36011-ctl --features linux-examples -- --bus /dev/i2c-1 status`
+
+use std::error::Error;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+use linux_embedded_hal::I2cdev;
+use lm36011::{FlashTimeout, Register, LM36011};
+
+const USAGE: &str =
+    "usage: lm36011-ctl [--bus <device>] status | torch <ma> | flash <ma> <timeout> | reset | dump";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (bus, command) = match args.as_slice() {
+        [flag, bus, command @ ..] if flag == "--bus" => (bus.as_str(), command),
+        command => ("/dev/i2c-1", command),
+    };
+
+    match run(bus, command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("lm36011-ctl: {}", error);
+            if let Some(source) = error.source() {
+                eprintln!("  caused by: {}", source);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// A parsed subcommand.
+enum Command {
+    Status,
+    Torch { ma: u16 },
+    Flash { ma: u16, timeout_ms: u32 },
+    Reset,
+    Dump,
+}
+
+impl Command {
+    fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        Ok(match args.as_slice() {
+            ["status"] => Command::Status,
+            ["torch", ma] => Command::Torch { ma: ma.parse()? },
+            ["flash", ma, timeout] => Command::Flash {
+                ma: ma.parse()?,
+                timeout_ms: timeout.parse()?,
+            },
+            ["reset"] => Command::Reset,
+            ["dump"] => Command::Dump,
+            _ => return Err(USAGE.into()),
+        })
+    }
+}
+
+fn run(bus: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let command = Command::parse(args)?;
+    let mut driver = LM36011::new(I2cdev::new(bus)?);
+
+    match command {
+        Command::Status => status(&mut driver),
+        Command::Torch { ma } => torch(&mut driver, ma),
+        Command::Flash { ma, timeout_ms } => flash(&mut driver, ma, timeout_ms),
+        Command::Reset => Ok(driver.software_reset()?),
+        Command::Dump => dump(&mut driver),
+    }
+}
+
+fn status(driver: &mut LM36011<I2cdev>) -> Result<(), Box<dyn Error>> {
+    let config = driver.read_config()?;
+    println!("mode:          {:?}", config.mode);
+    println!("flash current: {} mA", config.flash_ma);
+    println!("torch current: {} mA", config.torch_ma);
+    println!("flash timeout: {} ms", config.flash_timeout.ms());
+    println!(
+        "IVFM:          {} at {} mV",
+        if config.ivfm_enabled { "on" } else { "off" },
+        config.ivfm_threshold.millivolts()
+    );
+    println!("strobe:        {:?}", config.strobe);
+    // the status read cleared the Flags Register, its flags are kept by the driver
+    println!("faults:        {}", driver.take_faults());
+    Ok(())
+}
+
+fn torch(driver: &mut LM36011<I2cdev>, ma: u16) -> Result<(), Box<dyn Error>> {
+    driver.read_status()?;
+    if ma == 0 {
+        driver.disable_output()?;
+    } else {
+        driver.set_torch_current_ma(ma)?;
+        driver.enable_torch()?;
+        println!("torch on at {} mA", driver.get_torch_current_ma());
+    }
+    Ok(())
+}
+
+fn flash(driver: &mut LM36011<I2cdev>, ma: u16, timeout_ms: u32) -> Result<(), Box<dyn Error>> {
+    let timeout = FlashTimeout::at_least(timeout_ms).ok_or("time-out above 1600 ms")?;
+
+    driver.read_status()?;
+    driver.set_flash_timeout(timeout)?;
+    driver.set_flash_current_ma(ma)?;
+    driver.trigger_flash()?;
+    thread::sleep(Duration::from_millis(timeout.ms().into()));
+
+    println!(
+        "fired {} mA for {} ms, faults: {}",
+        driver.get_flash_current_ma(),
+        timeout.ms(),
+        driver.read_faults()?
+    );
+    Ok(())
+}
+
+fn dump(driver: &mut LM36011<I2cdev>) -> Result<(), Box<dyn Error>> {
+    for address in 0x01..=0x06 {
+        let register = Register::from_address(address).ok_or("no register")?;
+        let value = driver.get_register(register)?;
+        println!("0x{:02x} {:<30} 0x{:02x}", address, register, value);
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! Blinks the LED in torch mode at 1 Hz.
+//!
+//! `cargo build --example rp2040_blinky_torch --features rp2040-examples --target thumbv6m-none-eabi`
+
+#![no_std]
+#![no_main]
+
+mod board;
+
+use lm36011::{EnableRegisterFlags, LedTorchBrightnessFlags, LM36011};
+use panic_halt as _;
+use rp2040_hal::entry;
+
+#[entry]
+fn main() -> ! {
+    let mut board = board::init();
+    let mut driver = LM36011::new(board.i2c);
+
+    driver.torch_brightness_flags = LedTorchBrightnessFlags::TORCH_64MA;
+
+    loop {
+        driver.enable_flags.insert(EnableRegisterFlags::MODE_TORCH);
+        driver.write_status().unwrap();
+        board.delay.delay_ms(500);
+
+        driver.enable_flags.remove(EnableRegisterFlags::MODE_MASK);
+        driver.write_status().unwrap();
+        board.delay.delay_ms(500);
+    }
+}
@@ -0,0 +1,90 @@
+//! Shared RP2040 (Raspberry Pi Pico) set-up for the LM36011 examples.
+//!
+//! Wiring:
+//! * GPIO4 - SDA
+//! * GPIO5 - SCL
+//! * GPIO6 - STROBE
+//! * GPIO25 - on-board LED, used as a status indicator
+
+// not every example uses every pin
+#![allow(dead_code)]
+
+use cortex_m::delay::Delay;
+use rp2040_hal::{
+    clocks::init_clocks_and_plls,
+    fugit::RateExtU32,
+    gpio::{
+        bank0::{Gpio25, Gpio4, Gpio5, Gpio6},
+        FunctionI2C, FunctionSioOutput, Pin, Pins, PullDown, PullUp,
+    },
+    pac, Clock, Sio, Watchdog, I2C,
+};
+
+/// Second stage bootloader for the W25Q080 flash on the Pico.
+#[link_section = ".boot2"]
+#[used]
+pub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
+
+/// Crystal frequency of the Pico.
+const XTAL_FREQ_HZ: u32 = 12_000_000;
+
+/// The I2C bus the LM36011 is connected to.
+pub type Bus = I2C<
+    pac::I2C0,
+    (
+        Pin<Gpio4, FunctionI2C, PullUp>,
+        Pin<Gpio5, FunctionI2C, PullUp>,
+    ),
+>;
+
+/// Peripherals used by the examples.
+pub struct Board {
+    pub i2c: Bus,
+    pub delay: Delay,
+    pub strobe: Pin<Gpio6, FunctionSioOutput, PullDown>,
+    pub led: Pin<Gpio25, FunctionSioOutput, PullDown>,
+}
+
+/// Brings up the clocks, the I2C bus at 400 kHz and the GPIOs.
+pub fn init() -> Board {
+    let mut pac = pac::Peripherals::take().unwrap();
+    let core = pac::CorePeripherals::take().unwrap();
+
+    let mut watchdog = Watchdog::new(pac.WATCHDOG);
+    let clocks = init_clocks_and_plls(
+        XTAL_FREQ_HZ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .ok()
+    .unwrap();
+    let delay = Delay::new(core.SYST, clocks.system_clock.freq().to_Hz());
+
+    let sio = Sio::new(pac.SIO);
+    let pins = Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let i2c = I2C::i2c0(
+        pac.I2C0,
+        pins.gpio4.reconfigure(),
+        pins.gpio5.reconfigure(),
+        400.kHz(),
+        &mut pac.RESETS,
+        &clocks.system_clock,
+    );
+
+    Board {
+        i2c,
+        delay,
+        strobe: pins.gpio6.into_push_pull_output(),
+        led: pins.gpio25.into_push_pull_output(),
+    }
+}
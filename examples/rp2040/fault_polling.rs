@@ -0,0 +1,47 @@
+//! Runs the torch and polls the fault flags every 100 ms.
+//!
+//! On a thermal shutdown, VLED short or UVLO fault the torch is switched off and the on-board
+//! LED is lit.
+//!
+//! `cargo build --example rp2040_fault_polling --features rp2040-examples --target thumbv6m-none-eabi`
+
+#![no_std]
+#![no_main]
+
+mod board;
+
+use embedded_hal::digital::v2::OutputPin;
+use lm36011::{EnableRegisterFlags, FlagRegisterFlags, LedTorchBrightnessFlags, LM36011};
+use panic_halt as _;
+use rp2040_hal::entry;
+
+/// Faults that require the torch to be switched off.
+const FAULTS: FlagRegisterFlags = FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT
+    .union(FlagRegisterFlags::VLED_SHORT_FAULT)
+    .union(FlagRegisterFlags::UVLO_FAULT);
+
+#[entry]
+fn main() -> ! {
+    let mut board = board::init();
+    let mut driver = LM36011::new(board.i2c);
+
+    driver.torch_brightness_flags = LedTorchBrightnessFlags::TORCH_188MA;
+    driver.enable_flags.insert(EnableRegisterFlags::MODE_TORCH);
+    driver.write_status().unwrap();
+
+    loop {
+        board.delay.delay_ms(100);
+
+        driver.read_status().unwrap();
+        if driver.flag_register_flags.intersects(FAULTS) {
+            driver.enable_flags.remove(EnableRegisterFlags::MODE_MASK);
+            driver.write_status().unwrap();
+            board.led.set_high().unwrap();
+            break;
+        }
+    }
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
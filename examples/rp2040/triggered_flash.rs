@@ -0,0 +1,41 @@
+//! Fires a 50 ms, 500 mA flash every two seconds from the STROBE pin.
+//!
+//! The strobe input is level triggered, so the flash lasts as long as STROBE is held high, with
+//! the 200 ms hardware time-out as a backstop.
+//!
+//! `cargo build --example rp2040_triggered_flash --features rp2040-examples --target thumbv6m-none-eabi`
+
+#![no_std]
+#![no_main]
+
+mod board;
+
+use embedded_hal::digital::v2::OutputPin;
+use lm36011::{ConfigurationRegisterFlags, EnableRegisterFlags, LM36011};
+use panic_halt as _;
+use rp2040_hal::entry;
+
+#[entry]
+fn main() -> ! {
+    let mut board = board::init();
+    let mut driver = LM36011::new(board.i2c);
+
+    driver.verify_device_id().unwrap();
+    driver.set_flash_current_ma(500).unwrap();
+    driver.config_flags =
+        ConfigurationRegisterFlags::TIMEOUT_200MS | ConfigurationRegisterFlags::TORCH_RAMP_1MS;
+    driver.enable_flags = EnableRegisterFlags::IVFM_ENABLE
+        | EnableRegisterFlags::STROBE_ENABLE
+        | EnableRegisterFlags::MODE_FLASH;
+
+    loop {
+        // the device drops back to standby after each pulse, so re-arm first
+        driver.write_status().unwrap();
+
+        board.strobe.set_high().unwrap();
+        board.delay.delay_ms(50);
+        board.strobe.set_low().unwrap();
+
+        board.delay.delay_ms(2000);
+    }
+}
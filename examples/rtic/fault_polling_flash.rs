@@ -0,0 +1,146 @@
+//! RTIC application sharing the driver between a fault-polling task and a flash interrupt.
+//!
+//! A rising edge on the camera frame-sync input fires a 500 mA flash from the GPIO interrupt,
+//! ended by the 120 ms hardware time-out. A software task polls the fault flags every 100 ms.
+//! With the fault cut-off enabled, a thermal shutdown or VLED short puts the device in standby
+//! and refuses further flashes; the on-board LED is then lit.
+//!
+//! Both tasks use the driver through `lm36011::rtic::SharedLM36011`, which holds the lock for a
+//! single register transaction, so the fault poll delays a frame-sync flash by one transfer at
+//! most.
+//!
+//! Wiring on a Raspberry Pi Pico: GPIO4 - SDA, GPIO5 - SCL, GPIO7 - camera frame sync.
+//!
+//! `cargo build --example rtic_fault_polling_flash --features rtic-examples --target thumbv6m-none-eabi`
+
+#![no_std]
+#![no_main]
+
+use panic_halt as _;
+
+/// Second stage bootloader for the W25Q080 flash on the Pico.
+#[link_section = ".boot2"]
+#[used]
+pub static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_W25Q080;
+
+rtic_monotonics::rp2040_timer_monotonic!(Mono);
+
+#[rtic::app(device = rp2040_hal::pac, dispatchers = [SW0_IRQ])]
+mod app {
+    use super::Mono;
+    use embedded_hal::digital::v2::OutputPin;
+    use lm36011::rtic::SharedLM36011;
+    use lm36011::{FlashTimeout, LM36011Error, LM36011};
+    use rp2040_hal::{
+        clocks::init_clocks_and_plls,
+        fugit::RateExtU32,
+        gpio::{
+            bank0::{Gpio25, Gpio4, Gpio5, Gpio7},
+            FunctionI2C, FunctionSioInput, FunctionSioOutput, Interrupt, Pin, Pins, PullDown,
+            PullUp,
+        },
+        pac, Sio, Watchdog, I2C,
+    };
+    use rtic_monotonics::rp2040::prelude::*;
+
+    /// Crystal frequency of the Pico.
+    const XTAL_FREQ_HZ: u32 = 12_000_000;
+
+    /// The I2C bus the LM36011 is connected to.
+    type Bus = I2C<
+        pac::I2C0,
+        (
+            Pin<Gpio4, FunctionI2C, PullUp>,
+            Pin<Gpio5, FunctionI2C, PullUp>,
+        ),
+    >;
+
+    #[shared]
+    struct Shared {
+        flash: LM36011<Bus>,
+    }
+
+    #[local]
+    struct Local {
+        frame_sync: Pin<Gpio7, FunctionSioInput, PullDown>,
+        led: Pin<Gpio25, FunctionSioOutput, PullDown>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        let mut pac = cx.device;
+        Mono::start(pac.TIMER, &pac.RESETS);
+
+        let mut watchdog = Watchdog::new(pac.WATCHDOG);
+        let clocks = init_clocks_and_plls(
+            XTAL_FREQ_HZ,
+            pac.XOSC,
+            pac.CLOCKS,
+            pac.PLL_SYS,
+            pac.PLL_USB,
+            &mut pac.RESETS,
+            &mut watchdog,
+        )
+        .ok()
+        .unwrap();
+
+        let sio = Sio::new(pac.SIO);
+        let pins = Pins::new(
+            pac.IO_BANK0,
+            pac.PADS_BANK0,
+            sio.gpio_bank0,
+            &mut pac.RESETS,
+        );
+        let i2c = I2C::i2c0(
+            pac.I2C0,
+            pins.gpio4.reconfigure(),
+            pins.gpio5.reconfigure(),
+            400.kHz(),
+            &mut pac.RESETS,
+            &clocks.system_clock,
+        );
+
+        let mut flash = LM36011::new(i2c);
+        flash.verify_device_id().unwrap();
+        flash.set_flash_current_ma(500).unwrap();
+        flash.set_flash_timeout(FlashTimeout::Ms120).unwrap();
+        flash.set_fault_cutoff(true);
+
+        let frame_sync = pins.gpio7.into_pull_down_input();
+        frame_sync.set_interrupt_enabled(Interrupt::EdgeHigh, true);
+
+        poll_faults::spawn().ok();
+
+        (
+            Shared { flash },
+            Local {
+                frame_sync,
+                led: pins.gpio25.into_push_pull_output(),
+            },
+        )
+    }
+
+    /// Fires a flash on each frame-sync edge, with a single Enable Register write.
+    #[task(binds = IO_IRQ_BANK0, shared = [flash], local = [frame_sync], priority = 2)]
+    fn frame_sync(mut cx: frame_sync::Context) {
+        cx.local.frame_sync.clear_interrupt(Interrupt::EdgeHigh);
+        // refused with `FaultShutdown` once the cut-off holds the output off
+        cx.shared.flash.trigger_flash().ok();
+    }
+
+    /// Polls the Flags Register every 100 ms.
+    #[task(shared = [flash], local = [led], priority = 1)]
+    async fn poll_faults(mut cx: poll_faults::Context) {
+        loop {
+            Mono::delay(100.millis()).await;
+
+            let faulted = match cx.shared.flash.read_faults() {
+                Ok(faults) => faults.is_fault(),
+                Err(error) => matches!(error, LM36011Error::FaultShutdown),
+            };
+            if faulted {
+                cx.local.led.set_high().unwrap();
+            }
+        }
+    }
+}
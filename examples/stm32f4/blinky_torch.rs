@@ -0,0 +1,31 @@
+//! Blinks the LED in torch mode at 1 Hz.
+//!
+//! `cargo build --example stm32f4_blinky_torch --features stm32f4-examples --target thumbv7em-none-eabihf`
+
+#![no_std]
+#![no_main]
+
+mod board;
+
+use cortex_m_rt::entry;
+use lm36011::{EnableRegisterFlags, LedTorchBrightnessFlags, LM36011};
+use panic_halt as _;
+use stm32f4xx_hal::prelude::*;
+
+#[entry]
+fn main() -> ! {
+    let mut board = board::init();
+    let mut driver = LM36011::new(board.i2c);
+
+    driver.torch_brightness_flags = LedTorchBrightnessFlags::TORCH_64MA;
+
+    loop {
+        driver.enable_flags.insert(EnableRegisterFlags::MODE_TORCH);
+        driver.write_status().unwrap();
+        board.delay.delay_ms(500);
+
+        driver.enable_flags.remove(EnableRegisterFlags::MODE_MASK);
+        driver.write_status().unwrap();
+        board.delay.delay_ms(500);
+    }
+}
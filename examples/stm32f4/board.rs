@@ -0,0 +1,57 @@
+//! Shared STM32F411 ("Black Pill") set-up for the LM36011 examples.
+//!
+//! Wiring:
+//! * PB7 - SDA
+//! * PB6 - SCL
+//! * PB0 - STROBE
+//! * PC13 - on-board LED (active low), used as a status indicator
+
+// not every example uses every pin
+#![allow(dead_code)]
+
+use stm32f4xx_hal::{
+    gpio::{Output, PB0, PC13},
+    i2c::{DutyCycle, I2c, Mode},
+    pac,
+    prelude::*,
+    rcc::Config,
+    timer::SysDelay,
+};
+
+/// Peripherals used by the examples.
+pub struct Board {
+    pub i2c: I2c<pac::I2C1>,
+    pub delay: SysDelay,
+    pub strobe: PB0<Output>,
+    pub led: PC13<Output>,
+}
+
+/// Brings up the clocks, the I2C bus at 400 kHz and the GPIOs.
+pub fn init() -> Board {
+    init_with_core(cortex_m::Peripherals::take().unwrap())
+}
+
+/// Like [`init`], for examples that configure other core peripherals first.
+pub fn init_with_core(cp: cortex_m::Peripherals) -> Board {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut rcc = dp.RCC.freeze(Config::hsi().sysclk(48.MHz()));
+    let delay = cp.SYST.delay(&rcc.clocks);
+
+    let gpiob = dp.GPIOB.split(&mut rcc);
+    let gpioc = dp.GPIOC.split(&mut rcc);
+
+    let i2c = I2c::new(
+        dp.I2C1,
+        (gpiob.pb6, gpiob.pb7),
+        Mode::fast(400.kHz(), DutyCycle::Ratio2to1),
+        &mut rcc,
+    );
+
+    Board {
+        i2c,
+        delay,
+        strobe: gpiob.pb0.into_push_pull_output(),
+        led: gpioc.pc13.into_push_pull_output_in_state(true.into()),
+    }
+}
@@ -0,0 +1,48 @@
+//! Runs the torch and polls the fault flags every 100 ms.
+//!
+//! On a thermal shutdown, VLED short or UVLO fault the torch is switched off and the on-board
+//! LED is lit.
+//!
+//! `cargo build --example stm32f4_fault_polling --features stm32f4-examples --target thumbv7em-none-eabihf`
+
+#![no_std]
+#![no_main]
+
+mod board;
+
+use cortex_m_rt::entry;
+use lm36011::{EnableRegisterFlags, FlagRegisterFlags, LedTorchBrightnessFlags, LM36011};
+use panic_halt as _;
+use stm32f4xx_hal::prelude::*;
+
+/// Faults that require the torch to be switched off.
+const FAULTS: FlagRegisterFlags = FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT
+    .union(FlagRegisterFlags::VLED_SHORT_FAULT)
+    .union(FlagRegisterFlags::UVLO_FAULT);
+
+#[entry]
+fn main() -> ! {
+    let mut board = board::init();
+    let mut driver = LM36011::new(board.i2c);
+
+    driver.torch_brightness_flags = LedTorchBrightnessFlags::TORCH_188MA;
+    driver.enable_flags.insert(EnableRegisterFlags::MODE_TORCH);
+    driver.write_status().unwrap();
+
+    loop {
+        board.delay.delay_ms(100);
+
+        driver.read_status().unwrap();
+        if driver.flag_register_flags.intersects(FAULTS) {
+            driver.enable_flags.remove(EnableRegisterFlags::MODE_MASK);
+            driver.write_status().unwrap();
+            // the on-board LED is active low
+            board.led.set_low();
+            break;
+        }
+    }
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -0,0 +1,83 @@
+//! Measures the trigger latency of the I2C fire paths with the DWT cycle counter.
+//!
+//! Fires a 1 ms, 200 mA flash every 100 ms, alternating between `fire_flash` and
+//! `write_current_and_fire`, and keeps the minimum and maximum cycle counts of each path in
+//! `RESULTS`. Read them with a debugger (e.g. `probe-rs` or `gdb`: `print RESULTS`) to track
+//! latency regressions across releases. The on-board LED toggles after every batch of 100 flashes.
+//!
+//! `cargo build --example stm32f4_fire_latency --features "stm32f4-examples cycle-count" --target thumbv7em-none-eabihf`
+
+#![no_std]
+#![no_main]
+
+mod board;
+
+use cortex_m_rt::entry;
+use lm36011::{ConfigurationRegisterFlags, EnableRegisterFlags, TriggerMode, LM36011};
+use panic_halt as _;
+use stm32f4xx_hal::prelude::*;
+
+/// Minimum and maximum cycles of a fire path.
+#[derive(Clone, Copy)]
+struct Latency {
+    min: u32,
+    max: u32,
+}
+
+impl Latency {
+    const EMPTY: Self = Self {
+        min: u32::MAX,
+        max: 0,
+    };
+
+    fn record(&mut self, cycles: u32) {
+        self.min = self.min.min(cycles);
+        self.max = self.max.max(cycles);
+    }
+}
+
+/// `[fire_flash, write_current_and_fire]` latencies, inspected with a debugger.
+#[no_mangle]
+static mut RESULTS: [Latency; 2] = [Latency::EMPTY; 2];
+
+#[entry]
+fn main() -> ! {
+    let mut core = cortex_m::Peripherals::take().unwrap();
+    core.DCB.enable_trace();
+    core.DWT.enable_cycle_counter();
+
+    let mut board = board::init_with_core(core);
+    let mut driver = LM36011::new(board.i2c);
+
+    driver.verify_device_id().unwrap();
+    let code = lm36011::flash_code_for_ma(200).unwrap();
+    driver.set_flash_current_hex(code).unwrap();
+    driver.config_flags = ConfigurationRegisterFlags::TIMEOUT_40MS;
+    driver.enable_flags = EnableRegisterFlags::IVFM_ENABLE;
+    driver.write_status().unwrap();
+
+    let mut trigger: TriggerMode = TriggerMode::I2cOnly;
+    driver.arm_flash(&mut trigger).unwrap();
+
+    let mut count: u32 = 0;
+    loop {
+        let path = (count % 2) as usize;
+        if path == 0 {
+            driver.fire_flash(&mut trigger).unwrap();
+        } else {
+            driver.write_current_and_fire(code).unwrap();
+        }
+        let cycles = driver.last_fire_cycles().unwrap();
+        // SAFETY: single-threaded, RESULTS is only written here
+        unsafe { (*core::ptr::addr_of_mut!(RESULTS))[path].record(cycles) };
+
+        board.delay.delay_ms(1);
+        driver.end_flash(&mut trigger).unwrap();
+
+        count += 1;
+        if count % 100 == 0 {
+            board.led.toggle();
+        }
+        board.delay.delay_ms(100);
+    }
+}
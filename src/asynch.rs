@@ -0,0 +1,481 @@
+//! `async` support built on `embedded-hal-async`.
+//!
+//! Lets externally synchronized flash setups (camera frame sync, sensors, other lights) sleep
+//! until a trigger edge instead of busy-polling a GPIO.
+//!
+//! [`LM36011Async`] drives the LM36011 over an async [`I2c`] bus, so Embassy-based firmware
+//! does not block the executor during I2C transactions. It runs the logic of the blocking
+//! driver against a [`Shadow`] of the device registers and performs the resulting bus
+//! transactions asynchronously, so both drivers behave the same.
+
+use crate::config::Lm36011Config;
+use crate::persist::Settings;
+use crate::transcript::Transaction;
+use crate::{
+    Brightness, BusDevice, DeviceInfo, Faults, FlashTimeout, IvfmThreshold, LM36011Error,
+    OperatingMode, Register, StrobeConfig, TorchRamp, LM36011, LM36011_I2C_ADDRESS,
+};
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::I2c;
+
+/// Largest number of writes a single [`LM36011Async::apply`] operation may issue.
+const MAX_QUEUED_WRITES: usize = 8;
+
+/// Number of the last register, the Device ID Register.
+const REGISTER_COUNT: usize = 7;
+
+/// The trigger edge awaited by [`LM36011::await_trigger_and_fire`] and
+/// [`LM36011Async::await_trigger_and_fire`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerEdge {
+    Rising,
+    Falling,
+    Any,
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Waits for an edge on an external trigger input, then fires a flash over I2C.
+    ///
+    /// The Enable Register value is checked before waiting, so that a single register write
+    /// follows the edge. An interlock unlock is only used once the edge has arrived; it is kept
+    /// if waiting fails or the future is dropped. The flash uses the cached current and ends with the hardware time-out
+    /// (or [`LM36011::end_flash`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `trigger` - The trigger input, e.g. a camera frame-sync pin.
+    /// * `edge` - The edge that fires the flash.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once the flash has been started.
+    /// * `Err(LM36011Error::StrobePinError)` if waiting on the trigger input failed.
+    /// * `Err(LM36011Error::I2CError(E))` if the flash could not be started.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// loop {
+    ///     driver.await_trigger_and_fire(&mut frame_sync, TriggerEdge::Rising).await?;
+    /// }
+    /// ```
+    pub async fn await_trigger_and_fire<W: Wait>(
+        &mut self,
+        trigger: &mut W,
+        edge: TriggerEdge,
+    ) -> Result<(), LM36011Error<E>> {
+        self.check_fire_enable(self.device_flash_code)?;
+        wait_for_edge(trigger, edge).await?;
+        let enable = self.fire_enable(self.device_flash_code)?;
+
+        #[cfg(feature = "cycle-count")]
+        let start = crate::cycle_count();
+
+        // no trace record between the edge and the write
+        self.write_fire_enable(enable)?;
+
+        #[cfg(feature = "cycle-count")]
+        {
+            self.fire_cycles = Some(crate::cycle_count().wrapping_sub(start));
+        }
+
+        Ok(())
+    }
+}
+
+/// Waits for `edge` on `trigger`.
+async fn wait_for_edge<W: Wait, E>(
+    trigger: &mut W,
+    edge: TriggerEdge,
+) -> Result<(), LM36011Error<E>> {
+    match edge {
+        TriggerEdge::Rising => trigger.wait_for_rising_edge().await,
+        TriggerEdge::Falling => trigger.wait_for_falling_edge().await,
+        TriggerEdge::Any => trigger.wait_for_any_edge().await,
+    }
+    .map_err(|_| LM36011Error::StrobePinError)
+}
+
+/// Error of the [`Shadow`] transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowError {
+    /// The operation issued more writes than can be queued.
+    QueueFull,
+}
+
+/// Transport of the blocking driver inside [`LM36011Async`].
+///
+/// Reads are served from the registers last fetched from or written to the device, writes are
+/// queued until `LM36011Async` performs them on the async bus. Like the device, the Flags
+/// Register clears once read.
+pub struct Shadow {
+    registers: [u8; REGISTER_COUNT],
+    writes: [Option<Transaction>; MAX_QUEUED_WRITES],
+    queued: usize,
+}
+
+impl Shadow {
+    fn new() -> Self {
+        Self {
+            registers: [0; REGISTER_COUNT],
+            writes: [None; MAX_QUEUED_WRITES],
+            queued: 0,
+        }
+    }
+
+    /// Stores registers read from the device, starting at `first`.
+    fn load(&mut self, first: Register, data: &[u8]) {
+        for (offset, value) in data.iter().enumerate() {
+            if let Some(register) = self.registers.get_mut(first as usize + offset) {
+                *register = *value;
+            }
+        }
+    }
+
+    /// Returns and clears the queued writes, oldest first.
+    fn take_writes(&mut self) -> impl Iterator<Item = Transaction> {
+        let writes = core::mem::replace(&mut self.writes, [None; MAX_QUEUED_WRITES]);
+        self.queued = 0;
+        writes.into_iter().flatten()
+    }
+}
+
+impl BusDevice for Shadow {
+    type Error = ShadowError;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ShadowError> {
+        let slot = self
+            .writes
+            .get_mut(self.queued)
+            .ok_or(ShadowError::QueueFull)?;
+        *slot = Some(Transaction::write(address, bytes).ok_or(ShadowError::QueueFull)?);
+        self.queued += 1;
+
+        if let Some((pointer, data)) = bytes.split_first() {
+            for (offset, value) in data.iter().enumerate() {
+                if let Some(register) = self.registers.get_mut(*pointer as usize + offset) {
+                    *register = *value;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_read(
+        &mut self,
+        _address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), ShadowError> {
+        let pointer = bytes.first().copied().unwrap_or(0) as usize;
+        for (offset, value) in buffer.iter_mut().enumerate() {
+            let register = pointer + offset;
+            *value = self.registers.get(register).copied().unwrap_or(0);
+            if register == Register::FlagsRegister as usize {
+                self.registers[register] = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Async driver for the LM36011 on an `embedded-hal-async` I2C bus.
+///
+/// The register cache and every setting live in a blocking [`LM36011`] (see
+/// [`LM36011Async::driver`]) whose transport is a [`Shadow`]. Every async method runs the
+/// blocking method of the same name and arguments: the registers it reads are fetched
+/// asynchronously first, and the writes it issues are performed afterwards. Blocking methods
+/// without an async counterpart can be run with [`LM36011Async::apply`].
+///
+/// # Example
+///
+/// ```ignore
+/// let mut driver = LM36011Async::new(i2c);
+/// driver.verify_device_id().await?;
+/// driver.set_torch_current_ma(100).await?;
+/// driver.apply(|d| d.set_torch_ramp(TorchRamp::Ms1)).await?;
+/// driver.enable_torch().await?;
+/// ```
+pub struct LM36011Async<I2C, const MAX_FLASH_MA: u16 = 1500> {
+    i2c: I2C,
+    driver: LM36011<Shadow, MAX_FLASH_MA>,
+}
+
+impl<I2C> LM36011Async<I2C> {
+    /// Creates a driver for the device on `i2c`.
+    pub fn new(i2c: I2C) -> Self {
+        Self::new_limited(i2c)
+    }
+}
+
+impl<I2C, const MAX_FLASH_MA: u16> LM36011Async<I2C, MAX_FLASH_MA> {
+    /// Creates a driver whose flash current is limited to `MAX_FLASH_MA`, see
+    /// [`LM36011::new_limited`].
+    pub fn new_limited(i2c: I2C) -> Self {
+        Self {
+            i2c,
+            driver: LM36011::new_limited(Shadow::new()),
+        }
+    }
+
+    /// Returns the blocking driver holding the cached registers and settings.
+    pub fn driver(&self) -> &LM36011<Shadow, MAX_FLASH_MA> {
+        &self.driver
+    }
+
+    /// Returns the blocking driver mutably, e.g. for [`LM36011::take_faults`] or to edit the
+    /// cached registers before [`LM36011Async::write_status`].
+    pub fn driver_mut(&mut self) -> &mut LM36011<Shadow, MAX_FLASH_MA> {
+        &mut self.driver
+    }
+
+    /// Releases the I2C bus.
+    pub fn free(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C: I2c, const MAX_FLASH_MA: u16> LM36011Async<I2C, MAX_FLASH_MA> {
+    /// Runs `operation` on the blocking driver, then performs the writes it issued, in order.
+    ///
+    /// Reads inside `operation` see the registers last fetched from or written to the device,
+    /// not the device itself; use the async read APIs (e.g. [`LM36011Async::read_status`])
+    /// first where fresh values matter. Operations that wait, such as
+    /// [`LM36011::flash_once`], would block and are not suited.
+    ///
+    /// The writes are performed even if `operation` fails after issuing some, so the device
+    /// matches the cache as with the blocking driver.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(T)` with the result of `operation`.
+    /// * `Err(LM36011Error::InvalidInput)` if `operation` issued more than 8 writes.
+    /// * `Err(LM36011Error::I2CError(E))` if a write failed; later writes are dropped.
+    /// * Any other error returned by `operation`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver
+    ///     .apply(|d| d.set_flash_timeout(FlashTimeout::Ms200))
+    ///     .await?;
+    /// ```
+    pub async fn apply<T>(
+        &mut self,
+        operation: impl FnOnce(
+            &mut LM36011<Shadow, MAX_FLASH_MA>,
+        ) -> Result<T, LM36011Error<ShadowError>>,
+    ) -> Result<T, LM36011Error<I2C::Error>> {
+        let result = operation(&mut self.driver);
+
+        for write in self.driver.i2c.take_writes() {
+            self.i2c
+                .write(write.address, write.written())
+                .await
+                .map_err(LM36011Error::I2CError)?;
+        }
+
+        result.map_err(from_shadow)
+    }
+
+    /// Reads `count` registers starting at `first` into the shadow.
+    async fn fetch(
+        &mut self,
+        first: Register,
+        count: usize,
+    ) -> Result<(), LM36011Error<I2C::Error>> {
+        let mut buffer = [0u8; REGISTER_COUNT];
+        let buffer = &mut buffer[..count];
+        self.i2c
+            .write_read(LM36011_I2C_ADDRESS, &[first as u8], buffer)
+            .await
+            .map_err(LM36011Error::I2CError)?;
+        self.driver.i2c.load(first, buffer);
+
+        Ok(())
+    }
+
+    /// Reads a register, see [`LM36011::get_register`].
+    pub async fn get_register(&mut self, reg: Register) -> Result<u8, LM36011Error<I2C::Error>> {
+        self.fetch(reg, 1).await?;
+        self.apply(|d| d.get_register(reg).map_err(LM36011Error::I2CError))
+            .await
+    }
+
+    /// Waits for an edge on an external trigger input, then fires a flash over the async bus,
+    /// see [`LM36011::await_trigger_and_fire`].
+    ///
+    /// The Enable Register value is checked before waiting, and written directly to the bus
+    /// after the edge rather than through [`LM36011Async::apply`]. As there, an interlock unlock
+    /// is kept if waiting fails or the future is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// loop {
+    ///     driver.await_trigger_and_fire(&mut frame_sync, TriggerEdge::Rising).await?;
+    /// }
+    /// ```
+    pub async fn await_trigger_and_fire<W: Wait>(
+        &mut self,
+        trigger: &mut W,
+        edge: TriggerEdge,
+    ) -> Result<(), LM36011Error<I2C::Error>> {
+        let code = self.driver.device_flash_code;
+        self.driver.check_fire_enable(code).map_err(from_shadow)?;
+        wait_for_edge(trigger, edge).await?;
+        let enable = self.driver.fire_enable(code).map_err(from_shadow)?;
+
+        #[cfg(feature = "cycle-count")]
+        let start = crate::cycle_count();
+
+        let bytes = [Register::EnableRegister as u8, enable.bits()];
+        self.i2c
+            .write(LM36011_I2C_ADDRESS, &bytes)
+            .await
+            .map_err(LM36011Error::I2CError)?;
+        self.driver.i2c.load(Register::EnableRegister, &bytes[1..]);
+        self.driver.fired(enable);
+
+        #[cfg(feature = "cycle-count")]
+        {
+            self.driver.fire_cycles = Some(crate::cycle_count().wrapping_sub(start));
+        }
+
+        Ok(())
+    }
+
+    /// Samples the Flags Register `iterations` times, `interval_ms` apart, see
+    /// [`LM36011::monitor_faults`].
+    ///
+    /// Both the delay and the one-byte reads of register 0x05 are awaited, so other tasks run
+    /// between and during the samples.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.enable_torch().await?;
+    /// // watch the torch for 10 s
+    /// let faults = driver.monitor_faults(&mut delay, 100, 100).await?;
+    /// driver.disable_output().await?;
+    /// ```
+    pub async fn monitor_faults<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        interval_ms: u16,
+        iterations: u16,
+    ) -> Result<Faults, LM36011Error<I2C::Error>> {
+        for _ in 0..iterations {
+            delay.delay_ms(interval_ms as u32).await;
+            self.fetch(Register::FlagsRegister, 1).await?;
+            self.apply(|d| {
+                d.get_register(Register::FlagsRegister)
+                    .map_err(LM36011Error::I2CError)?;
+                d.report_cutoff()
+            })
+            .await?;
+        }
+
+        Ok(self.driver.take_faults())
+    }
+
+    /// Fires a flash and reports the faults seen once it has timed out, see
+    /// [`LM36011::flash_and_check`].
+    pub async fn flash_and_check<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<Faults, LM36011Error<I2C::Error>> {
+        self.clear_faults().await?;
+        self.trigger_flash().await?;
+        let wait_ms = self.driver.get_flash_timeout().ms() + crate::FLASH_END_MARGIN_MS;
+        delay.delay_ms(wait_ms as u32).await;
+
+        self.read_faults().await
+    }
+}
+
+/// Generates the async counterparts of blocking driver methods, each running the blocking
+/// method with [`LM36011Async::apply`] after fetching the registers it reads.
+///
+/// Listing the methods here, rather than writing the wrappers by hand, keeps the names,
+/// arguments and results of both drivers identical.
+macro_rules! async_api {
+    ($(
+        $([$first:ident; $count:literal])?
+        fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;
+    )*) => {
+        impl<I2C: I2c, const MAX_FLASH_MA: u16> LM36011Async<I2C, MAX_FLASH_MA> {
+            $(
+                #[doc = concat!("See [`LM36011::", stringify!($name), "`].")]
+                pub async fn $name(
+                    &mut self,
+                    $($arg: $ty),*
+                ) -> Result<$ret, LM36011Error<I2C::Error>> {
+                    $(self.fetch(Register::$first, $count).await?;)?
+                    self.apply(move |d| d.$name($($arg),*)).await
+                }
+            )*
+        }
+    };
+}
+
+async_api! {
+    // reads, all registers
+    [EnableRegister; 6] fn read_status() -> ();
+    [EnableRegister; 6] fn verify_device_id() -> bool;
+    [EnableRegister; 6] fn read_config() -> Lm36011Config;
+    // reads, single registers
+    [EnableRegister; 1] fn get_mode() -> OperatingMode;
+    [DeviceIdRegister; 1] fn probe() -> DeviceInfo;
+    [FlagsRegister; 1] fn read_faults() -> Faults;
+    [FlagsRegister; 1] fn has_fault() -> bool;
+    [FlagsRegister; 1] fn clear_faults() -> ();
+    [FlagsRegister; 1] fn recover_from_uvlo(supply_mv: u16) -> bool;
+    [LEDFlashBrightnessRegister; 1] fn set_thermal_scaleback(enabled: bool) -> ();
+    // writes
+    fn set_register(reg: Register, data: u8) -> ();
+    fn write_status() -> ();
+    fn software_reset() -> ();
+    fn apply_settings(settings: &Settings) -> ();
+    fn apply_config(config: &Lm36011Config) -> ();
+    fn set_flash_current_hex(current: u8) -> ();
+    fn set_flash_current_ma(ma: u16) -> ();
+    fn set_torch_current_ma(ma: u16) -> ();
+    fn set_torch_level(level: u8) -> ();
+    fn set_flash_brightness_percent(percent: u8) -> ();
+    fn set_torch_brightness_percent(percent: u8) -> ();
+    fn set_flash(brightness: Brightness) -> ();
+    fn set_torch(brightness: Brightness) -> ();
+    fn set_flash_timeout(timeout: FlashTimeout) -> ();
+    fn set_ivfm_threshold(threshold: IvfmThreshold) -> ();
+    fn set_torch_ramp(ramp: TorchRamp) -> ();
+    fn configure_strobe(strobe: StrobeConfig) -> ();
+    fn enable_ivfm() -> ();
+    fn disable_ivfm() -> ();
+    fn set_mode(mode: OperatingMode) -> ();
+    fn enable_torch() -> ();
+    fn enable_ir_drive(strobe: bool) -> ();
+    fn disable_output() -> ();
+    fn trigger_flash() -> ();
+}
+
+/// Converts an error of the blocking driver inside [`LM36011Async`].
+fn from_shadow<E>(error: LM36011Error<ShadowError>) -> LM36011Error<E> {
+    match error {
+        LM36011Error::I2CError(ShadowError::QueueFull) => LM36011Error::InvalidInput,
+        LM36011Error::InvalidInput => LM36011Error::InvalidInput,
+        LM36011Error::CurrentOutOfRange => LM36011Error::CurrentOutOfRange,
+        LM36011Error::DeviceIDError => LM36011Error::DeviceIDError,
+        LM36011Error::StrobePinError => LM36011Error::StrobePinError,
+        LM36011Error::TemperatureSensorError => LM36011Error::TemperatureSensorError,
+        LM36011Error::TimeoutOutOfRange => LM36011Error::TimeoutOutOfRange,
+        LM36011Error::InterlockEngaged => LM36011Error::InterlockEngaged,
+        LM36011Error::CurrentSenseError => LM36011Error::CurrentSenseError,
+        LM36011Error::StandbyVerifyError => LM36011Error::StandbyVerifyError,
+        LM36011Error::FaultShutdown => LM36011Error::FaultShutdown,
+    }
+}
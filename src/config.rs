@@ -0,0 +1,450 @@
+//! High-level device configuration.
+//!
+//! [`Lm36011Config`] describes the whole device in application terms (mode, strobe, IVFM,
+//! time-out, ramp and currents in mA). [`LM36011::apply_config`] translates it to register
+//! values and writes them in one transaction, and [`LM36011::read_config`] translates the
+//! registers back, so the register bitflags never have to be edited by hand.
+//!
+//! [`LM36011Builder`] assembles a configuration step by step and writes it once, returning a
+//! driver that is ready to use. [`LM36011::configure`] does the same for a driver already in
+//! use, starting from its cached registers.
+//!
+//! [`Lm36011Config::encode`] packs a configuration into a few versioned bytes for provisioning
+//! blobs; with the `serde` feature this is also its `serde` representation.
+
+use crate::{
+    persist::Settings, BusDevice, ConfigurationRegisterFlags, EnableRegisterFlags, FlashTimeout,
+    IvfmThreshold, LM36011Error, LedFlashBrightnessFlags, LedTorchBrightnessFlags, OperatingMode,
+    RangePolicy, StrobeConfig, TorchRamp, FLASH_SCALE, LM36011, MAX_BRIGHTNESS_CODE, TORCH_SCALE,
+};
+
+/// Version of the encoded configuration layout.
+const ENCODING_VERSION: u8 = 1;
+
+/// Thermal scale-back bit of the encoded Enable Register byte, one of its reserved bits.
+const THERMAL_SCALEBACK_BIT: u8 = 0b1000_0000;
+
+/// Complete LM36011 configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Lm36011Config {
+    /// Operating mode.
+    pub mode: OperatingMode,
+    /// STROBE input settings.
+    pub strobe: StrobeConfig,
+    /// Whether input voltage flash monitoring is enabled.
+    pub ivfm_enabled: bool,
+    /// IVFM threshold.
+    pub ivfm_threshold: IvfmThreshold,
+    /// Hardware flash time-out.
+    pub flash_timeout: FlashTimeout,
+    /// Torch current ramp.
+    pub torch_ramp: TorchRamp,
+    /// Flash current in mA, rounded down to a brightness step when applied.
+    pub flash_ma: u16,
+    /// Torch (and IR) current in mA, rounded down to a brightness step when applied.
+    pub torch_ma: u16,
+    /// Whether the thermal current scale-back is enabled.
+    pub thermal_scaleback: bool,
+}
+
+impl Lm36011Config {
+    /// Length of an encoded configuration in bytes.
+    pub const ENCODED_LEN: usize = 7;
+
+    /// Encodes the configuration for a provisioning blob.
+    ///
+    /// The layout is stable within a version, and [`Lm36011Config::decode`] reproduces the
+    /// configuration exactly, currents included:
+    ///
+    /// * byte 0: layout version, currently 1
+    /// * byte 1: the mode, strobe and IVFM enable bits in their Enable Register positions, and
+    ///   the thermal scale-back in bit 7
+    /// * byte 2: the IVFM threshold, time-out and torch ramp bits, as in the Configuration
+    ///   Register
+    /// * bytes 3-4 and 5-6: the flash and torch currents in mA, little-endian
+    ///
+    /// With the `serde` feature, the configuration serializes as these bytes, so e.g. `postcard`
+    /// stores it in exactly [`Lm36011Config::ENCODED_LEN`] bytes.
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut enable = self.mode.bits() | self.strobe.bits();
+        if self.ivfm_enabled {
+            enable |= EnableRegisterFlags::IVFM_ENABLE.bits();
+        }
+        if self.thermal_scaleback {
+            enable |= THERMAL_SCALEBACK_BIT;
+        }
+        let config =
+            self.ivfm_threshold.bits() | self.flash_timeout.bits() | self.torch_ramp.bits();
+        let [flash_lo, flash_hi] = self.flash_ma.to_le_bytes();
+        let [torch_lo, torch_hi] = self.torch_ma.to_le_bytes();
+
+        [
+            ENCODING_VERSION,
+            enable,
+            config,
+            flash_lo,
+            flash_hi,
+            torch_lo,
+            torch_hi,
+        ]
+    }
+
+    /// Decodes bytes produced by [`Lm36011Config::encode`].
+    ///
+    /// Returns `None` if the bytes are too short, have a different layout version or set unused
+    /// bits.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::config::Lm36011Config;
+    ///
+    /// assert_eq!(Lm36011Config::decode(&[2, 0, 0, 0, 0, 0, 0]), None);
+    /// ```
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let &[version, enable, config, flash_lo, flash_hi, torch_lo, torch_hi] =
+            bytes.get(..Self::ENCODED_LEN)?
+        else {
+            return None;
+        };
+
+        let unused = EnableRegisterFlags::ENABLE_REGISTER_RFU.bits() & !THERMAL_SCALEBACK_BIT;
+        if version != ENCODING_VERSION || enable & unused != 0 {
+            return None;
+        }
+
+        Some(Self {
+            mode: OperatingMode::from_bits(enable),
+            strobe: StrobeConfig::from_bits(enable),
+            ivfm_enabled: enable & EnableRegisterFlags::IVFM_ENABLE.bits() != 0,
+            ivfm_threshold: IvfmThreshold::from_bits(config),
+            flash_timeout: FlashTimeout::from_bits(config),
+            torch_ramp: TorchRamp::from_bits(config),
+            flash_ma: u16::from_le_bytes([flash_lo, flash_hi]),
+            torch_ma: u16::from_le_bytes([torch_lo, torch_hi]),
+            thermal_scaleback: enable & THERMAL_SCALEBACK_BIT != 0,
+        })
+    }
+
+    /// Decodes a register snapshot.
+    ///
+    /// Currents are rounded up to the next mA, so applying the result programs the same
+    /// brightness codes again.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let flash_code = settings.flash_brightness.bits() & MAX_BRIGHTNESS_CODE;
+        let torch_code = settings.torch_brightness.bits() & MAX_BRIGHTNESS_CODE;
+
+        Self {
+            mode: OperatingMode::from_bits(settings.enable.bits()),
+            strobe: StrobeConfig::from_bits(settings.enable.bits()),
+            ivfm_enabled: settings.enable.contains(EnableRegisterFlags::IVFM_ENABLE),
+            ivfm_threshold: IvfmThreshold::from_bits(settings.config.bits()),
+            flash_timeout: FlashTimeout::from_bits(settings.config.bits()),
+            torch_ramp: TorchRamp::from_bits(settings.config.bits()),
+            flash_ma: FLASH_SCALE.ua(flash_code).div_ceil(1000) as u16,
+            torch_ma: TORCH_SCALE.ua(torch_code).div_ceil(1000) as u16,
+            thermal_scaleback: settings
+                .flash_brightness
+                .contains(LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Lm36011Config {
+    /// Serializes the configuration as the bytes of [`Lm36011Config::encode`].
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.encode().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Lm36011Config {
+    /// Deserializes the bytes of [`Lm36011Config::encode`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = <[u8; Self::ENCODED_LEN]>::deserialize(deserializer)?;
+        Self::decode(&bytes)
+            .ok_or_else(|| serde::de::Error::custom("invalid LM36011 configuration encoding"))
+    }
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Creates a driver, checks the device and programs `config`, see
+    /// [`LM36011::new_limited`].
+    ///
+    /// The device ID is verified before anything is written, so a wrong or missing part is
+    /// reported instead of being configured.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LM36011)` with the device programmed and the cache matching it.
+    /// * `Err(LM36011Error::DeviceIDError)` if the device ID does not match.
+    /// * Any error of [`LM36011::apply_config`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver: LM36011<_> = LM36011::with_config(i2c, &LIGHT_CONFIG)?;
+    /// ```
+    pub fn with_config(i2c: I2C, config: &Lm36011Config) -> Result<Self, LM36011Error<E>> {
+        let mut driver = Self::new_limited(i2c);
+        driver.verify_device_id()?;
+        driver.apply_config(config)?;
+
+        Ok(driver)
+    }
+
+    /// Writes a complete configuration to the device and updates the cached registers.
+    ///
+    /// The currents are converted like [`LM36011::set_flash_current_ma`] and
+    /// [`LM36011::set_torch_current_ma`], so out-of-range currents follow the range policy.
+    /// Everything is checked before the registers are written in a single transaction; see
+    /// [`LM36011::apply_settings`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut config = driver.read_config()?;
+    /// config.torch_ma = 150;
+    /// config.mode = OperatingMode::Torch;
+    /// driver.apply_config(&config)?;
+    /// ```
+    pub fn apply_config(&mut self, config: &Lm36011Config) -> Result<(), LM36011Error<E>> {
+        let flash_code = self.limit_ma(config.flash_ma, self.flash_scale())?;
+        let torch_code = self.limit_ma(config.torch_ma, self.torch_scale())?;
+
+        let mut enable =
+            EnableRegisterFlags::from_bits_truncate(config.mode.bits() | config.strobe.bits());
+        enable.set(EnableRegisterFlags::IVFM_ENABLE, config.ivfm_enabled);
+
+        let mut flash_brightness = LedFlashBrightnessFlags::from_bits_truncate(flash_code);
+        flash_brightness.set(
+            LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED,
+            config.thermal_scaleback,
+        );
+
+        self.apply_settings(&Settings {
+            enable,
+            config: ConfigurationRegisterFlags::from_bits_truncate(
+                config.ivfm_threshold.bits()
+                    | config.flash_timeout.bits()
+                    | config.torch_ramp.bits(),
+            ),
+            flash_brightness,
+            torch_brightness: LedTorchBrightnessFlags::from_bits_truncate(torch_code),
+        })
+    }
+
+    /// Starts a chainable edit of the cached configuration, written by
+    /// [`ConfigEdit::commit`].
+    ///
+    /// The flash mode left by an I2C-started flash is edited as standby, so committing does
+    /// not fire the flash again.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver
+    ///     .configure()
+    ///     .torch_current_ma(150)
+    ///     .flash_timeout(FlashTimeout::Ms600)
+    ///     .commit()?;
+    /// ```
+    pub fn configure(&mut self) -> ConfigEdit<'_, I2C, MAX_FLASH_MA> {
+        let config = Lm36011Config::from_settings(&self.resumable_settings());
+
+        ConfigEdit {
+            driver: self,
+            config,
+        }
+    }
+
+    /// Reads the registers from the device and returns them as a configuration.
+    ///
+    /// The cache is refreshed with [`LM36011::read_status`] first. A flash started over I2C is
+    /// reported as standby, so the result can be passed back to [`LM36011::apply_config`]
+    /// without firing it again.
+    pub fn read_config(&mut self) -> Result<Lm36011Config, LM36011Error<E>> {
+        self.read_status()?;
+
+        Ok(Lm36011Config::from_settings(&self.resumable_settings()))
+    }
+
+    /// Returns the cached registers without an I2C-started flash, which has already ended.
+    fn resumable_settings(&self) -> Settings {
+        let mut settings = self.settings();
+        settings.enable = Self::without_one_shot_flash(settings.enable);
+        settings
+    }
+}
+
+/// Generates the chainable setters of [`LM36011Builder`] and [`ConfigEdit`], which both edit
+/// the [`Lm36011Config`] in their `config` field, so the two keep the same names and docs.
+macro_rules! config_setters {
+    ($($target:ty),* $(,)?) => {
+        $(
+            impl<I2C, const MAX_FLASH_MA: u16> $target {
+                /// Sets the operating mode.
+                pub fn mode(mut self, mode: OperatingMode) -> Self {
+                    self.config.mode = mode;
+                    self
+                }
+
+                /// Sets the STROBE input settings.
+                pub fn strobe(mut self, strobe: StrobeConfig) -> Self {
+                    self.config.strobe = strobe;
+                    self
+                }
+
+                /// Enables IVFM with the given threshold.
+                pub fn ivfm(mut self, threshold: IvfmThreshold) -> Self {
+                    self.config.ivfm_enabled = true;
+                    self.config.ivfm_threshold = threshold;
+                    self
+                }
+
+                /// Disables IVFM.
+                pub fn without_ivfm(mut self) -> Self {
+                    self.config.ivfm_enabled = false;
+                    self
+                }
+
+                /// Sets the hardware flash time-out.
+                pub fn flash_timeout(mut self, timeout: FlashTimeout) -> Self {
+                    self.config.flash_timeout = timeout;
+                    self
+                }
+
+                /// Sets the torch current ramp.
+                pub fn torch_ramp(mut self, ramp: TorchRamp) -> Self {
+                    self.config.torch_ramp = ramp;
+                    self
+                }
+
+                /// Sets the flash current in mA, rounded down to a brightness step.
+                pub fn flash_current_ma(mut self, ma: u16) -> Self {
+                    self.config.flash_ma = ma;
+                    self
+                }
+
+                /// Sets the torch (and IR) current in mA, rounded down to a brightness step.
+                pub fn torch_current_ma(mut self, ma: u16) -> Self {
+                    self.config.torch_ma = ma;
+                    self
+                }
+
+                /// Enables or disables the thermal current scale-back.
+                pub fn thermal_scaleback(mut self, enabled: bool) -> Self {
+                    self.config.thermal_scaleback = enabled;
+                    self
+                }
+            }
+        )*
+    };
+}
+
+config_setters!(
+    LM36011Builder<I2C, MAX_FLASH_MA>,
+    ConfigEdit<'_, I2C, MAX_FLASH_MA>,
+);
+
+/// Builder for a configured driver, created with [`LM36011::builder`] or
+/// [`LM36011Builder::new`].
+///
+/// Settings that are not given keep the driver defaults of [`LM36011::new`].
+///
+/// # Example
+///
+/// ```ignore
+/// let mut driver = LM36011::builder(i2c)
+///     .torch_current_ma(150)
+///     .flash_timeout(FlashTimeout::Ms600)
+///     .ivfm(IvfmThreshold::V3_2)
+///     .build()?;
+/// ```
+pub struct LM36011Builder<I2C, const MAX_FLASH_MA: u16 = 1500> {
+    driver: LM36011<I2C, MAX_FLASH_MA>,
+    config: Lm36011Config,
+}
+
+impl<I2C, E> LM36011<I2C>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Starts building a driver with the provided I2C interface.
+    pub fn builder(i2c: I2C) -> LM36011Builder<I2C> {
+        LM36011Builder::new(i2c)
+    }
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011Builder<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Starts building a driver whose flash current is limited to `MAX_FLASH_MA`, see
+    /// [`LM36011::new_limited`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011Builder::<_, 800>::new(i2c)
+    ///     .flash_current_ma(750)
+    ///     .build()?;
+    /// ```
+    pub fn new(i2c: I2C) -> Self {
+        let driver = LM36011::new_limited(i2c);
+        let config = Lm36011Config::from_settings(&driver.settings());
+
+        Self { driver, config }
+    }
+
+    /// Sets how out-of-range currents are handled, during the build and afterwards.
+    pub fn range_policy(mut self, policy: RangePolicy) -> Self {
+        self.driver.set_range_policy(policy);
+        self
+    }
+
+    /// Validates the settings, writes all registers in one transaction and returns the driver.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LM36011)` with the cache matching the registers written.
+    /// * `Err(LM36011Error::InvalidInput)` if flash mode is requested without the STROBE input
+    ///   enabled, which would fire a flash during initialization.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if a current is above its limit and the range
+    ///   policy is [`RangePolicy::Error`].
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    pub fn build(self) -> Result<LM36011<I2C, MAX_FLASH_MA>, LM36011Error<E>> {
+        let Self { mut driver, config } = self;
+        if config.mode == OperatingMode::Flash && !config.strobe.enabled {
+            return Err(LM36011Error::InvalidInput);
+        }
+
+        driver.apply_config(&config)?;
+
+        Ok(driver)
+    }
+}
+
+/// Chainable edit of a driver's configuration, created with [`LM36011::configure`].
+///
+/// Nothing is written until [`ConfigEdit::commit`]; dropping the edit discards it.
+#[must_use = "the edit is only written by `commit`"]
+pub struct ConfigEdit<'a, I2C, const MAX_FLASH_MA: u16 = 1500> {
+    driver: &'a mut LM36011<I2C, MAX_FLASH_MA>,
+    config: Lm36011Config,
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> ConfigEdit<'_, I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Writes the edited configuration with [`LM36011::apply_config`].
+    pub fn commit(self) -> Result<(), LM36011Error<E>> {
+        self.driver.apply_config(&self.config)
+    }
+}
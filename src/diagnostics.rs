@@ -0,0 +1,224 @@
+//! Field-service diagnostics.
+//!
+//! [`LM36011::diagnostics`] bundles the device identity, a checksum of the programmed
+//! configuration and the fault history into one [`Diagnostics`] report that can be collected
+//! from every unit and sent as a compact, checksummed record.
+
+use crate::{
+    BusDevice, DeviceId, DeviceInfo, FlagRegisterFlags, LM36011Error, SiliconRevision, LM36011,
+};
+
+/// Marks the start of an encoded diagnostics record.
+const MAGIC: u8 = b'D';
+
+/// Version of the encoded diagnostics record layout.
+const VERSION: u8 = 2;
+
+/// Number of times each flag has been seen in the Flags Register, saturating at `u16::MAX`.
+///
+/// The driver keeps one set, returned by [`LM36011::fault_counters`] and cleared with
+/// [`LM36011::clear_fault_counters`], and optionally a never-cleared since-boot copy, see
+/// [`LM36011::with_fault_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounters {
+    /// IVFM trips, where the input voltage fell to the IVFM threshold.
+    pub ivfm_trip: u16,
+    /// VLED short faults.
+    pub vled_short: u16,
+    /// Thermal current scale-backs.
+    pub thermal_scaleback: u16,
+    /// Thermal shutdown faults.
+    pub thermal_shutdown: u16,
+    /// Under-voltage lockouts.
+    pub uvlo: u16,
+    /// Flashes ended by the hardware time-out.
+    pub flash_timeout: u16,
+}
+
+impl FaultCounters {
+    /// Counts the flags of a Flags Register value. The register clears on read, so every
+    /// read with a flag set is a new occurrence.
+    pub(crate) fn count(&mut self, flags: FlagRegisterFlags) {
+        let counters = [
+            (FlagRegisterFlags::IVFM_TRIP, &mut self.ivfm_trip),
+            (FlagRegisterFlags::VLED_SHORT_FAULT, &mut self.vled_short),
+            (
+                FlagRegisterFlags::THERMAL_CURRENT_SCALE_BACK,
+                &mut self.thermal_scaleback,
+            ),
+            (
+                FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT,
+                &mut self.thermal_shutdown,
+            ),
+            (FlagRegisterFlags::UVLO_FAULT, &mut self.uvlo),
+            (
+                FlagRegisterFlags::FLASH_TIMEOUT_FLAG,
+                &mut self.flash_timeout,
+            ),
+        ];
+        for (flag, counter) in counters {
+            if flags.contains(flag) {
+                *counter = counter.saturating_add(1);
+            }
+        }
+    }
+
+    fn to_array(self) -> [u16; 6] {
+        [
+            self.ivfm_trip,
+            self.vled_short,
+            self.thermal_scaleback,
+            self.thermal_shutdown,
+            self.uvlo,
+            self.flash_timeout,
+        ]
+    }
+}
+
+/// Diagnostics report returned by [`LM36011::diagnostics`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Diagnostics {
+    /// Identity read from the Device ID Register.
+    pub device: DeviceInfo,
+    /// Checksum of the Enable, Configuration, Flash Brightness and Torch Brightness Registers.
+    pub config_checksum: u8,
+    /// Fault history since the driver was created or the counters were cleared.
+    pub fault_counters: FaultCounters,
+    /// Faults latched by [`LM36011::last_faults`].
+    pub last_faults: FlagRegisterFlags,
+}
+
+impl Diagnostics {
+    /// Length of an encoded diagnostics record in bytes.
+    pub const ENCODED_LEN: usize = 18;
+
+    /// Encodes the report into a record with a magic, version and checksum.
+    ///
+    /// Counters are little endian.
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut record = [0u8; Self::ENCODED_LEN];
+        record[0] = MAGIC;
+        record[1] = VERSION;
+        record[2] = device_register(self.device);
+        record[3] = self.config_checksum;
+        record[4] = self.last_faults.bits();
+        for (i, counter) in self.fault_counters.to_array().iter().enumerate() {
+            record[5 + 2 * i..7 + 2 * i].copy_from_slice(&counter.to_le_bytes());
+        }
+        record[Self::ENCODED_LEN - 1] = crate::checksum(&record[..Self::ENCODED_LEN - 1]);
+
+        record
+    }
+
+    /// Decodes a record produced by [`Diagnostics::encode`].
+    ///
+    /// Returns `None` if the record is too short, has a different magic or version, or fails
+    /// the checksum.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let record = bytes.get(..Self::ENCODED_LEN)?;
+        let (body, sum) = record.split_at(Self::ENCODED_LEN - 1);
+
+        if body[0] != MAGIC || body[1] != VERSION || sum[0] != crate::checksum(body) {
+            return None;
+        }
+
+        let counter = |i: usize| u16::from_le_bytes([body[5 + 2 * i], body[6 + 2 * i]]);
+        Some(Self {
+            device: DeviceInfo::from_register(body[2]),
+            config_checksum: body[3],
+            fault_counters: FaultCounters {
+                ivfm_trip: counter(0),
+                vled_short: counter(1),
+                thermal_scaleback: counter(2),
+                thermal_shutdown: counter(3),
+                uvlo: counter(4),
+                flash_timeout: counter(5),
+            },
+            last_faults: FlagRegisterFlags::from_bits_truncate(body[4]),
+        })
+    }
+}
+
+/// Rebuilds the Device ID Register bits of a decoded identity.
+fn device_register(device: DeviceInfo) -> u8 {
+    let id = match device.device_id {
+        DeviceId::Lm36011 => 0b000,
+        DeviceId::Unknown(id) => id,
+    };
+    let revision = match device.revision {
+        SiliconRevision::Rev1 => 0b001,
+        SiliconRevision::Unknown(revision) => revision,
+    };
+
+    (id << 3) | revision
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Collects a diagnostics report.
+    ///
+    /// Reads the Device ID Register and refreshes the register cache with
+    /// [`LM36011::read_status`], so faults pending in the Flags Register are counted too.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let report = driver.diagnostics()?;
+    /// uplink.send(&report.encode())?;
+    /// ```
+    pub fn diagnostics(&mut self) -> Result<Diagnostics, LM36011Error<E>> {
+        let device =
+            DeviceInfo::from_register(self.get_device_id().map_err(LM36011Error::I2CError)?);
+        self.read_status()?;
+
+        Ok(Diagnostics {
+            device,
+            config_checksum: crate::checksum(&[
+                self.enable_flags.bits(),
+                self.config_flags.bits(),
+                self.flash_brightness_flags.bits(),
+                self.torch_brightness_flags.bits(),
+            ]),
+            fault_counters: self.fault_counters,
+            last_faults: self.last_faults,
+        })
+    }
+
+    /// Returns how often each fault has been seen since the driver was created or the
+    /// counters were cleared.
+    pub fn fault_counters(&self) -> FaultCounters {
+        self.fault_counters
+    }
+
+    /// Resets the fault counters.
+    pub fn clear_fault_counters(&mut self) {
+        self.fault_counters = FaultCounters::default();
+    }
+
+    /// Enables a since-boot copy of the [`FaultCounters`], which costs 12 bytes of RAM.
+    ///
+    /// Unlike [`LM36011::fault_counters`] it is never cleared, so a one-off UVLO can be told
+    /// apart from a chronically shorted LED over the whole uptime.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011::new(i2c).with_fault_stats();
+    /// // ...
+    /// if let Some(stats) = driver.fault_stats() {
+    ///     log.uvlo_count(stats.uvlo);
+    /// }
+    /// ```
+    pub fn with_fault_stats(mut self) -> Self {
+        self.fault_stats = Some(FaultCounters::default());
+        self
+    }
+
+    /// Returns the since-boot fault counters, or `None` unless enabled with
+    /// [`LM36011::with_fault_stats`].
+    pub fn fault_stats(&self) -> Option<FaultCounters> {
+        self.fault_stats
+    }
+}
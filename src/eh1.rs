@@ -0,0 +1,155 @@
+//! `embedded-hal` 1.0 support.
+//!
+//! The driver API is built on the `embedded-hal` 0.2 traits. The adapters in this module wrap
+//! `embedded-hal` 1.0 peripherals, as provided by modern HALs such as `embassy-stm32` and
+//! `esp-hal`, so they can be passed wherever the driver expects the 0.2 traits:
+//!
+//! * [`I2cDevice`] - an [`embedded_hal_1::i2c::I2c`] bus as the driver transport. Without the
+//!   `eh0` feature every 1.0 bus is a transport as is, so the wrapper is only needed when both
+//!   `eh0` and `eh1` are enabled.
+//! * [`Delay`] - an [`embedded_hal_1::delay::DelayNs`] delay for the timed APIs.
+//! * [`Pin`] - an [`embedded_hal_1::digital::OutputPin`] as a strobe pin.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lm36011::eh1::{Delay, I2cDevice, Pin};
+//!
+//! let mut driver = LM36011::new(I2cDevice::new(i2c));
+//! let mut delay = Delay::new(delay);
+//! let mut trigger = TriggerMode::HardwareStrobe { pin: Pin::new(strobe) };
+//! driver.led_connectivity_check(&mut delay)?;
+//! ```
+
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_1::delay::DelayNs;
+use embedded_hal_1::i2c::I2c;
+
+use crate::BusDevice;
+
+/// Uses an `embedded-hal` 1.0 I2C bus as the driver transport.
+#[derive(Debug)]
+pub struct I2cDevice<I2C>(I2C);
+
+impl<I2C> I2cDevice<I2C> {
+    /// Wraps an `embedded-hal` 1.0 I2C bus.
+    pub fn new(i2c: I2C) -> Self {
+        Self(i2c)
+    }
+
+    /// Returns the wrapped bus.
+    pub fn into_inner(self) -> I2C {
+        self.0
+    }
+}
+
+#[cfg(not(feature = "eh0"))]
+impl<I2C: I2c> BusDevice for I2C {
+    type Error = I2C::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        I2c::write(self, address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        I2c::write_read(self, address, bytes, buffer)
+    }
+}
+
+impl<I2C: I2c> BusDevice for I2cDevice<I2C> {
+    type Error = I2C::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.write(address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.0.write_read(address, bytes, buffer)
+    }
+}
+
+/// Uses an `embedded-hal` 1.0 delay for the driver's timed APIs.
+#[derive(Debug)]
+pub struct Delay<D>(D);
+
+impl<D> Delay<D> {
+    /// Wraps an `embedded-hal` 1.0 delay.
+    pub fn new(delay: D) -> Self {
+        Self(delay)
+    }
+
+    /// Returns the wrapped delay.
+    pub fn into_inner(self) -> D {
+        self.0
+    }
+}
+
+impl<D: DelayNs> DelayMs<u8> for Delay<D> {
+    fn delay_ms(&mut self, ms: u8) {
+        self.0.delay_ms(ms.into());
+    }
+}
+
+impl<D: DelayNs> DelayMs<u16> for Delay<D> {
+    fn delay_ms(&mut self, ms: u16) {
+        self.0.delay_ms(ms.into());
+    }
+}
+
+impl<D: DelayNs> DelayMs<u32> for Delay<D> {
+    fn delay_ms(&mut self, ms: u32) {
+        self.0.delay_ms(ms);
+    }
+}
+
+impl<D: DelayNs> DelayUs<u16> for Delay<D> {
+    fn delay_us(&mut self, us: u16) {
+        self.0.delay_us(us.into());
+    }
+}
+
+impl<D: DelayNs> DelayUs<u32> for Delay<D> {
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+}
+
+/// Uses an `embedded-hal` 1.0 output pin as the strobe pin of a
+/// [`TriggerMode::HardwareStrobe`](crate::TriggerMode::HardwareStrobe).
+#[derive(Debug)]
+pub struct Pin<P>(P);
+
+impl<P> Pin<P> {
+    /// Wraps an `embedded-hal` 1.0 output pin.
+    pub fn new(pin: P) -> Self {
+        Self(pin)
+    }
+
+    /// Returns the wrapped pin.
+    pub fn into_inner(self) -> P {
+        self.0
+    }
+}
+
+impl<P: embedded_hal_1::digital::OutputPin> OutputPin for Pin<P> {
+    type Error = P::Error;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high()
+    }
+}
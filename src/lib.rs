@@ -1,26 +1,197 @@
 #![no_std]
+//! Library for the Texas instruments LM36011 inductorless LED driver
+//!
+//! <https://www.ti.com/lit/ds/symlink/lm36011.pdf?ts=1694461699965&ref_url=https%253A%252F%252Fwww.ti.com%252Fproduct%252FLM36011>
+//!
+//! This crate enables register only read / write, or complete register one-shot read/write based
+//! on the last known register values and the bitflags crate for updating specific features.
+//!
+//! Current conversions use integer arithmetic internally. The `f32` based APIs
+//! (`set_flash_current`, `plan_flash_current`, `flash_currents`, ...) need the default `float`
+//! feature; without it, and always on AVR targets, the crate has no floating point code and
+//! the integer mA APIs remain.
 
 use bitflags::bitflags;
 use core::fmt;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
 use embedded_hal::blocking::i2c;
+use embedded_hal::digital::v2::OutputPin;
 
-/// Library for the Texas instruments LM36011 inductorless LED driver
-///
-/// https://www.ti.com/lit/ds/symlink/lm36011.pdf?ts=1694461699965&ref_url=https%253A%252F%252Fwww.ti.com%252Fproduct%252FLM36011
-///
-/// This crate enables register only read / write, or complete register one-shot read/write based
-/// on the last known register values and the bitflags crate for updating specific features.
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod config;
+pub mod diagnostics;
+#[cfg(feature = "eh1")]
+pub mod eh1;
+#[cfg(feature = "alloc")]
+pub mod parse;
+pub mod persist;
+pub mod presets;
+#[cfg(feature = "heapless")]
+pub mod queue;
+#[cfg(feature = "rtic")]
+pub mod rtic;
+pub mod schedule;
+pub mod shared;
+pub mod telemetry;
+pub mod transcript;
+pub mod typestate;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 /// Custom errors for the LM36011.
 #[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum LM36011Error<E> {
     I2CError(E),
     InvalidInput,
     CurrentOutOfRange,
     DeviceIDError,
+    StrobePinError,
+    TemperatureSensorError,
+    TimeoutOutOfRange,
+    InterlockEngaged,
+    CurrentSenseError,
+    StandbyVerifyError,
+    FaultShutdown,
+}
+
+impl<E> LM36011Error<E> {
+    /// Returns whether the error came from the I2C bus rather than the driver.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::LM36011Error;
+    ///
+    /// assert!(LM36011Error::I2CError(()).is_bus_error());
+    /// assert!(!LM36011Error::<()>::CurrentOutOfRange.is_bus_error());
+    /// ```
+    pub fn is_bus_error(&self) -> bool {
+        matches!(self, LM36011Error::I2CError(_))
+    }
+
+    /// Returns the bus error of an `I2CError`, or `None` for errors raised by the driver.
+    pub fn into_inner(self) -> Option<E> {
+        match self {
+            LM36011Error::I2CError(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns a stable one-byte code for the error, for telemetry links such as LoRa or CAN.
+    ///
+    /// Codes are never reused; `0` is reserved for "no error".
+    ///
+    /// | Code | Error                    |
+    /// |------|--------------------------|
+    /// | 1    | `I2CError`               |
+    /// | 2    | `InvalidInput`           |
+    /// | 3    | `CurrentOutOfRange`      |
+    /// | 4    | `DeviceIDError`          |
+    /// | 5    | `StrobePinError`         |
+    /// | 6    | `TemperatureSensorError` |
+    /// | 7    | `TimeoutOutOfRange`      |
+    /// | 8    | `InterlockEngaged`       |
+    /// | 9    | `CurrentSenseError`      |
+    /// | 10   | `StandbyVerifyError`     |
+    /// | 11   | `FaultShutdown`          |
+    pub fn code(&self) -> u8 {
+        match self {
+            LM36011Error::I2CError(_) => 1,
+            LM36011Error::InvalidInput => 2,
+            LM36011Error::CurrentOutOfRange => 3,
+            LM36011Error::DeviceIDError => 4,
+            LM36011Error::StrobePinError => 5,
+            LM36011Error::TemperatureSensorError => 6,
+            LM36011Error::TimeoutOutOfRange => 7,
+            LM36011Error::InterlockEngaged => 8,
+            LM36011Error::CurrentSenseError => 9,
+            LM36011Error::StandbyVerifyError => 10,
+            LM36011Error::FaultShutdown => 11,
+        }
+    }
+}
+
+impl LM36011Error<()> {
+    /// Decodes a code produced by [`LM36011Error::code`], e.g. on the host side of a telemetry
+    /// link. The bus error itself is not transmitted, so `I2CError` carries `()`.
+    ///
+    /// Returns `None` for `0` and unknown codes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::LM36011Error;
+    ///
+    /// let code = LM36011Error::<()>::CurrentOutOfRange.code();
+    /// assert!(matches!(
+    ///     LM36011Error::from_code(code),
+    ///     Some(LM36011Error::CurrentOutOfRange)
+    /// ));
+    /// ```
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(LM36011Error::I2CError(())),
+            2 => Some(LM36011Error::InvalidInput),
+            3 => Some(LM36011Error::CurrentOutOfRange),
+            4 => Some(LM36011Error::DeviceIDError),
+            5 => Some(LM36011Error::StrobePinError),
+            6 => Some(LM36011Error::TemperatureSensorError),
+            7 => Some(LM36011Error::TimeoutOutOfRange),
+            8 => Some(LM36011Error::InterlockEngaged),
+            9 => Some(LM36011Error::CurrentSenseError),
+            10 => Some(LM36011Error::StandbyVerifyError),
+            11 => Some(LM36011Error::FaultShutdown),
+            _ => None,
+        }
+    }
+}
+
+/// Describes the error in one lowercase phrase, e.g. `current out of range`.
+///
+/// The bus error of `I2CError` is not repeated; error reporters print it as the source.
+impl<E> fmt::Display for LM36011Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LM36011Error::I2CError(_) => "I2C bus error",
+            LM36011Error::InvalidInput => "invalid input",
+            LM36011Error::CurrentOutOfRange => "current out of range",
+            LM36011Error::DeviceIDError => "unexpected device ID",
+            LM36011Error::StrobePinError => "strobe pin error",
+            LM36011Error::TemperatureSensorError => "temperature sensor could not be read",
+            LM36011Error::TimeoutOutOfRange => "flash time-out out of range",
+            LM36011Error::InterlockEngaged => "eye-safety interlock engaged",
+            LM36011Error::CurrentSenseError => "LED current could not be measured",
+            LM36011Error::StandbyVerifyError => "device did not reach standby",
+            LM36011Error::FaultShutdown => "output shut down after a fault",
+        })
+    }
+}
+
+/// Wraps a bus error, so `?` works on bus calls in code returning an [`LM36011Error`].
+impl<E> From<E> for LM36011Error<E> {
+    fn from(error: E) -> Self {
+        LM36011Error::I2CError(error)
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for LM36011Error<E> {
+    /// Returns the bus error of an `I2CError`.
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            LM36011Error::I2CError(error) => Some(error),
+            _ => None,
+        }
+    }
 }
 
 /// Represents the configuration registers of the LM36011.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Register {
     /// Enable Register
     EnableRegister = 0x01,
@@ -36,23 +207,80 @@ pub enum Register {
     DeviceIdRegister = 0x06,
 }
 
+impl Register {
+    /// Returns the register at a register address, or `None` for addresses outside 0x01-0x06.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::Register;
+    ///
+    /// assert_eq!(Register::from_address(0x05), Some(Register::FlagsRegister));
+    /// assert_eq!(Register::from_address(0x07), None);
+    /// ```
+    pub fn from_address(address: u8) -> Option<Self> {
+        Some(match address {
+            0x01 => Register::EnableRegister,
+            0x02 => Register::ConfigurationRegister,
+            0x03 => Register::LEDFlashBrightnessRegister,
+            0x04 => Register::LEDTorchBrightnessRegister,
+            0x05 => Register::FlagsRegister,
+            0x06 => Register::DeviceIdRegister,
+            _ => return None,
+        })
+    }
+
+    /// Returns the datasheet name of the register.
+    fn name(self) -> &'static str {
+        match self {
+            Register::EnableRegister => "Enable Register",
+            Register::ConfigurationRegister => "Configuration Register",
+            Register::LEDFlashBrightnessRegister => "LED Flash Brightness Register",
+            Register::LEDTorchBrightnessRegister => "LED Torch Brightness Register",
+            Register::FlagsRegister => "Flags Register",
+            Register::DeviceIdRegister => "Device ID Register",
+        }
+    }
+}
+
 /// implement display trait for Register Enum to be used in printing out to serial if needed
 impl fmt::Display for Register {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            Register::EnableRegister => write!(f, "Enable Register"),
-            Register::ConfigurationRegister => write!(f, "Configuration Register"),
-            Register::LEDFlashBrightnessRegister => write!(f, "LED Flash Brightness Register"),
-            Register::LEDTorchBrightnessRegister => write!(f, "LED Torch Brightness Register"),
-            Register::FlagsRegister => write!(f, "Flags Register"),
-            Register::DeviceIdRegister => write!(f, "Device ID Register"),
-        }
+        f.pad(self.name())
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for Register {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        f.write_str(self.name())
+    }
+}
+
+/// Prints the variant name like the `Debug` implementation.
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for Register {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        f.write_str(match self {
+            Register::EnableRegister => "EnableRegister",
+            Register::ConfigurationRegister => "ConfigurationRegister",
+            Register::LEDFlashBrightnessRegister => "LEDFlashBrightnessRegister",
+            Register::LEDTorchBrightnessRegister => "LEDTorchBrightnessRegister",
+            Register::FlagsRegister => "FlagsRegister",
+            Register::DeviceIdRegister => "DeviceIdRegister",
+        })
     }
 }
 
 // Bitflags for the Enable Register (0x01)
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug,PartialEq,Clone,Copy)]
     pub struct EnableRegisterFlags: u8 {
         // Reserved for future use
         const ENABLE_REGISTER_RFU           = 0b1110_0000;
@@ -70,7 +298,7 @@ bitflags! {
 
 // Bitflags for the Configuration Register (0x02)
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug,PartialEq,Clone,Copy)]
     pub struct ConfigurationRegisterFlags: u8 {
         /// IVFM Levels (IVFM-D) [Bit 7-5]
         const IVFM_2_9V         = 0b0000_0000;
@@ -108,7 +336,7 @@ bitflags! {
 
 // Bitflags for the LED Flash Brightness Register (0x03)
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug,PartialEq,Clone,Copy)]
     pub struct LedFlashBrightnessFlags: u8 {
         /// LED Flash Brightness Level [Bit 6:0]
         const FLASH_11MA    = 0x00;
@@ -125,7 +353,7 @@ bitflags! {
 
 // Bitflags for the LED Torch Brightness Register (0x04)
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug,PartialEq,Clone,Copy)]
     pub struct LedTorchBrightnessFlags: u8 {
         // Reserved for future use
         const TORCH_BRIGHTNESS_RFU  = 0b1000_0000;
@@ -141,7 +369,7 @@ bitflags! {
 
 // Bitflags for the Flags Register (0x05)
 bitflags! {
-    #[derive(Debug)]
+    #[derive(Debug,PartialEq,Clone,Copy)]
     pub struct FlagRegisterFlags: u8 {
         // Reserved for future use
         const FLAGS_REGISTER_RFU            = 0b1000_0000;
@@ -155,6 +383,70 @@ bitflags! {
     }
 }
 
+impl FlagRegisterFlags {
+    /// Returns a human-readable description of a single flag, e.g. `"thermal shutdown"`.
+    ///
+    /// Returns `"none"` for no flags and `"multiple flags"` for a combination; print those with
+    /// `Display`, which lists every flag set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::FlagRegisterFlags;
+    ///
+    /// assert_eq!(FlagRegisterFlags::VLED_SHORT_FAULT.describe(), "VLED short");
+    /// ```
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::IVFM_TRIP => "IVFM trip",
+            Self::VLED_SHORT_FAULT => "VLED short",
+            Self::THERMAL_CURRENT_SCALE_BACK => "thermal current scale-back",
+            Self::THERMAL_SHUTDOWN_FAULT => "thermal shutdown",
+            Self::UVLO_FAULT => "UVLO",
+            Self::FLASH_TIMEOUT_FLAG => "flash time-out",
+            Self::FLAGS_REGISTER_RFU => "reserved bit",
+            flags if flags.is_empty() => "none",
+            _ => "multiple flags",
+        }
+    }
+}
+
+/// Lists the flags set, e.g. `thermal shutdown, UVLO`, or `none`.
+impl fmt::Display for FlagRegisterFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+        for (index, flag) in self.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", flag.describe())?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists the flags set like the `Display` implementation.
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for FlagRegisterFlags {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+        for (index, flag) in self.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(flag.describe())?;
+        }
+        Ok(())
+    }
+}
+
 // Bitflags for the Device ID Register (0x06)
 bitflags! {
     // Represents the Device ID and RESET Register of the LM36011.
@@ -176,389 +468,4644 @@ bitflags! {
     }
 }
 
-/// I2C address for the LM36011 device.
-const LM36011_I2C_ADDRESS: u8 = 0x64;
+/// Formats register flags as their raw bits, leaving the decoding to the host.
+#[cfg(feature = "defmt")]
+macro_rules! impl_defmt_bits {
+    ($($flags:ident => $format:literal),* $(,)?) => {
+        $(
+            impl defmt::Format for $flags {
+                fn format(&self, f: defmt::Formatter) {
+                    defmt::write!(f, $format, self.bits());
+                }
+            }
+        )*
+    };
+}
 
-/// Represents the LM36011 device with an associated I2C interface.
-pub struct LM36011<I2C> {
-    /// The I2C interface used to communicate with the device.
-    i2c: I2C,
-    pub enable_flags: EnableRegisterFlags,
-    pub config_flags: ConfigurationRegisterFlags,
-    pub flash_brightness_flags: LedFlashBrightnessFlags,
-    pub torch_brightness_flags: LedTorchBrightnessFlags,
-    pub flag_register_flags: FlagRegisterFlags,
-    pub device_id: DeviceIdFlags,
+#[cfg(feature = "defmt")]
+impl_defmt_bits!(
+    EnableRegisterFlags => "EnableRegisterFlags({=u8:#010b})",
+    ConfigurationRegisterFlags => "ConfigurationRegisterFlags({=u8:#010b})",
+    LedFlashBrightnessFlags => "LedFlashBrightnessFlags({=u8:#010b})",
+    LedTorchBrightnessFlags => "LedTorchBrightnessFlags({=u8:#010b})",
+    FlagRegisterFlags => "FlagRegisterFlags({=u8:#010b})",
+    DeviceIdFlags => "DeviceIdFlags({=u8:#010b})",
+);
+
+/// Formats register flags as their raw bits, e.g. `FlagRegisterFlags(0x22)`.
+#[cfg(feature = "ufmt")]
+macro_rules! impl_udebug_bits {
+    ($($flags:ident),* $(,)?) => {
+        $(
+            impl ufmt::uDebug for $flags {
+                fn fmt<W: ufmt::uWrite + ?Sized>(
+                    &self,
+                    f: &mut ufmt::Formatter<'_, W>,
+                ) -> Result<(), W::Error> {
+                    ufmt::uwrite!(f, "{}({:#x})", stringify!($flags), self.bits())
+                }
+            }
+        )*
+    };
 }
 
-impl<I2C> fmt::Display for LM36011<I2C> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Enable Register: {:?}, \
-            Configuration Register: {:?}, \
-            LED Flash Brightness Register: {:?}, \
-            LED Torch Brightness Register: {:?}, \
-            Flags Register: {:?}, \
-            Device ID Register: {:?}",
-            self.enable_flags,
-            self.config_flags,
-            self.flash_brightness_flags,
-            self.torch_brightness_flags,
-            self.flag_register_flags,
-            self.device_id
-        )
-    }
+#[cfg(feature = "ufmt")]
+impl_udebug_bits!(
+    EnableRegisterFlags,
+    ConfigurationRegisterFlags,
+    LedFlashBrightnessFlags,
+    LedTorchBrightnessFlags,
+    FlagRegisterFlags,
+    DeviceIdFlags,
+);
+
+/// A `step * code + offset` current scale of a brightness register, in µA.
+///
+/// All conversions are done in integer µA so targets without an FPU (e.g. AVR) never need
+/// floating point or 64-bit arithmetic.
+#[derive(Debug, Clone, Copy)]
+struct CurrentScale {
+    /// Current step per brightness code.
+    step_ua: u32,
+    /// Current at brightness code 0.
+    offset_ua: u32,
+    /// Highest current that can be requested.
+    max_ua: u32,
 }
 
-impl<I2C, E> LM36011<I2C>
-where
-    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
-{
-    /// Creates a new instance of the LM36011 with the provided I2C interface.
-    pub fn new(i2c: I2C) -> Self {
-        Self {
-            i2c,
-            enable_flags: EnableRegisterFlags::IVFM_ENABLE,
-            config_flags: ConfigurationRegisterFlags::IVFM_2_9V
-                | ConfigurationRegisterFlags::TIMEOUT_600MS
-                | ConfigurationRegisterFlags::TORCH_RAMP_1MS,
-            flash_brightness_flags: LedFlashBrightnessFlags::FLASH_11MA
-                | LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED,
-            torch_brightness_flags: LedTorchBrightnessFlags::TORCH_2_4MA,
-            flag_register_flags: FlagRegisterFlags::empty(),
-            device_id: DeviceIdFlags::empty(),
+impl CurrentScale {
+    /// Returns the highest code whose current does not exceed `ua`.
+    fn code_floor(self, ua: u32) -> u8 {
+        if ua <= self.offset_ua {
+            return 0;
         }
+        ((ua - self.offset_ua) / self.step_ua).min(MAX_BRIGHTNESS_CODE as u32) as u8
     }
 
-    /// Sets the flash current of the LM36011 device.
-    ///
-    /// This function configures the flash current of the LM36011 by writing to the
-    /// `LEDFlashBrightnessRegister`. The desired current value is passed as an argument.
-    ///
-    /// # Arguments
-    ///
-    /// * `current` - The desired flash current value to be set. The exact range and interpretation
-    ///               of this value should be based on the LM36011 documentation.
-    ///
-    /// # Returns
+    /// Returns the current driven for `code`.
+    fn ua(self, code: u8) -> u32 {
+        code as u32 * self.step_ua + self.offset_ua
+    }
+
+    /// Returns the highest code within the scale.
+    fn max_code(self) -> u8 {
+        self.code_floor(self.max_ua)
+    }
+
+    /// Rounds a request in mA down to a code, or returns `None` if it is out of range.
+    fn code_for_ma(self, ma: u16) -> Option<u8> {
+        self.code_for_ma_rounded(ma, Rounding::Floor)
+    }
+
+    /// Rounds a request in mA to a code, or returns `None` if it is out of range.
     ///
-    /// * `Ok(())` if the operation was successful.
-    /// * `Err(E)` if there was an error during the operation. The error type `E` is determined by the I2C interface.
+    /// Rounding up never selects a code above the scale maximum.
+    fn code_for_ma_rounded(self, ma: u16, rounding: Rounding) -> Option<u8> {
+        let ua = ma as u32 * 1000;
+        if ua > self.max_ua {
+            return None;
+        }
+
+        let floor = self.code_floor(ua);
+        let remainder = ua.saturating_sub(self.ua(floor));
+        let up = match rounding {
+            Rounding::Floor => false,
+            Rounding::Nearest => remainder * 2 >= self.step_ua,
+            Rounding::Ceil => remainder > 0,
+        };
+
+        Some(if up {
+            (floor + 1).min(self.max_code())
+        } else {
+            floor
+        })
+    }
+}
+
+/// Flash current scale (datasheet: I = code * 11.725 mA + 10.9 mA, up to 1.5 A).
+const FLASH_SCALE: CurrentScale = CurrentScale {
+    step_ua: 11_725,
+    offset_ua: 10_900,
+    max_ua: 1_500_000,
+};
+
+/// Torch current scale (datasheet: I = code * 2.94 mA + 2.4 mA, up to 376 mA).
+const TORCH_SCALE: CurrentScale = CurrentScale {
+    step_ua: 2_940,
+    offset_ua: 2_400,
+    max_ua: 376_000,
+};
+
+/// IVFM threshold bits 7:5 of the Configuration Register.
+const IVFM_THRESHOLD_MASK: u8 = 0b1110_0000;
+
+/// Flash time-out bits 4:1 of the Configuration Register.
+const FLASH_TIMEOUT_MASK: u8 = 0b0001_1110;
+
+/// Flags latched by [`LM36011::last_faults`]; the flash time-out flag marks a normal flash end.
+const LATCHED_FLAGS: FlagRegisterFlags = FlagRegisterFlags::IVFM_TRIP
+    .union(FlagRegisterFlags::VLED_SHORT_FAULT)
+    .union(FlagRegisterFlags::THERMAL_CURRENT_SCALE_BACK)
+    .union(FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT)
+    .union(FlagRegisterFlags::UVLO_FAULT);
+
+/// Faults that turn the output off when the fault cut-off is enabled.
+const CUTOFF_FLAGS: FlagRegisterFlags =
+    FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT.union(FlagRegisterFlags::VLED_SHORT_FAULT);
+
+/// Time to wait for the 40 ms test pulse of the LED connectivity check to end.
+const LED_CHECK_WAIT_MS: u8 = 50;
+
+/// Time the minimum torch current of [`LM36011::self_test_led`] stays on, past the 1 ms ramp.
+const SELF_TEST_WAIT_MS: u8 = 5;
+
+/// Extra wait after the flash time-out before [`LM36011::flash_and_check`] reads the flags.
+const FLASH_END_MARGIN_MS: u16 = 10;
+
+/// Highest 7-bit brightness code accepted by the flash and torch brightness registers.
+const MAX_BRIGHTNESS_CODE: u8 = 0x7F;
+
+/// The outcome of converting a requested current into a brightness code.
+///
+/// Returned by [`LM36011::plan_flash_current`] and [`LM36011::plan_torch_current`] so callers
+/// can evaluate the quantization before committing a write.
+#[cfg(all(feature = "float", not(target_arch = "avr")))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurrentPlan {
+    /// The 7-bit brightness code that would be written.
+    pub code: u8,
+    /// The current in mA the device drives for `code`.
+    pub actual_ma: f32,
+    /// `actual_ma` minus the requested current in mA (negative when the device undershoots).
+    pub error_ma: f32,
+}
+
+#[cfg(all(feature = "float", not(target_arch = "avr")))]
+impl CurrentPlan {
+    /// Rounds the requested current down to the nearest code of `scale`, or returns `None` if
+    /// the request is out of range.
+    fn new(requested: f32, scale: CurrentScale) -> Option<Self> {
+        if !(0.0..=ua_to_ma(scale.max_ua)).contains(&requested) {
+            return None;
+        }
+        let code = scale.code_floor(ma_to_ua(requested));
+        let actual_ma = ua_to_ma(scale.ua(code));
+
+        Some(Self {
+            code,
+            actual_ma,
+            error_ma: actual_ma - requested,
+        })
+    }
+}
+
+/// How a current between two brightness steps is converted to a code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// The step at or below the request (the default), never exceeding it.
+    #[default]
+    Floor,
+    /// The closest step, halfway rounding up.
+    Nearest,
+    /// The step at or above the request, as long as it is within the limits.
+    Ceil,
+}
+
+/// How out-of-range current and time-out requests are handled.
+///
+/// Set per driver with [`LM36011::set_range_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RangePolicy {
+    /// Reject the request with [`LM36011Error::CurrentOutOfRange`] or
+    /// [`LM36011Error::TimeoutOutOfRange`] (the default).
+    #[default]
+    Error,
+    /// Clamp the request to the nearest value within the device, board and profile limits.
+    ClampToLimit,
+}
+
+/// A brightness in whichever unit the caller has, for [`LM36011::set_torch`] and
+/// [`LM36011::set_flash`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Brightness {
+    /// A raw 7-bit brightness code.
+    Code(u8),
+    /// A current in mA, rounded down to a brightness step.
+    Milliamps(u16),
+    /// A percentage (0 - 100) of the current limit in force, rounded down to a brightness step.
+    Percent(u8),
+}
+
+impl Brightness {
+    /// Returns whether the brightness asks for no light, used by auto-standby.
+    fn is_zero(self) -> bool {
+        matches!(self, Brightness::Milliamps(0) | Brightness::Percent(0))
+    }
+}
+
+/// Software soft-start settings for [`LM36011::soft_start_torch`] and
+/// [`LM36011::soft_start_ir`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoftStart {
+    /// Total duration of the ramp in ms.
+    pub duration_ms: u16,
+    /// Number of brightness steps the ramp is split into.
+    pub steps: u8,
+}
+
+impl SoftStart {
+    /// Creates soft-start settings from a duration, saturating at `u16::MAX` ms.
     ///
     /// # Example
     ///
     /// ```
-    /// // Some initialization to get the device instance
-    /// //(I2C needs to be initialized first)
-    /// let mut driver = lm36011::LM36011::new(i2c); // Some initialization to get the device instance
-    /// match driver.set_flash_current(285.0) {
-    ///     Ok(_) => println!("Flash current set successfully"),
-    ///     Err(e) => eprintln!("Error setting flash current: {:?}", e),
-    /// }
+    /// use lm36011::SoftStart;
+    ///
+    /// assert_eq!(SoftStart::new(50, 10), SoftStart { duration_ms: 50, steps: 10 });
     /// ```
-    pub fn set_flash_current_hex(&mut self, current: u8) -> Result<(), LM36011Error<E>> {
-        if current > 0b1000_0000 {
-            return Err(LM36011Error::CurrentOutOfRange);
+    pub fn new(duration: impl IntoMillis, steps: u8) -> Self {
+        Self {
+            duration_ms: u16::try_from(duration.into_millis()).unwrap_or(u16::MAX),
+            steps,
         }
+    }
+}
 
-        // Use the set_register function to set the flash current
-        self.set_register(Register::LEDFlashBrightnessRegister, current)
+/// Operating mode selected by the MODE bits 1:0 of the Enable Register.
+///
+/// The modes are exclusive values of a two-bit field, not combinable flags: inserting
+/// `EnableRegisterFlags::MODE_TORCH` on top of `MODE_IR_DRIVE` yields flash mode. Use
+/// [`LM36011::set_mode`] to change the mode safely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OperatingMode {
+    Standby,
+    IrDrive,
+    Torch,
+    Flash,
+}
+
+impl OperatingMode {
+    /// Returns the MODE bits of the mode.
+    pub fn bits(self) -> u8 {
+        self as u8
     }
 
-    /// Sets the flash current of the LM36011 device.
+    /// Decodes the MODE bits of an Enable Register value.
     ///
-    /// This function configures the flash current of the LM36011 by writing to the
-    /// `LEDFlashBrightnessRegister`. The desired current value is passed as an argument.
+    /// # Example
     ///
-    /// # Arguments
+    /// ```
+    /// use lm36011::OperatingMode;
+    ///
+    /// assert_eq!(OperatingMode::from_bits(0b0001_0010), OperatingMode::Torch);
+    /// ```
+    pub fn from_bits(enable: u8) -> Self {
+        match enable & EnableRegisterFlags::MODE_MASK.bits() {
+            0b00 => OperatingMode::Standby,
+            0b01 => OperatingMode::IrDrive,
+            0b10 => OperatingMode::Torch,
+            _ => OperatingMode::Flash,
+        }
+    }
+}
+
+/// Hardware flash time-out selected by bits 4:1 of the Configuration Register.
+///
+/// The flash ends after the time-out unless it is ended earlier over I2C or the strobe pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlashTimeout {
+    Ms40,
+    Ms80,
+    Ms120,
+    Ms160,
+    Ms200,
+    Ms240,
+    Ms280,
+    Ms320,
+    Ms360,
+    Ms400,
+    Ms600,
+    Ms800,
+    Ms1000,
+    Ms1200,
+    Ms1400,
+    Ms1600,
+}
+
+impl FlashTimeout {
+    /// All time-outs, shortest first.
+    pub const ALL: [FlashTimeout; 16] = [
+        FlashTimeout::Ms40,
+        FlashTimeout::Ms80,
+        FlashTimeout::Ms120,
+        FlashTimeout::Ms160,
+        FlashTimeout::Ms200,
+        FlashTimeout::Ms240,
+        FlashTimeout::Ms280,
+        FlashTimeout::Ms320,
+        FlashTimeout::Ms360,
+        FlashTimeout::Ms400,
+        FlashTimeout::Ms600,
+        FlashTimeout::Ms800,
+        FlashTimeout::Ms1000,
+        FlashTimeout::Ms1200,
+        FlashTimeout::Ms1400,
+        FlashTimeout::Ms1600,
+    ];
+
+    /// Returns the time-out in ms.
     ///
-    /// * `current` - The desired flash current value to be set. The input current in mA will be
-    /// divided by 11.7 and converted to a u8 byte.  Note: since the resolution of the driver is
-    /// 11.7mA, setting fractions of the current is likely overkill, but could be more accurate in a
-    /// very small subset of results.
+    /// # Example
     ///
-    /// # Returns
+    /// ```
+    /// use lm36011::FlashTimeout;
     ///
-    /// * `Ok(())` if the operation was successful.
-    /// * `Err(E)` if there was an error during the operation. The error type `E` is determined by the I2C interface.
+    /// assert_eq!(FlashTimeout::Ms400.ms(), 400);
+    /// assert_eq!(FlashTimeout::Ms1600.ms(), 1600);
+    /// ```
+    pub fn ms(self) -> u16 {
+        let index = self as u16;
+        if index < 10 {
+            40 * (index + 1)
+        } else {
+            400 + 200 * (index - 9)
+        }
+    }
+
+    /// Returns the time-out bits in their Configuration Register position.
+    pub fn bits(self) -> u8 {
+        (self as u8) << 1
+    }
+
+    /// Returns the shortest time-out of at least `ms`, or `None` above 1600 ms.
     ///
     /// # Example
     ///
     /// ```
-    /// // Some initialization to get the device instance
-    /// //(I2C needs to be initialized first)
-    /// let mut driver = lm36011::LM36011::new(i2c); // Some initialization to get the device instance
-    /// match driver.set_flash_current(150.0) {
-    ///     Ok(_) => println!("Flash current set successfully"),
-    ///     Err(e) => eprintln!("Error setting flash current: {:?}", e),
-    /// }
+    /// use lm36011::FlashTimeout;
+    ///
+    /// assert_eq!(FlashTimeout::at_least(250), Some(FlashTimeout::Ms280));
+    /// assert_eq!(FlashTimeout::at_least(1601), None);
     /// ```
-    pub fn set_flash_current(&mut self, current: f32) -> Result<(), LM36011Error<E>> {
-        if current < 0.0 || current > 1500.0 {
-            return Err(LM36011Error::CurrentOutOfRange);
-        }
-        // take in the current in mA (f32) and convert it to a hex value
-        let brightness_flags: u8 = (current / 11.7) as u8;
+    pub fn at_least(ms: u32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|timeout| timeout.ms() as u32 >= ms)
+    }
 
-        // convert the u8 value to a LedFlashBrightnessFlags
-        let mut brightness_bitflags = LedFlashBrightnessFlags::from_bits_truncate(brightness_flags);
+    /// Decodes the time-out bits of a Configuration Register value.
+    pub fn from_bits(config: u8) -> Self {
+        Self::ALL[((config & FLASH_TIMEOUT_MASK) >> 1) as usize]
+    }
+}
 
-        // Ensure the thermal current scale-back bit remains set/not set
-        brightness_bitflags.set(
+/// Torch current ramp, bit 0 of the Configuration Register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TorchRamp {
+    /// The torch current steps to its level immediately.
+    Off,
+    /// The torch current ramps up over 1 ms, softening inrush on the supply.
+    Ms1,
+}
+
+impl TorchRamp {
+    /// Returns the ramp bit in its Configuration Register position.
+    pub fn bits(self) -> u8 {
+        self as u8
+    }
+
+    /// Decodes the torch ramp bit of a Configuration Register value.
+    pub fn from_bits(config: u8) -> Self {
+        if config & ConfigurationRegisterFlags::TORCH_RAMP_1MS.bits() != 0 {
+            TorchRamp::Ms1
+        } else {
+            TorchRamp::Off
+        }
+    }
+}
+
+/// How the STROBE input triggers, bit 3 of the Enable Register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StrobeTrigger {
+    /// The LED is on while STROBE is high, limited by the flash time-out.
+    Level,
+    /// A rising edge on STROBE starts a flash that runs for the flash time-out.
+    Edge,
+}
+
+/// STROBE input settings, bits 3:2 of the Enable Register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StrobeConfig {
+    /// Whether the STROBE input is enabled.
+    pub enabled: bool,
+    /// How the STROBE input triggers.
+    pub trigger: StrobeTrigger,
+}
+
+impl StrobeConfig {
+    /// Returns the strobe bits in their Enable Register position.
+    pub fn bits(self) -> u8 {
+        let mut enable = EnableRegisterFlags::empty();
+        enable.set(EnableRegisterFlags::STROBE_ENABLE, self.enabled);
+        enable.set(
+            EnableRegisterFlags::STROBE_TYPE_EDGE_TRIGGERED,
+            self.trigger == StrobeTrigger::Edge,
+        );
+        enable.bits()
+    }
+
+    /// Decodes the strobe bits of an Enable Register value.
+    pub fn from_bits(enable: u8) -> Self {
+        let enable = EnableRegisterFlags::from_bits_truncate(enable);
+        Self {
+            enabled: enable.contains(EnableRegisterFlags::STROBE_ENABLE),
+            trigger: if enable.contains(EnableRegisterFlags::STROBE_TYPE_EDGE_TRIGGERED) {
+                StrobeTrigger::Edge
+            } else {
+                StrobeTrigger::Level
+            },
+        }
+    }
+}
+
+/// Input voltage flash monitor (IVFM) threshold, bits 7:5 of the Configuration Register.
+///
+/// When the input voltage falls to the threshold the device reduces the LED current to keep
+/// the supply from collapsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IvfmThreshold {
+    V2_9,
+    V3_0,
+    V3_1,
+    V3_2,
+    V3_3,
+    V3_4,
+    V3_5,
+    V3_6,
+}
+
+/// Battery types with a preset IVFM threshold, see [`IvfmThreshold::for_chemistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryChemistry {
+    /// One lithium-ion / lithium-polymer cell (4.2 V full, 3.0 V cut-off).
+    LiIon1S,
+    /// One lithium iron phosphate cell (3.6 V full, 2.5 V cut-off).
+    LiFePo4_1S,
+    /// Three NiMH cells in series (4.2 V full, 3.0 V cut-off).
+    NiMh3S,
+    /// Two NiMH cells in series (2.8 V full, 2.0 V cut-off).
+    NiMh2S,
+    /// Three alkaline cells in series (4.8 V fresh, 2.7 V cut-off).
+    Alkaline3S,
+    /// Two alkaline cells in series (3.2 V fresh, 1.8 V cut-off).
+    Alkaline2S,
+}
+
+/// How much of the battery's capacity the IVFM preset may use before limiting the current.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CutoffPreference {
+    /// Limit early, keeping a wide margin above the cut-off voltage.
+    Conservative,
+    /// A margin suited to most designs.
+    #[default]
+    Balanced,
+    /// Limit as late as the chemistry allows, for the brightest flashes on a low battery.
+    Aggressive,
+}
+
+impl IvfmThreshold {
+    /// Returns the threshold in mV.
+    pub fn millivolts(self) -> u16 {
+        2900 + 100 * self as u16
+    }
+
+    /// Returns the threshold bits in their Configuration Register position.
+    pub fn bits(self) -> u8 {
+        (self as u8) << 5
+    }
+
+    /// Decodes the IVFM threshold bits of a Configuration Register value.
+    pub fn from_bits(config: u8) -> Self {
+        use IvfmThreshold::*;
+        [V2_9, V3_0, V3_1, V3_2, V3_3, V3_4, V3_5, V3_6][(config >> 5) as usize]
+    }
+
+    /// Returns the balanced IVFM threshold for a battery chemistry.
+    ///
+    /// Returns `None` when the chemistry operates below the 2.9 V lowest threshold, in which
+    /// case IVFM should be disabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::{BatteryChemistry, IvfmThreshold};
+    ///
+    /// assert_eq!(IvfmThreshold::for_chemistry(BatteryChemistry::LiIon1S), Some(IvfmThreshold::V3_2));
+    /// assert_eq!(IvfmThreshold::for_chemistry(BatteryChemistry::NiMh2S), None);
+    /// ```
+    pub fn for_chemistry(chemistry: BatteryChemistry) -> Option<Self> {
+        Self::for_chemistry_with(chemistry, CutoffPreference::Balanced)
+    }
+
+    /// Returns the IVFM threshold for a battery chemistry and cut-off preference.
+    ///
+    /// Returns `None` when the chemistry operates below the 2.9 V lowest threshold.
+    pub fn for_chemistry_with(
+        chemistry: BatteryChemistry,
+        preference: CutoffPreference,
+    ) -> Option<Self> {
+        use CutoffPreference::*;
+        use IvfmThreshold::*;
+
+        let level = match (chemistry, preference) {
+            (BatteryChemistry::LiIon1S, Conservative) => V3_4,
+            (BatteryChemistry::LiIon1S, Balanced) => V3_2,
+            (BatteryChemistry::LiIon1S, Aggressive) => V3_0,
+            (BatteryChemistry::LiFePo4_1S, Conservative) => V3_0,
+            (BatteryChemistry::LiFePo4_1S, _) => V2_9,
+            (BatteryChemistry::NiMh3S, Conservative) => V3_3,
+            (BatteryChemistry::NiMh3S, Balanced) => V3_1,
+            (BatteryChemistry::NiMh3S, Aggressive) => V3_0,
+            (BatteryChemistry::Alkaline3S, Conservative) => V3_3,
+            (BatteryChemistry::Alkaline3S, Balanced) => V3_0,
+            (BatteryChemistry::Alkaline3S, Aggressive) => V2_9,
+            (BatteryChemistry::NiMh2S | BatteryChemistry::Alkaline2S, _) => return None,
+        };
+
+        Some(level)
+    }
+}
+
+/// A flash current and hardware time-out realizing a requested flash energy.
+///
+/// Returned by [`LM36011::plan_exposure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExposurePlan {
+    /// The 7-bit flash brightness code.
+    pub code: u8,
+    /// The flash current in mA driven for `code`, rounded down.
+    pub current_ma: u16,
+    /// The hardware time-out ending the flash.
+    pub timeout: FlashTimeout,
+    /// The realized flash energy in mA·ms, rounded down.
+    pub energy_ma_ms: u32,
+}
+
+/// A duration accepted by the timing APIs.
+///
+/// Implemented for plain `u32` millisecond counts and, with the `fugit` feature, for every
+/// `fugit::Duration` (e.g. `MillisDurationU32` or `MicrosDurationU64`), so RTIC and embassy
+/// users can pass their own duration type.
+pub trait IntoMillis {
+    /// Returns the duration in whole milliseconds, rounded down and saturating at `u32::MAX`.
+    fn into_millis(self) -> u32;
+}
+
+impl IntoMillis for u32 {
+    fn into_millis(self) -> u32 {
+        self
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> IntoMillis for fugit::Duration<u32, NOM, DENOM> {
+    fn into_millis(self) -> u32 {
+        self.to_millis()
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> IntoMillis for fugit::Duration<u64, NOM, DENOM> {
+    fn into_millis(self) -> u32 {
+        u32::try_from(self.to_millis()).unwrap_or(u32::MAX)
+    }
+}
+
+/// How a flash is started and stopped.
+///
+/// Chosen once at configuration time and passed to [`LM36011::arm_flash`],
+/// [`LM36011::fire_flash`] and [`LM36011::end_flash`], which use the matching mechanism, so
+/// sequence code does not change when the triggering strategy does.
+pub enum TriggerMode<P = NoStrobePin> {
+    /// The flash is started and stopped over I2C with the STROBE input disabled.
+    I2cOnly,
+    /// The flash is started and stopped by driving the STROBE input from `pin`.
+    HardwareStrobe {
+        /// The output connected to the STROBE input.
+        pin: P,
+    },
+    /// The STROBE input is enabled for an external source (e.g. an image sensor's flash output)
+    /// and the flash can also be fired over I2C.
+    Hybrid,
+}
+
+/// Placeholder pin for the [`TriggerMode`] variants that do not drive the STROBE input.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoStrobePin;
+
+impl OutputPin for NoStrobePin {
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Outcome of [`LM36011::led_connectivity_check`] and [`LM36011::self_test_led`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LedCheck {
+    /// The test pulse completed without a fault.
+    Healthy,
+    /// The LED output is shorted (VLED short fault).
+    Shorted,
+    /// The test pulse was interrupted by a thermal shutdown or UVLO fault.
+    Fault(FlagRegisterFlags),
+}
+
+impl LedCheck {
+    /// Classifies the Flags register read after the test pulse.
+    fn from_flags(flags: FlagRegisterFlags) -> Self {
+        if flags.contains(FlagRegisterFlags::VLED_SHORT_FAULT) {
+            LedCheck::Shorted
+        } else if flags
+            .intersects(FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT | FlagRegisterFlags::UVLO_FAULT)
+        {
+            LedCheck::Fault(flags)
+        } else {
+            LedCheck::Healthy
+        }
+    }
+}
+
+/// Decoded Flags Register (0x05), returned by [`LM36011::read_faults`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Faults {
+    /// The die overheated and the LED was turned off.
+    pub thermal_shutdown: bool,
+    /// The LED output is shorted.
+    pub vled_short: bool,
+    /// The input voltage fell below the under-voltage lockout threshold.
+    pub uvlo: bool,
+    /// The input voltage reached the IVFM threshold and the LED current was reduced.
+    pub ivfm_trip: bool,
+    /// A flash ended at the hardware time-out.
+    pub flash_timeout: bool,
+    /// The die temperature reduced the flash current.
+    pub thermal_scaleback: bool,
+}
+
+impl Faults {
+    /// Decodes a Flags Register value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::{Faults, FlagRegisterFlags};
+    ///
+    /// let faults = Faults::from_flags(FlagRegisterFlags::UVLO_FAULT);
+    /// assert!(faults.uvlo && faults.is_fault());
+    /// ```
+    pub fn from_flags(flags: FlagRegisterFlags) -> Self {
+        Self {
+            thermal_shutdown: flags.contains(FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT),
+            vled_short: flags.contains(FlagRegisterFlags::VLED_SHORT_FAULT),
+            uvlo: flags.contains(FlagRegisterFlags::UVLO_FAULT),
+            ivfm_trip: flags.contains(FlagRegisterFlags::IVFM_TRIP),
+            flash_timeout: flags.contains(FlagRegisterFlags::FLASH_TIMEOUT_FLAG),
+            thermal_scaleback: flags.contains(FlagRegisterFlags::THERMAL_CURRENT_SCALE_BACK),
+        }
+    }
+
+    /// Returns whether a fault turned the LED off: thermal shutdown, VLED short or UVLO.
+    ///
+    /// IVFM trips and thermal scale-back only reduce the current, and the flash time-out
+    /// marks a normal flash end.
+    pub fn is_fault(&self) -> bool {
+        self.thermal_shutdown || self.vled_short || self.uvlo
+    }
+
+    /// Returns the severity of the most severe flag set, or `None` if no flag is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::{FaultSeverity, Faults, FlagRegisterFlags};
+    ///
+    /// let faults = Faults::from_flags(
+    ///     FlagRegisterFlags::THERMAL_CURRENT_SCALE_BACK | FlagRegisterFlags::VLED_SHORT_FAULT,
+    /// );
+    /// assert_eq!(faults.severity(), Some(FaultSeverity::Critical));
+    /// ```
+    pub fn severity(&self) -> Option<FaultSeverity> {
+        if self.is_fault() {
+            Some(FaultSeverity::Critical)
+        } else if self.ivfm_trip || self.thermal_scaleback {
+            Some(FaultSeverity::Degraded)
+        } else if self.flash_timeout {
+            Some(FaultSeverity::Info)
+        } else {
+            None
+        }
+    }
+
+    /// Encodes the faults back into a Flags Register value.
+    pub fn flags(&self) -> FlagRegisterFlags {
+        let mut flags = FlagRegisterFlags::empty();
+        flags.set(
+            FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT,
+            self.thermal_shutdown,
+        );
+        flags.set(FlagRegisterFlags::VLED_SHORT_FAULT, self.vled_short);
+        flags.set(FlagRegisterFlags::UVLO_FAULT, self.uvlo);
+        flags.set(FlagRegisterFlags::IVFM_TRIP, self.ivfm_trip);
+        flags.set(FlagRegisterFlags::FLASH_TIMEOUT_FLAG, self.flash_timeout);
+        flags.set(
+            FlagRegisterFlags::THERMAL_CURRENT_SCALE_BACK,
+            self.thermal_scaleback,
+        );
+        flags
+    }
+}
+
+/// How serious a Flags Register flag is, ordered from least to most severe.
+///
+/// Lets safety logic react uniformly, e.g. shut the product down on `Critical`, without
+/// repeating the datasheet meaning of each flag. See [`Faults::severity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FaultSeverity {
+    /// A normal event: a flash ended at its hardware time-out.
+    Info,
+    /// The LED still works at a reduced current: IVFM trip or thermal scale-back.
+    Degraded,
+    /// The LED was turned off: thermal shutdown, VLED short or UVLO.
+    Critical,
+}
+
+/// Lists the faults and flags set, e.g. `VLED short, UVLO`, or `none`, for debug output.
+///
+/// # Example
+///
+/// ```ignore
+/// let faults = driver.read_faults()?;
+/// if faults.is_fault() {
+///     writeln!(uart, "LED off: {}", faults)?;
+/// }
+/// ```
+impl fmt::Display for Faults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.flags(), f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDisplay for Faults {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        ufmt::uDisplay::fmt(&self.flags(), f)
+    }
+}
+
+/// Converts µA to mA.
+#[cfg(all(feature = "float", not(target_arch = "avr")))]
+fn ua_to_ma(ua: u32) -> f32 {
+    ua as f32 / 1000.0
+}
+
+/// Converts mA to µA.
+///
+/// The cast truncates, so a current just below a step in `f32` (e.g. 128.15 mA, flash code
+/// 10, is 128149.99 µA) selects the code below. The `libm` feature rounds to the nearest µA
+/// first.
+#[cfg(all(feature = "float", not(target_arch = "avr")))]
+fn ma_to_ua(ma: f32) -> u32 {
+    let ua = ma * 1000.0;
+    #[cfg(feature = "libm")]
+    let ua = libm::roundf(ua);
+
+    ua as u32
+}
+
+/// Returns every flash current (in mA) the LM36011 can be programmed to, in code order.
+///
+/// The n-th item is the current driven for brightness code `n`, so the values can be offered as
+/// exact selectable steps instead of letting users enter currents that get quantized.
+///
+/// # Example
+///
+/// ```
+/// let steps = lm36011::flash_currents();
+/// assert_eq!(steps.len(), 128);
+/// ```
+#[cfg(all(feature = "float", not(target_arch = "avr")))]
+pub fn flash_currents() -> impl ExactSizeIterator<Item = f32> + DoubleEndedIterator + Clone {
+    (0..=MAX_BRIGHTNESS_CODE).map(|code| ua_to_ma(FLASH_SCALE.ua(code)))
+}
+
+/// Returns every torch current (in mA) the LM36011 can be programmed to, in code order.
+///
+/// The n-th item is the current driven for brightness code `n`.
+///
+/// # Example
+///
+/// ```
+/// let lowest = lm36011::torch_currents().next();
+/// assert_eq!(lowest, Some(2.4));
+/// ```
+#[cfg(all(feature = "float", not(target_arch = "avr")))]
+pub fn torch_currents() -> impl ExactSizeIterator<Item = f32> + DoubleEndedIterator + Clone {
+    (0..=MAX_BRIGHTNESS_CODE).map(|code| ua_to_ma(TORCH_SCALE.ua(code)))
+}
+
+/// Converts a flash current in mA to the brightness code, rounding down, using integer math only.
+///
+/// Returns `None` if the current is above 1500 mA.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(lm36011::flash_code_for_ma(1500), Some(0x7F));
+/// assert_eq!(lm36011::flash_code_for_ma(1501), None);
+/// ```
+pub fn flash_code_for_ma(ma: u16) -> Option<u8> {
+    FLASH_SCALE.code_for_ma(ma)
+}
+
+/// Converts a torch current in mA to the brightness code, rounding down, using integer math only.
+///
+/// Returns `None` if the current is above 376 mA.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(lm36011::torch_code_for_ma(376), Some(0x7F));
+/// ```
+pub fn torch_code_for_ma(ma: u16) -> Option<u8> {
+    TORCH_SCALE.code_for_ma(ma)
+}
+
+/// Converts a flash brightness code to the driven current in mA, rounded down.
+///
+/// Only the 7 brightness bits of `code` are used.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(lm36011::flash_ma_for_code(0x15), 257);
+/// ```
+pub fn flash_ma_for_code(code: u8) -> u16 {
+    (FLASH_SCALE.ua(code & MAX_BRIGHTNESS_CODE) / 1000) as u16
+}
+
+/// Converts a torch brightness code to the driven current in mA, rounded down.
+///
+/// Only the 7 brightness bits of `code` are used.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(lm36011::torch_ma_for_code(0x7F), 375);
+/// ```
+pub fn torch_ma_for_code(code: u8) -> u16 {
+    (TORCH_SCALE.ua(code & MAX_BRIGHTNESS_CODE) / 1000) as u16
+}
+
+/// Inverted wrapping sum of `bytes`, used by the persisted and streamed records so an all-zero
+/// record does not validate.
+fn checksum(bytes: &[u8]) -> u8 {
+    !bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+/// Part number decoded from the device ID bits 5:3 of the Device ID Register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceId {
+    /// LM36011 (device ID `000`).
+    Lm36011,
+    /// A device ID this driver does not know, holding the raw 3-bit value.
+    Unknown(u8),
+}
+
+impl DeviceId {
+    /// Decodes the device ID bits of a Device ID Register value.
+    pub fn from_register(value: u8) -> Self {
+        match (value & DeviceIdFlags::DEVICE_ID_MASK.bits()) >> 3 {
+            0b000 => DeviceId::Lm36011,
+            other => DeviceId::Unknown(other),
+        }
+    }
+}
+
+/// Silicon revision decoded from bits 2:0 of the Device ID Register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiliconRevision {
+    /// Silicon revision `001`, the production revision documented in the datasheet.
+    Rev1,
+    /// A revision this driver does not know, holding the raw 3-bit value.
+    Unknown(u8),
+}
+
+impl SiliconRevision {
+    /// Decodes the silicon revision bits of a Device ID Register value.
+    pub fn from_register(value: u8) -> Self {
+        match value & DeviceIdFlags::SILICON_REVISION_MASK.bits() {
+            0b001 => SiliconRevision::Rev1,
+            other => SiliconRevision::Unknown(other),
+        }
+    }
+}
+
+/// Outcome of a flash, returned by [`LM36011::confirm_flash_fired`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlashOutcome {
+    /// The flash ran until the hardware time-out ended it.
+    TimedOut,
+    /// The device is still in flash mode, firing or waiting for the strobe.
+    InProgress,
+    /// The device is in standby without a time-out flag: the flash never started, or was ended
+    /// early over I2C or by the strobe.
+    NotFired,
+    /// The flash was stopped by a fault; holds the Flags Register.
+    Fault(FlagRegisterFlags),
+}
+
+/// Change of the connected device detected by the identity check, see
+/// [`LM36011::poll_identity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentityEvent {
+    /// The device answers with a different identity than when it was first seen.
+    DeviceChanged {
+        /// The identity first seen.
+        expected: DeviceInfo,
+        /// The identity now reported.
+        found: DeviceInfo,
+    },
+    /// The device did not answer the identity read.
+    DeviceLost,
+}
+
+/// Proof of the first step of the eye-safety interlock, see [`LM36011::request_unlock`].
+///
+/// Tokens cannot be copied or built outside the driver; only the most recently issued token
+/// is accepted by [`LM36011::unlock`].
+#[derive(Debug)]
+pub struct UnlockToken {
+    nonce: u16,
+    issued: u32,
+    valid_ms: Option<u32>,
+}
+
+/// A photobiological exposure budget (IEC 62471 style) for continuous torch and IR operation.
+///
+/// The weighted exposure of the observer (e.g. blue-light weighted radiance, or IR irradiance
+/// at the eye) is modelled as proportional to the LED current; the budget is the permitted dose
+/// of that quantity. Units are up to the caller as long as they are consistent, e.g. W·m⁻²·sr⁻¹
+/// per mA for `rate_per_ma` and mJ·m⁻²·sr⁻¹ (W·ms) for `dose_limit`. Both fit 32 bits and the
+/// products saturate, so pick units that keep the limit below `u32::MAX`.
+///
+/// # Example
+///
+/// ```
+/// use lm36011::ExposureBudget;
+///
+/// // blue-light hazard: 10^6 J·m⁻²·sr⁻¹, the LED gives 40 W·m⁻²·sr⁻¹ per mA at the viewing distance
+/// let budget = ExposureBudget { rate_per_ma: 40, dose_limit: 1_000_000_000 };
+/// assert_eq!(budget.max_on_time_ms(250), Some(100_000));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExposureBudget {
+    /// Weighted exposure rate per mA of LED current.
+    pub rate_per_ma: u32,
+    /// Permitted dose, in the units of `rate_per_ma` times mA times ms.
+    pub dose_limit: u32,
+}
+
+impl ExposureBudget {
+    /// Returns the maximum continuous on-time in ms at `current_ma`, or `None` if unlimited.
+    pub fn max_on_time_ms(&self, current_ma: u16) -> Option<u32> {
+        let rate = self.rate_per_ma.saturating_mul(current_ma as u32);
+        if rate == 0 {
+            return None;
+        }
+
+        Some(self.dose_limit / rate)
+    }
+}
+
+/// Orderable variant of the device, selected with [`LM36011::with_variant`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    /// The LM36011 (-40 °C to 85 °C ambient).
+    #[default]
+    Lm36011,
+    /// The AEC-Q100 qualified LM36011-Q1 (-40 °C to 125 °C ambient).
+    Lm36011Q1,
+}
+
+impl Variant {
+    /// Returns the part number.
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Lm36011 => "LM36011",
+            Variant::Lm36011Q1 => "LM36011-Q1",
+        }
+    }
+
+    /// Returns the device ID the variant reports.
+    ///
+    /// The -Q1 shares the die and register map of the LM36011, including its device ID.
+    pub fn device_id(self) -> DeviceId {
+        match self {
+            Variant::Lm36011 | Variant::Lm36011Q1 => DeviceId::Lm36011,
+        }
+    }
+
+    /// Returns the highest specified ambient temperature in °C.
+    pub fn max_ambient_c(self) -> i16 {
+        match self {
+            Variant::Lm36011 => 85,
+            Variant::Lm36011Q1 => 125,
+        }
+    }
+}
+
+/// Identity of the device returned by [`LM36011::probe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// The part number.
+    pub device_id: DeviceId,
+    /// The silicon revision.
+    pub revision: SiliconRevision,
+}
+
+impl DeviceInfo {
+    /// Decodes a Device ID Register value.
+    pub fn from_register(value: u8) -> Self {
+        Self {
+            device_id: DeviceId::from_register(value),
+            revision: SiliconRevision::from_register(value),
+        }
+    }
+}
+
+/// Behaviour that differs between silicon revisions, consulted by the driver.
+///
+/// See [`LM36011::quirks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// Time the device needs after a software reset before it accepts commands, in µs.
+    pub reset_settle_us: u16,
+    /// Whether reading the Flags Register clears the latched flags.
+    pub flags_clear_on_read: bool,
+}
+
+/// Quirks of every known silicon revision.
+///
+/// Add a row here when a new revision with different behaviour is released.
+const QUIRKS_TABLE: &[(SiliconRevision, Quirks)] = &[(
+    SiliconRevision::Rev1,
+    Quirks {
+        reset_settle_us: 1000,
+        flags_clear_on_read: true,
+    },
+)];
+
+/// Conservative quirks used for revisions missing from [`QUIRKS_TABLE`].
+const UNKNOWN_REVISION_QUIRKS: Quirks = Quirks {
+    reset_settle_us: 5000,
+    flags_clear_on_read: true,
+};
+
+impl Quirks {
+    /// Looks up the quirks of a silicon revision.
+    pub fn for_revision(revision: SiliconRevision) -> Self {
+        QUIRKS_TABLE
+            .iter()
+            .find(|(known, _)| *known == revision)
+            .map_or(UNKNOWN_REVISION_QUIRKS, |(_, quirks)| *quirks)
+    }
+}
+
+/// A named set of current limits, see [`LM36011::with_profiles`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitProfile {
+    /// Name used to select the profile with [`LM36011::set_profile`].
+    pub name: &'static str,
+    /// Highest flash current allowed while the profile is active, in mA.
+    pub max_flash_ma: u16,
+    /// Highest torch (and IR) current allowed while the profile is active, in mA.
+    pub max_torch_ma: u16,
+}
+
+/// A point of a board-level thermal derating table, see [`LM36011::with_derating`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeratingPoint {
+    /// Temperature in °C from which the limits apply.
+    pub temperature_c: i16,
+    /// Highest flash current allowed from `temperature_c`, in mA.
+    pub max_flash_ma: u16,
+    /// Highest torch (and IR) current allowed from `temperature_c`, in mA.
+    pub max_torch_ma: u16,
+}
+
+/// An external temperature sensor feeding [`LM36011::update_temperature`].
+pub trait TemperatureSensor {
+    /// Error type of the sensor.
+    type Error;
+
+    /// Reads the board temperature in °C.
+    fn read_celsius(&mut self) -> Result<i16, Self::Error>;
+}
+
+/// A measurement of the actual LED current, e.g. an ADC across a sense resistor, feeding
+/// [`LM36011::verify_current`].
+pub trait CurrentSense {
+    /// Error type of the measurement.
+    type Error;
+
+    /// Reads the LED current in mA.
+    fn read_ma(&mut self) -> Result<u16, Self::Error>;
+}
+
+/// Observer of the faults found in the Flags Register, registered with
+/// [`LM36011::with_fault_handler`].
+///
+/// Called from whichever driver call read the flags, so keep it short, e.g. store the faults
+/// in an atomic or a queue for a logging task. Closures and functions taking [`Faults`]
+/// implement it.
+pub trait FaultHandler {
+    /// Called with the decoded flags whenever a read finds a fault, IVFM trip or thermal
+    /// scale-back.
+    fn on_fault(&self, faults: Faults);
+}
+
+impl<F: Fn(Faults)> FaultHandler for F {
+    fn on_fault(&self, faults: Faults) {
+        self(faults)
+    }
+}
+
+/// Result of [`LM36011::verify_current`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurrentCheck {
+    /// The measured current is within the tolerance of the programmed one.
+    WithinTolerance { expected_ma: u16, measured_ma: u16 },
+    /// The measured current deviates beyond the tolerance, e.g. an open LED, a wrong LED bin or
+    /// a solder fault.
+    Deviation { expected_ma: u16, measured_ma: u16 },
+}
+
+/// I2C address for the LM36011 device.
+const LM36011_I2C_ADDRESS: u8 = 0x64;
+
+/// Emits a trace-level record per register of a transfer with the `log` or `defmt` feature,
+/// e.g. `LM36011 write Enable Register: 0x12`.
+fn trace_registers(access: &str, first: u8, values: &[u8]) {
+    #[cfg(any(feature = "log", feature = "defmt"))]
+    for (address, &value) in (first..).zip(values) {
+        let Some(reg) = Register::from_address(address) else {
+            continue;
+        };
+        #[cfg(feature = "log")]
+        log::trace!("LM36011 {} {}: {:#04x}", access, reg, value);
+        #[cfg(feature = "defmt")]
+        defmt::trace!("LM36011 {=str} {}: {=u8:#04x}", access, reg, value);
+    }
+    #[cfg(not(any(feature = "log", feature = "defmt")))]
+    let _ = (access, first, values);
+}
+
+/// Transport used by the driver to reach the LM36011 registers.
+///
+/// With the `eh0` feature (the default) it is implemented for every embedded-hal 0.2 I2C master
+/// with `Write` and `WriteRead`. With `eh1` and without `eh0`, it is implemented for every
+/// embedded-hal 1.0 I2C bus instead; with both, wrap 1.0 buses in `eh1::I2cDevice`.
+/// Implementing it for another transport, such as an SPI-to-I2C bridge like the SC18IS602B, lets the driver run
+/// over that transport without duplicating any register logic.
+///
+/// # Example
+///
+/// ```ignore
+/// struct Sc18is602b<SPI> { spi: SPI }
+///
+/// impl<SPI: Transfer<u8>> lm36011::BusDevice for Sc18is602b<SPI> {
+///     type Error = SPI::Error;
+///
+///     fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+///         // issue the bridge's "write N bytes to I2C slave" command
+///     }
+///
+///     fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Self::Error> {
+///         // issue the bridge's "I2C read after write" command, then fetch the read buffer
+///     }
+/// }
+///
+/// let mut driver = lm36011::LM36011::new(Sc18is602b { spi });
+/// ```
+pub trait BusDevice {
+    /// Error returned by the transport.
+    type Error;
+
+    /// Writes `bytes` to the device at `address` in a single transaction.
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Writes `bytes` to the device at `address`, then reads `buffer.len()` bytes back.
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "eh0")]
+impl<I2C, E> BusDevice for I2C
+where
+    I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E>,
+{
+    type Error = E;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), E> {
+        i2c::Write::write(self, address, bytes)
+    }
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), E> {
+        i2c::WriteRead::write_read(self, address, bytes, buffer)
+    }
+}
+
+/// Represents the LM36011 device with an associated I2C interface.
+///
+/// `MAX_FLASH_MA` is the highest flash current the board's LED may be driven with. It defaults
+/// to the device maximum of 1500 mA; see [`LM36011::new_limited`] to lower it.
+pub struct LM36011<I2C, const MAX_FLASH_MA: u16 = 1500> {
+    /// The I2C interface used to communicate with the device.
+    i2c: I2C,
+    pub enable_flags: EnableRegisterFlags,
+    pub config_flags: ConfigurationRegisterFlags,
+    pub flash_brightness_flags: LedFlashBrightnessFlags,
+    pub torch_brightness_flags: LedTorchBrightnessFlags,
+    /// Sticky Flags Register: every flag seen by a read since the flags were last taken with
+    /// [`LM36011::take_faults`] (or [`LM36011::read_faults`]) or cleared.
+    pub flag_register_flags: FlagRegisterFlags,
+    pub device_id: DeviceIdFlags,
+    /// Behaviour of the connected silicon revision.
+    quirks: Quirks,
+    /// Registered current-limit profiles.
+    profiles: &'static [LimitProfile],
+    /// Index of the active profile in `profiles`.
+    active_profile: Option<usize>,
+    /// Whether a zero current drops the device to standby.
+    auto_standby: bool,
+    /// Mode to restore when a non-zero current is set after an auto-standby.
+    suspended_mode: Option<EnableRegisterFlags>,
+    /// Handling of out-of-range requests.
+    range_policy: RangePolicy,
+    /// Board-level thermal derating table.
+    derating: &'static [DeratingPoint],
+    /// Index of the derating point in force.
+    derated: Option<usize>,
+    /// Most recent non-empty fault state.
+    last_faults: FlagRegisterFlags,
+    /// Every fault seen since the history was last reset.
+    fault_history: FlagRegisterFlags,
+    /// Observer called when a read finds faults.
+    fault_handler: Option<&'static (dyn FaultHandler + Sync)>,
+    /// Whether a thermal shutdown or VLED short turns the output off.
+    fault_cutoff: bool,
+    /// The output was turned off by the fault cut-off and is held off until the faults are
+    /// cleared.
+    cut_off: bool,
+    /// A cut-off happened that has not been reported yet.
+    cutoff_event: bool,
+    /// Occurrences of each fault seen in the Flags Register.
+    fault_counters: diagnostics::FaultCounters,
+    /// Never-cleared copy of `fault_counters`, if enabled.
+    fault_stats: Option<diagnostics::FaultCounters>,
+    /// The orderable variant the board is fitted with.
+    variant: Variant,
+    /// Identity seen by the first probe or identity check.
+    identity: Option<DeviceInfo>,
+    /// Identity change noticed by `read_status`, reported by the next poll.
+    identity_event: Option<IdentityEvent>,
+    /// Polls between identity reads, 0 when periodic reads are disabled.
+    identity_interval: u16,
+    /// Polls left until the next identity read.
+    identity_countdown: u16,
+    /// Flash current above which the interlock must be unlocked, in mA.
+    interlock_ma: Option<u16>,
+    /// Nonce of the most recently issued unlock token.
+    interlock_nonce: u16,
+    /// Whether the next high-current flash is allowed.
+    unlocked: bool,
+    /// Flash brightness code last written to or read from the device; raising the current
+    /// above it is what the interlock guards.
+    device_flash_code: u8,
+    /// Photobiological budget enforced in torch and IR modes.
+    exposure_budget: Option<ExposureBudget>,
+    /// Dose accumulated during the current continuous on-time, in budget units times ms.
+    exposure_dose: u32,
+    /// Cycles spent in the last successful fire call.
+    #[cfg(feature = "cycle-count")]
+    fire_cycles: Option<u32>,
+}
+
+impl<I2C, const MAX_FLASH_MA: u16> fmt::Display for LM36011<I2C, MAX_FLASH_MA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Enable Register: {:?}, \
+            Configuration Register: {:?}, \
+            LED Flash Brightness Register: {:?}, \
+            LED Torch Brightness Register: {:?}, \
+            Flags Register: {:?}, \
+            Device ID Register: {:?}",
+            self.enable_flags,
+            self.config_flags,
+            self.flash_brightness_flags,
+            self.torch_brightness_flags,
+            self.flag_register_flags,
+            self.device_id
+        )
+    }
+}
+
+impl<I2C, E> LM36011<I2C>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Creates a new instance of the LM36011 with the provided I2C interface.
+    pub fn new(i2c: I2C) -> Self {
+        Self::new_limited(i2c)
+    }
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// The flash current scale capped at `MAX_FLASH_MA`.
+    const FLASH_LIMIT: CurrentScale = CurrentScale {
+        max_ua: MAX_FLASH_MA as u32 * 1000,
+        ..FLASH_SCALE
+    };
+
+    /// Creates a new instance of the LM36011 whose flash current is limited to `MAX_FLASH_MA`.
+    ///
+    /// Every flash current setter rejects currents above the limit, and
+    /// [`LM36011::set_flash_current_const`] rejects them at compile time. A `MAX_FLASH_MA`
+    /// above the 1500 mA device maximum fails to compile.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // the board's LED is rated for 800 mA
+    /// let mut driver: LM36011<_, 800> = LM36011::new_limited(i2c);
+    /// ```
+    pub fn new_limited(i2c: I2C) -> Self {
+        const {
+            assert!(
+                MAX_FLASH_MA as u32 * 1000 <= FLASH_SCALE.max_ua,
+                "MAX_FLASH_MA is above the 1500 mA flash maximum"
+            )
+        };
+
+        Self {
+            i2c,
+            enable_flags: EnableRegisterFlags::IVFM_ENABLE,
+            config_flags: ConfigurationRegisterFlags::IVFM_2_9V
+                | ConfigurationRegisterFlags::TIMEOUT_600MS
+                | ConfigurationRegisterFlags::TORCH_RAMP_1MS,
+            flash_brightness_flags: LedFlashBrightnessFlags::FLASH_11MA
+                | LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED,
+            torch_brightness_flags: LedTorchBrightnessFlags::TORCH_2_4MA,
+            flag_register_flags: FlagRegisterFlags::empty(),
+            device_id: DeviceIdFlags::empty(),
+            quirks: Quirks::for_revision(SiliconRevision::Unknown(0)),
+            profiles: &[],
+            active_profile: None,
+            auto_standby: false,
+            suspended_mode: None,
+            range_policy: RangePolicy::Error,
+            derating: &[],
+            derated: None,
+            last_faults: FlagRegisterFlags::empty(),
+            fault_history: FlagRegisterFlags::empty(),
+            fault_handler: None,
+            fault_cutoff: false,
+            cut_off: false,
+            cutoff_event: false,
+            fault_counters: diagnostics::FaultCounters::default(),
+            fault_stats: None,
+            variant: Variant::Lm36011,
+            identity: None,
+            identity_event: None,
+            identity_interval: 0,
+            identity_countdown: 0,
+            interlock_ma: None,
+            interlock_nonce: 0,
+            unlocked: false,
+            device_flash_code: 0,
+            exposure_budget: None,
+            exposure_dose: 0,
+            #[cfg(feature = "cycle-count")]
+            fire_cycles: None,
+        }
+    }
+
+    /// Creates a new instance whose cached registers hold the datasheet power-on reset values
+    /// ([`persist::Settings::POWER_ON_RESET`]), so the cache matches a freshly powered device
+    /// before the first [`LM36011::read_status`].
+    ///
+    /// [`LM36011::new`] and [`LM36011::new_limited`] seed the cache with IVFM and the thermal
+    /// scale-back enabled instead, which the first [`LM36011::write_status`] programs. The
+    /// Device ID Register is not seeded; it is read by [`LM36011::read_status`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver: LM36011<_> = LM36011::with_por_defaults(i2c);
+    /// driver.set_torch_current_ma(100)?; // the Enable Register can be left as it is
+    /// ```
+    pub fn with_por_defaults(i2c: I2C) -> Self {
+        let mut driver = Self::new_limited(i2c);
+        let por = persist::Settings::POWER_ON_RESET;
+        driver.enable_flags = por.enable;
+        driver.config_flags = por.config;
+        driver.flash_brightness_flags = por.flash_brightness;
+        driver.torch_brightness_flags = por.torch_brightness;
+
+        driver
+    }
+
+    /// Sets the flash current of the LM36011 device.
+    ///
+    /// This function configures the flash current of the LM36011 by writing to the
+    /// `LEDFlashBrightnessRegister`. The desired current value is passed as an argument.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The desired flash current value to be set. The exact range and interpretation
+    ///   of this value should be based on the LM36011 documentation.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the operation was successful.
+    /// * `Err(E)` if there was an error during the operation. The error type `E` is determined by the I2C interface.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Some initialization to get the device instance
+    /// //(I2C needs to be initialized first)
+    /// let mut driver = lm36011::LM36011::new(i2c); // Some initialization to get the device instance
+    /// match driver.set_flash_current(285.0) {
+    ///     Ok(_) => println!("Flash current set successfully"),
+    ///     Err(e) => eprintln!("Error setting flash current: {:?}", e),
+    /// }
+    /// ```
+    pub fn set_flash_current_hex(&mut self, current: u8) -> Result<(), LM36011Error<E>> {
+        self.write_flash_code(current)?;
+        self.resume_mode()
+    }
+
+    /// Sets the flash current of the LM36011 device.
+    ///
+    /// This function configures the flash current of the LM36011 by writing to the
+    /// `LEDFlashBrightnessRegister`. The desired current value is passed as an argument.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The desired flash current value to be set in mA (0 - `MAX_FLASH_MA`). The value is
+    ///   converted to the 7-bit brightness code with the datasheet formula and rounded down, see
+    ///   [`LM36011::plan_flash_current`] to preview the code and resulting current. With the
+    ///   `libm` feature it is rounded to the nearest µA first, so a value from
+    ///   [`flash_currents`] always selects its own code.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f32)` with the quantized current actually programmed in mA (0 when auto-standby
+    ///   switched the output off).
+    /// * `Err(E)` if there was an error during the operation. The error type `E` is determined by the I2C interface.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Some initialization to get the device instance
+    /// //(I2C needs to be initialized first)
+    /// let mut driver = lm36011::LM36011::new(i2c); // Some initialization to get the device instance
+    /// match driver.set_flash_current(150.0) {
+    ///     Ok(actual) => println!("Flash current set to {} mA", actual),
+    ///     Err(e) => eprintln!("Error setting flash current: {:?}", e),
+    /// }
+    /// ```
+    #[cfg(all(feature = "float", not(target_arch = "avr")))]
+    pub fn set_flash_current(&mut self, current: f32) -> Result<f32, LM36011Error<E>> {
+        if self.auto_standby && current == 0.0 {
+            self.standby_for_zero()?;
+            return Ok(0.0);
+        }
+
+        // take in the current in mA (f32) and convert it to a brightness code
+        let plan = self.plan_flash_current(current)?;
+
+        self.write_flash_code(plan.code)?;
+        self.resume_mode()?;
+
+        Ok(plan.actual_ma)
+    }
+
+    /// Sets a flash current that is known at compile time.
+    ///
+    /// The current is checked against `MAX_FLASH_MA` when the program is compiled, so a constant
+    /// above the board limit is a build error rather than a runtime `CurrentOutOfRange`. The
+    /// current is converted with integer math and rounded down to the nearest brightness code.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver: LM36011<_, 800> = LM36011::new_limited(i2c);
+    /// driver.set_flash_current_const::<750>()?;
+    /// // driver.set_flash_current_const::<1000>()?; // does not compile
+    /// ```
+    pub fn set_flash_current_const<const MA: u16>(&mut self) -> Result<(), LM36011Error<E>> {
+        const {
+            assert!(
+                MA <= MAX_FLASH_MA,
+                "flash current is above the MAX_FLASH_MA board limit"
+            )
+        };
+
+        if self.auto_standby && MA == 0 {
+            return self.standby_for_zero();
+        }
+
+        self.write_flash_code(FLASH_SCALE.code_floor(MA as u32 * 1000))?;
+        self.resume_mode()
+    }
+
+    /// Sets the torch (and IR) current of the LM36011 device.
+    ///
+    /// The torch equivalent of [`LM36011::set_flash_current`]: the current is converted to the
+    /// 7-bit torch code with the datasheet formula (2.94 mA per step from 2.4 mA) and rounded
+    /// down, see [`LM36011::plan_torch_current`]. The reserved bit of the Torch Brightness
+    /// Register is preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The desired torch current in mA (0 - 376 mA).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f32)` with the quantized current actually programmed in mA (0 when auto-standby
+    ///   switched the output off).
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the current is above 376 mA (or the active
+    ///   torch limit) and the range policy is [`RangePolicy::Error`].
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let actual = driver.set_torch_current(100.0)?; // 99.4 mA
+    /// ```
+    #[cfg(all(feature = "float", not(target_arch = "avr")))]
+    pub fn set_torch_current(&mut self, current: f32) -> Result<f32, LM36011Error<E>> {
+        if self.auto_standby && current == 0.0 {
+            self.standby_for_zero()?;
+            return Ok(0.0);
+        }
+
+        let plan = self.plan_torch_current(current)?;
+
+        self.write_torch_code(plan.code)?;
+        self.resume_mode()?;
+
+        Ok(plan.actual_ma)
+    }
+
+    /// Sets the torch (and IR) current from a raw 7-bit brightness code.
+    ///
+    /// The torch equivalent of [`LM36011::set_flash_current_hex`]. The code is written to the
+    /// LED Torch Brightness Register (0x04) with the reserved bit preserved, and the cached
+    /// `torch_brightness_flags` are updated.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The brightness code, `0x00` (2.4 mA) to `0x7F` (376 mA).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the operation was successful.
+    /// * `Err(LM36011Error::InvalidInput)` if `level` does not fit in 7 bits.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the code is above the active torch limit and
+    ///   the range policy is [`RangePolicy::Error`].
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_torch_level(0x3F)?; // 188 mA
+    /// ```
+    pub fn set_torch_level(&mut self, level: u8) -> Result<(), LM36011Error<E>> {
+        if level > MAX_BRIGHTNESS_CODE {
+            return Err(LM36011Error::InvalidInput);
+        }
+
+        self.write_torch_code(level)?;
+        self.resume_mode()
+    }
+
+    /// Sets the flash current in mA with integer math.
+    ///
+    /// Picks the same code as `set_flash_current` (rounded down) without any floating point, so
+    /// FPU-less targets such as Cortex-M0/M0+ do not pull in soft-float routines. Unlike the
+    /// `f32` setters it is available without the `float` feature.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the operation was successful.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the current is above the flash limits and the
+    ///   range policy is [`RangePolicy::Error`].
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_flash_current_ma(1200)?;
+    /// ```
+    pub fn set_flash_current_ma(&mut self, ma: u16) -> Result<(), LM36011Error<E>> {
+        if self.auto_standby && ma == 0 {
+            return self.standby_for_zero();
+        }
+
+        let code = self.limit_ma(ma, self.flash_scale())?;
+        self.write_flash_code(code)?;
+        self.resume_mode()
+    }
+
+    /// Sets the flash current in mA, choosing how it is rounded to a brightness step.
+    ///
+    /// [`Rounding::Floor`] matches [`LM36011::set_flash_current_ma`] and can undershoot by
+    /// almost a full 11.7 mA step; exposure-calibrated camera flashes usually want
+    /// [`Rounding::Nearest`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_flash_current_with(500, Rounding::Nearest)?; // 503 mA instead of 491 mA
+    /// ```
+    pub fn set_flash_current_with(
+        &mut self,
+        ma: u16,
+        rounding: Rounding,
+    ) -> Result<(), LM36011Error<E>> {
+        if self.auto_standby && ma == 0 {
+            return self.standby_for_zero();
+        }
+
+        let code = self.limit_ma_rounded(ma, self.flash_scale(), rounding)?;
+        self.write_flash_code(code)?;
+        self.resume_mode()
+    }
+
+    /// Sets the torch (and IR) current in mA, choosing how it is rounded to a brightness step.
+    ///
+    /// See [`LM36011::set_flash_current_with`].
+    pub fn set_torch_current_with(
+        &mut self,
+        ma: u16,
+        rounding: Rounding,
+    ) -> Result<(), LM36011Error<E>> {
+        if self.auto_standby && ma == 0 {
+            return self.standby_for_zero();
+        }
+
+        let code = self.limit_ma_rounded(ma, self.torch_scale(), rounding)?;
+        self.write_torch_code(code)?;
+        self.resume_mode()
+    }
+
+    /// Sets the torch (and IR) current in mA with integer math.
+    ///
+    /// The integer counterpart of `set_torch_current`, see
+    /// [`LM36011::set_flash_current_ma`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_torch_current_ma(100)?;
+    /// ```
+    pub fn set_torch_current_ma(&mut self, ma: u16) -> Result<(), LM36011Error<E>> {
+        if self.auto_standby && ma == 0 {
+            return self.standby_for_zero();
+        }
+
+        let code = self.limit_ma(ma, self.torch_scale())?;
+        self.write_torch_code(code)?;
+        self.resume_mode()
+    }
+
+    /// Sets the torch (and IR) brightness as a percentage of the torch code range.
+    ///
+    /// 0 % is the lowest code and 100 % the highest code within the torch limits in force,
+    /// with the steps in between spread evenly over the codes (rounded to the nearest code).
+    /// Unlike [`Brightness::Percent`], which is a percentage of the current, every percent
+    /// changes the brightness by roughly the same number of steps, which suits a simple
+    /// brightness knob. With auto-standby enabled, 0 % drops to standby instead.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the operation was successful.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if `percent` is above 100 and the range policy
+    ///   is [`RangePolicy::Error`]; [`RangePolicy::ClampToLimit`] treats it as 100 %.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_torch_brightness_percent(50)?; // code 0x40, 190 mA
+    /// ```
+    pub fn set_torch_brightness_percent(&mut self, percent: u8) -> Result<(), LM36011Error<E>> {
+        if self.auto_standby && percent == 0 {
+            return self.standby_for_zero();
+        }
+
+        let code = self.percent_code(percent, self.torch_scale())?;
+        self.write_torch_code(code)?;
+        self.resume_mode()
+    }
+
+    /// Sets the flash brightness as a percentage of the flash code range.
+    ///
+    /// Behaves like [`LM36011::set_torch_brightness_percent`] against the flash limits.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_flash_brightness_percent(100)?; // code 0x7F, 1500 mA
+    /// ```
+    pub fn set_flash_brightness_percent(&mut self, percent: u8) -> Result<(), LM36011Error<E>> {
+        if self.auto_standby && percent == 0 {
+            return self.standby_for_zero();
+        }
+
+        let code = self.percent_code(percent, self.flash_scale())?;
+        self.write_flash_code(code)?;
+        self.resume_mode()
+    }
+
+    /// Maps a percentage onto the codes `0..=scale.max_code()`, rounded to the nearest code.
+    fn percent_code(&self, percent: u8, scale: CurrentScale) -> Result<u8, LM36011Error<E>> {
+        if percent > 100 {
+            return self.out_of_range(scale);
+        }
+
+        let max_code = scale.max_code() as u16;
+        Ok(((max_code * percent as u16 + 50) / 100) as u8)
+    }
+
+    /// Writes a flash brightness code, keeping the cached thermal scale-back setting.
+    ///
+    /// Codes above the flash current limits are handled according to the range policy.
+    fn write_flash_code(&mut self, code: u8) -> Result<(), LM36011Error<E>> {
+        let code = self.limit_code(code, self.flash_scale())?;
+        self.check_interlock(code)?;
+
+        // convert the u8 value to a LedFlashBrightnessFlags
+        let mut brightness_bitflags = LedFlashBrightnessFlags::from_bits_truncate(code);
+
+        // Ensure the thermal current scale-back bit remains set/not set
+        brightness_bitflags.set(
             LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED,
             self.flash_brightness_flags
                 .contains(LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED),
         );
 
-        // Use the set_register function to set the flash current
-        self.set_register(Register::LEDFlashBrightnessRegister, brightness_flags)?;
+        // Use the set_register function to set the flash current
+        self.set_register(
+            Register::LEDFlashBrightnessRegister,
+            brightness_bitflags.bits(),
+        )?;
+
+        // update internal struct state
+        self.flash_brightness_flags = brightness_bitflags;
+
+        Ok(())
+    }
+
+    /// Previews the flash brightness code for a requested current without touching the device.
+    ///
+    /// The requested current is converted with the same rounding used by
+    /// [`LM36011::set_flash_current`], so the returned plan is exactly what a subsequent call
+    /// would program.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The requested flash current in mA (0 - `MAX_FLASH_MA`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CurrentPlan)` with the code, the resulting current and the error against the request.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the request is outside the flash range.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let driver = lm36011::LM36011::new(i2c);
+    /// let plan = driver.plan_flash_current(500.0)?;
+    /// println!("code {:#04x} gives {} mA ({} mA off)", plan.code, plan.actual_ma, plan.error_ma);
+    /// ```
+    #[cfg(all(feature = "float", not(target_arch = "avr")))]
+    pub fn plan_flash_current(&self, current: f32) -> Result<CurrentPlan, LM36011Error<E>> {
+        self.limit_plan(current, self.flash_scale())
+    }
+
+    /// Plans a flash current and hardware time-out that deliver the requested flash energy.
+    ///
+    /// The shortest time-out whose required current does not exceed `max_current` (and the
+    /// flash current limit) is chosen, keeping the exposure as short as possible, and the
+    /// current is rounded to the nearest brightness code. Nothing is written to the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `energy_ma_ms` - The requested flash energy as current times duration in mA·ms.
+    /// * `max_current` - The highest flash current in mA the plan may use.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ExposurePlan)` with the code, time-out and realized energy.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the energy cannot be reached within 1600 ms
+    ///   at `max_current` (unless clamped by [`RangePolicy::ClampToLimit`]), or `max_current` is
+    ///   below the lowest flash current.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // 100 mA·s of flash, at most 1 A
+    /// let plan = driver.plan_exposure(100_000, 1000)?;
+    /// assert_eq!(plan.timeout, FlashTimeout::Ms120);
+    /// ```
+    pub fn plan_exposure(
+        &self,
+        energy_ma_ms: u32,
+        max_current: u16,
+    ) -> Result<ExposurePlan, LM36011Error<E>> {
+        let scale = self.flash_scale();
+        let max_ua = (max_current as u32 * 1000).min(scale.max_ua);
+        if max_ua < scale.offset_ua {
+            return Err(LM36011Error::CurrentOutOfRange);
+        }
+        let max_code = scale.code_floor(max_ua);
+
+        // energy * 1000 / ms, rounded up, split so that it stays within 32 bits
+        let required_ua = |ms: u32| {
+            (energy_ma_ms / ms)
+                .saturating_mul(1000)
+                .saturating_add((energy_ma_ms % ms * 1000).div_ceil(ms))
+        };
+        let (timeout, required_ua) = FlashTimeout::ALL
+            .into_iter()
+            .map(|timeout| (timeout, required_ua(timeout.ms() as u32)))
+            .find(|(_, required_ua)| *required_ua <= scale.ua(max_code))
+            .or(match self.range_policy {
+                RangePolicy::Error => None,
+                RangePolicy::ClampToLimit => Some((FlashTimeout::Ms1600, scale.ua(max_code))),
+            })
+            .ok_or(LM36011Error::CurrentOutOfRange)?;
+
+        let floor = scale.code_floor(required_ua).min(max_code);
+        let code = if floor < max_code
+            && scale.ua(floor + 1).abs_diff(required_ua) < scale.ua(floor).abs_diff(required_ua)
+        {
+            floor + 1
+        } else {
+            floor
+        };
+
+        Ok(ExposurePlan {
+            code,
+            current_ma: (scale.ua(code) / 1000) as u16,
+            timeout,
+            energy_ma_ms: scale.ua(code) * timeout.ms() as u32 / 1000,
+        })
+    }
+
+    /// Previews the torch brightness code for a requested current without touching the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The requested torch current in mA (0 - 376).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CurrentPlan)` with the code, the resulting current and the error against the request.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the request is outside the torch range.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let driver = lm36011::LM36011::new(i2c);
+    /// let plan = driver.plan_torch_current(100.0)?;
+    /// println!("code {:#04x} gives {} mA", plan.code, plan.actual_ma);
+    /// ```
+    #[cfg(all(feature = "float", not(target_arch = "avr")))]
+    pub fn plan_torch_current(&self, current: f32) -> Result<CurrentPlan, LM36011Error<E>> {
+        self.limit_plan(current, self.torch_scale())
+    }
+
+    /// Retrieves the device ID from the LM36011.
+    ///
+    /// This function reads the `DeviceIdRegister` of the LM36011 device to obtain its ID.
+    /// It uses the I2C `write_read` method to request and retrieve the device ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(u8)` containing the device ID if the read operation was successful.
+    /// * `Err(E)` if there was an error during the read operation. The error type `E` is determined by the I2C interface.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Some initialization to get the device instance
+    /// //(I2C needs to be initialized first)
+    /// let mut driver = lm36011::LM36011::new(i2c); // Some initialization to get the device instance
+    /// match driver.get_device_id() {
+    ///     Ok(id) => println!("LM36011 device ID: {}", id),
+    ///     Err(e) => eprintln!("Error reading device ID: {:?}", e),
+    /// }
+    /// ```
+    pub fn get_device_id(&mut self) -> Result<u8, E> {
+        let mut buffer = [0u8; 1];
+        self.read_registers(Register::DeviceIdRegister, &mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    /// Retrieves the value of a specified register from the device.
+    ///
+    /// This function reads a byte of data from a specified register on the LM36011 device.
+    /// It uses the I2C `write_read` method to request and retrieve the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `reg` - The register from which the data should be read. This is specified using the `Register` enum.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(u8)` containing the byte value read from the specified register if the read operation was successful.
+    /// * `Err(E)` if there was an error during the read operation. The error type `E` is determined by the I2C interface.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Some initialization to get the device instance
+    /// //(I2C needs to be initialized first)
+    /// let mut driver = lm36011::LM36011::new(i2c);
+    /// match driver.get_register(Register::DeviceIdRegister) {
+    ///     Ok(value) => println!("Register value: {}", value),
+    ///     Err(e) => eprintln!("Error reading register: {:?}", e),
+    /// }
+    /// ```
+    pub fn get_register(&mut self, reg: Register) -> Result<u8, E> {
+        let flags = matches!(reg, Register::FlagsRegister);
+        let mut buffer = [0u8; 1];
+        self.read_registers(reg, &mut buffer)?;
+
+        // the Flags Register clears on read, keep what was seen
+        if flags {
+            self.record_flags(FlagRegisterFlags::from_bits_truncate(buffer[0]))?;
+        }
+
+        Ok(buffer[0])
+    }
+
+    /// Sets the value of a specified register on the device.
+    ///
+    /// This function writes a given data byte to a specified register on the LM36011 device.
+    /// It uses the I2C `write` method to send the data.
+    ///
+    /// The byte is written as given: the current limits, the fault cut-off and the eye-safety
+    /// interlock (see [`LM36011::set_interlock_threshold`]) are not checked, so a flash current
+    /// or flash mode written here bypasses the interlock. Use the current and mode setters
+    /// where the interlock must hold.
+    ///
+    /// # Arguments
+    ///
+    /// * `reg` - The register to which the data should be written. This is specified using the `Register` enum.
+    /// * `data` - The data byte to be written to the specified register.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the write operation was successful.
+    /// * `Err(E)` if there was an error during the write operation. The error type `E` is determined by the I2C interface.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Some initialization to get the device instance
+    /// //(I2C needs to be initialized first)
+    /// let mut driver = lm36011::LM36011::new(i2c);
+    /// let result = driver.set_register(Register::DeviceIdRegister, 0x01);
+    /// if result.is_err() {
+    ///     // Handle the error
+    /// }
+    /// ```
+    #[inline]
+    pub fn set_register(&mut self, reg: Register, data: u8) -> Result<(), LM36011Error<E>> {
+        let buffer: [u8; 2] = [reg as u8, data];
+        self.write_registers(&buffer)
+            .map_err(LM36011Error::I2CError)
+    }
+
+    /// Writes the adjacent Configuration and LED Flash Brightness Registers in one transaction.
+    fn set_config_and_flash(&mut self, config: u8, brightness: u8) -> Result<(), LM36011Error<E>> {
+        let buffer = [Register::ConfigurationRegister as u8, config, brightness];
+        self.write_registers(&buffer)
+            .map_err(LM36011Error::I2CError)
+    }
+
+    /// Writes `bytes`, a register address followed by the values of consecutive registers.
+    fn write_registers(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.write_registers_untraced(bytes)?;
+        trace_registers("write", bytes[0], &bytes[1..]);
+        Ok(())
+    }
+
+    /// Writes like `write_registers` without a trace record, for the fire paths, which do no
+    /// formatting between the trigger and the write.
+    #[inline]
+    fn write_registers_untraced(&mut self, bytes: &[u8]) -> Result<(), E> {
+        self.i2c.write(LM36011_I2C_ADDRESS, bytes)?;
+        self.track_flash_code(bytes[0], &bytes[1..]);
+        Ok(())
+    }
+
+    /// Returns the Enable Register value that starts a flash over I2C at the flash code
+    /// `code`: flash mode with the STROBE input disabled, keeping the other cached and
+    /// reserved bits.
+    ///
+    /// Fails with [`LM36011Error::FaultShutdown`] while the fault cut-off holds the output off,
+    /// and with [`LM36011Error::InterlockEngaged`] if `code` is above the interlock threshold
+    /// without an unlock, which is consumed otherwise. A flash armed for the STROBE input has
+    /// passed the interlock already.
+    #[inline]
+    fn fire_enable(&mut self, code: u8) -> Result<EnableRegisterFlags, LM36011Error<E>> {
+        let enable = self.check_fire_enable(code)?;
+        if !self.strobe_armed() {
+            self.check_flash_interlock(enable, code)?;
+        }
+
+        Ok(enable)
+    }
+
+    /// Like [`LM36011::fire_enable`], but leaves an interlock unlock in place, so that a flash
+    /// that is only fired later (e.g. after a trigger edge) can be checked up front.
+    #[inline]
+    fn check_fire_enable(&self, code: u8) -> Result<EnableRegisterFlags, LM36011Error<E>> {
+        let mut enable = self.enable_flags;
+        enable.remove(EnableRegisterFlags::STROBE_ENABLE | EnableRegisterFlags::MODE_MASK);
+        enable.insert(EnableRegisterFlags::MODE_FLASH);
+        self.check_cutoff(enable)?;
+        if !self.strobe_armed() && !self.unlocked && self.above_interlock(code) {
+            return Err(LM36011Error::InterlockEngaged);
+        }
+
+        Ok(EnableRegisterFlags::from_bits_truncate(
+            self.with_reserved(Register::EnableRegister, enable.bits()),
+        ))
+    }
+
+    /// Writes an Enable Register value from [`LM36011::fire_enable`], like `write_enable` but
+    /// without a trace record.
+    #[inline]
+    fn write_fire_enable(&mut self, enable: EnableRegisterFlags) -> Result<(), LM36011Error<E>> {
+        self.write_registers_untraced(&[Register::EnableRegister as u8, enable.bits()])
+            .map_err(LM36011Error::I2CError)?;
+        self.fired(enable);
+
+        Ok(())
+    }
+
+    /// Caches an Enable Register value from [`LM36011::fire_enable`] once it has been written.
+    #[inline]
+    fn fired(&mut self, enable: EnableRegisterFlags) {
+        self.enable_flags = enable;
+        self.suspended_mode = None;
+    }
+
+    /// Reads consecutive registers, starting at `first`, into `buffer`.
+    fn read_registers(&mut self, first: Register, buffer: &mut [u8]) -> Result<(), E> {
+        self.i2c
+            .write_read(LM36011_I2C_ADDRESS, &[first as u8], buffer)?;
+        self.track_flash_code(first as u8, buffer);
+        trace_registers("read", first as u8, buffer);
+        Ok(())
+    }
+
+    /// Notes the flash brightness code on the device if `values`, starting at register
+    /// `first`, include the Flash Brightness Register.
+    #[inline]
+    fn track_flash_code(&mut self, first: u8, values: &[u8]) {
+        let index = (Register::LEDFlashBrightnessRegister as u8).wrapping_sub(first) as usize;
+        if let Some(value) = values.get(index) {
+            self.device_flash_code = value & MAX_BRIGHTNESS_CODE;
+        }
+    }
+
+    /// Reads all the registers of the LM36011 and saves the register states to the respective bitflag structs.
+    ///
+    /// This function performs a single I2C read operation starting from the `EnableRegister` and reads 6 bytes,
+    /// which correspond to the 6 registers of the LM36011. The read values are then saved to the respective
+    /// bitflag structs for easy access and manipulation.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the I2C read operation is successful.
+    /// * `Err(E)` if the I2C read operation fails, where `E` is the error type of the I2C operations.
+    ///
+    /// The Flags Register clears on read, so its bits are merged into the sticky
+    /// `flag_register_flags` rather than replacing them; see [`LM36011::take_faults`].
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011::new(i2c_instance);
+    ///
+    /// if let Err(e) = driver.read_status() {
+    ///     // Handle the error `e` here.
+    /// }
+    /// ```
+    pub fn read_status(&mut self) -> Result<(), LM36011Error<E>> {
+        self.read_all().map(|_| ())
+    }
+
+    /// Reads all registers like [`LM36011::read_status`] and returns the flags of this read.
+    fn read_all(&mut self) -> Result<FlagRegisterFlags, LM36011Error<E>> {
+        // Read all 6 LM36011 registers
+        let mut buffer = [0u8; 6];
+        self.read_registers(Register::EnableRegister, &mut buffer)
+            .map_err(LM36011Error::I2CError)?;
+
+        // Save registers to the struct
+        self.enable_flags = EnableRegisterFlags::from_bits_truncate(buffer[0]);
+        self.config_flags = ConfigurationRegisterFlags::from_bits_truncate(buffer[1]);
+        self.flash_brightness_flags = LedFlashBrightnessFlags::from_bits_truncate(buffer[2]);
+        self.torch_brightness_flags = LedTorchBrightnessFlags::from_bits_truncate(buffer[3]);
+        let flags = FlagRegisterFlags::from_bits_truncate(buffer[4]);
+        self.record_flags(flags).map_err(LM36011Error::I2CError)?;
+        self.device_id = DeviceIdFlags::from_bits_truncate(buffer[5]);
+        self.quirks = Quirks::for_revision(SiliconRevision::from_register(buffer[5]));
+        if let Some(event) = self.compare_identity(DeviceInfo::from_register(buffer[5])) {
+            self.identity_event = Some(event);
+        }
+        self.report_cutoff()?;
+
+        Ok(flags)
+    }
+
+    /// Writes the bitflags settings to the LM36011 device.
+    ///
+    /// This function will take the current settings stored in the bitflag structs and write them to the
+    /// respective registers on the LM36011 device using I2C.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// // Assuming `i2c` is an initialized I2C instance`
+    /// let mut driver = LM36011::new(i2c_instance);
+    /// // Modify some settings
+    /// driver.enable_flags.insert(EnableRegisterFlags::MODE_TORCH);
+    /// driver.config_flags.insert(ConfigurationRegisterFlags::IVFM_3_4V);
+    ///
+    /// // Write the modified settings to the device
+    /// match lm36011.write_status() {
+    ///     Ok(_) => println!("Settings written successfully!"),
+    ///     Err(e) => println!("Failed to write settings: {:?}", e),
+    /// }
+    /// ```
+    ///
+    /// The cached brightness codes are checked like those of the current setters: a code above
+    /// the flash or torch limits in force fails with [`LM36011Error::CurrentOutOfRange`], and
+    /// raising the flash current above the interlock threshold, or writing flash mode at such a
+    /// current, needs an unlock token (see [`LM36011::unlock`]). Nothing is written when a
+    /// check fails.
+    pub fn write_status(&mut self) -> Result<(), LM36011Error<E>> {
+        self.check_currents(
+            self.enable_flags,
+            self.flash_brightness_flags.bits(),
+            self.torch_brightness_flags.bits(),
+        )?;
+        self.write_cached_registers()
+    }
+
+    /// Writes the cached Enable, Configuration and brightness registers without checking the
+    /// brightness codes.
+    fn write_cached_registers(&mut self) -> Result<(), LM36011Error<E>> {
+        self.check_cutoff(self.enable_flags)?;
+
+        // create a buffer with all of the settings
+        let buffer = [
+            0x01,
+            self.enable_flags.bits(),
+            self.config_flags.bits(),
+            self.flash_brightness_flags.bits(),
+            self.torch_brightness_flags.bits(),
+            //self.flag_register_flags.bits(),
+            //self.device_id.bits(),
+        ];
+
+        self.write_registers(&buffer)
+            .map_err(LM36011Error::I2CError)
+    }
+
+    /// Performs a software reset on the LM36011 device.
+    ///
+    /// This function sends a specific command to the LM36011 device to initiate a software reset.
+    /// The reset command sets the RESET bit of the Device ID Register (0x06); the reserved bit keeps
+    /// its cached value.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let mut device = LM36011::new(i2c_instance);
+    ///
+    /// match device.software_reset() {
+    ///     Ok(_) => println!("Software reset successful!"),
+    ///     Err(e) => println!("Software reset failed with error: {:?}", e),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` variant of `LM36011Error` if there's an I2C communication error.
+    pub fn software_reset(&mut self) -> Result<(), LM36011Error<E>> {
+        let reset = self.with_reserved(
+            Register::DeviceIdRegister,
+            DeviceIdFlags::SOFTWARE_RESET.bits(),
+        );
+        let buffer = [Register::DeviceIdRegister as u8, reset];
+        self.write_registers(&buffer)
+            .map_err(LM36011Error::I2CError)
+    }
+
+    /// Verifies the device ID of the LM36011.
+    ///
+    /// This function reads the current status of the LM36011, including its device ID,
+    /// and then checks that the device ID matches the selected [`Variant`] and the silicon
+    /// revision mask matches the expected value.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if the device ID matches the expected value.
+    /// * `Err(LM36011Error::InvalidInput)` if the device ID does not match the expected value.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011::new(i2c);
+    /// match driver.verify_device_id() {
+    ///     Ok(true) => println!("Device ID verified!"),
+    ///     Err(LM36011Error::DeviceIDError) => println!("Device ID does not match!"),
+    ///     Err(LM36011Error::I2CError(_)) => println!("Error verifying device ID due to I2C communication"),
+    ///     _ => println!("Some other error occurred"),
+    /// }
+    /// ```
+    pub fn verify_device_id(&mut self) -> Result<bool, LM36011Error<E>> {
+        match self.read_status() {
+            Ok(_) => (),
+            Err(e) => return Err(e),
+        }
+
+        // Check if the read value matches the expected device ID
+        if DeviceId::from_register(self.device_id.bits()) == self.variant.device_id()
+            && self.device_id & DeviceIdFlags::SILICON_REVISION_MASK
+                == DeviceIdFlags::from_bits_truncate(0x01)
+        {
+            Ok(true)
+        } else {
+            Err(LM36011Error::DeviceIDError)
+        }
+    }
+
+    /// Verifies that an LED is connected and healthy by firing a short test flash.
+    ///
+    /// The flash is fired at the minimum flash current (code 0, ~11 mA) with the shortest
+    /// hardware time-out (40 ms). Once the pulse has ended the Flags register is inspected for
+    /// a VLED short or a fault that interrupted the pulse. The Configuration, LED Flash Brightness
+    /// and Enable registers are restored from the cached state afterwards.
+    ///
+    /// The LM36011 has no open-LED flag, so an open LED is only reported when it leads to one
+    /// of the checked faults.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - A delay provider used to wait for the test pulse to time out.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LedCheck)` describing the outcome of the test pulse.
+    /// * `Err(LM36011Error::FaultShutdown)` if the fault cut-off holds the output off, or the
+    ///   test pulse tripped it.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011::new(i2c);
+    /// match driver.led_connectivity_check(&mut delay)? {
+    ///     LedCheck::Healthy => println!("LED OK"),
+    ///     LedCheck::Shorted => println!("LED output shorted!"),
+    ///     LedCheck::Fault(flags) => println!("LED check failed: {:?}", flags),
+    /// }
+    /// ```
+    pub fn led_connectivity_check<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<LedCheck, LM36011Error<E>> {
+        self.check_cutoff(EnableRegisterFlags::MODE_FLASH)?;
+
+        // clear any stale flags, the Flags register is cleared on read
+        if self.quirks.flags_clear_on_read {
+            self.get_register(Register::FlagsRegister)
+                .map_err(LM36011Error::I2CError)?;
+            self.report_cutoff()?;
+        }
+
+        // minimum current with the shortest time-out, set up before entering flash mode
+        let config = self.config_flags.bits() & !FLASH_TIMEOUT_MASK;
+        let brightness = self.flash_brightness_flags.bits() & !MAX_BRIGHTNESS_CODE;
+        let enable = self.with_reserved(
+            Register::EnableRegister,
+            (self.enable_flags.bits() & EnableRegisterFlags::IVFM_ENABLE.bits())
+                | EnableRegisterFlags::MODE_FLASH.bits(),
+        );
+        self.set_config_and_flash(config, brightness)?;
+        self.set_register(Register::EnableRegister, enable)?;
+
+        delay.delay_ms(LED_CHECK_WAIT_MS);
+
+        let flags = self
+            .get_register(Register::FlagsRegister)
+            .map_err(LM36011Error::I2CError)?;
+
+        // restore the cached configuration, enable last so no mode is entered early; a cut-off
+        // tripped by the pulse has already written the Enable Register
+        self.set_config_and_flash(self.config_flags.bits(), self.flash_brightness_flags.bits())?;
+        self.report_cutoff()?;
+        self.restore_enable()?;
+
+        Ok(LedCheck::from_flags(FlagRegisterFlags::from_bits_truncate(
+            flags,
+        )))
+    }
+
+    /// Checks the LED for a VLED short with a brief torch pulse at the minimum torch current.
+    ///
+    /// Lighter than [`LM36011::led_connectivity_check`], which fires a flash: the torch is
+    /// turned on at code 0 (~2.4 mA) for a few milliseconds, then the Flags register is read.
+    /// The Enable and LED Torch Brightness registers are restored from the cached state
+    /// afterwards, which suits production-line and power-on checks.
+    ///
+    /// The LM36011 has no open-LED flag, so an open LED is only reported when it leads to one
+    /// of the checked faults.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LedCheck)` describing the outcome of the torch pulse.
+    /// * `Err(LM36011Error::FaultShutdown)` if the fault cut-off holds the output off, or a
+    ///   stale fault or the torch pulse tripped it.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if driver.self_test_led(&mut delay)? != LedCheck::Healthy {
+    ///     status_led.blink_error();
+    /// }
+    /// ```
+    pub fn self_test_led<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<LedCheck, LM36011Error<E>> {
+        self.check_cutoff(EnableRegisterFlags::MODE_TORCH)?;
+
+        // clear any stale flags, the Flags register is cleared on read
+        if self.quirks.flags_clear_on_read {
+            self.get_register(Register::FlagsRegister)
+                .map_err(LM36011Error::I2CError)?;
+            self.report_cutoff()?;
+        }
+
+        let brightness = self.torch_brightness_flags.bits() & !MAX_BRIGHTNESS_CODE;
+        let enable = self.with_reserved(
+            Register::EnableRegister,
+            (self.enable_flags.bits() & EnableRegisterFlags::IVFM_ENABLE.bits())
+                | EnableRegisterFlags::MODE_TORCH.bits(),
+        );
+        self.set_register(Register::LEDTorchBrightnessRegister, brightness)?;
+        self.set_register(Register::EnableRegister, enable)?;
+
+        delay.delay_ms(SELF_TEST_WAIT_MS);
+
+        let flags = self
+            .get_register(Register::FlagsRegister)
+            .map_err(LM36011Error::I2CError)?;
+
+        // restore the cached mode first, so the torch does not light at the cached current; a
+        // cut-off tripped by the pulse has already put the device in standby
+        let cutoff = self.report_cutoff();
+        if cutoff.is_ok() {
+            self.restore_enable()?;
+        }
+        self.set_register(
+            Register::LEDTorchBrightnessRegister,
+            self.torch_brightness_flags.bits(),
+        )?;
+        cutoff?;
+
+        Ok(LedCheck::from_flags(FlagRegisterFlags::from_bits_truncate(
+            flags,
+        )))
+    }
+
+    /// Switches the torch on with a software soft-start.
+    ///
+    /// The torch brightness is stepped from its current level (or the minimum level when the
+    /// output is off) to `target_ma` in `soft_start.steps` equal steps spread over
+    /// `soft_start.duration_ms`. The hardware torch ramp bit in the Configuration Register is
+    /// left as configured and still smooths each individual step.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_ma` - The final torch current in mA (0 - 376).
+    /// * `soft_start` - The duration and number of steps of the ramp.
+    /// * `delay` - A delay provider used to pace the steps.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once the target current is reached.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if `target_ma` is above the torch range.
+    /// * `Err(LM36011Error::InvalidInput)` if `soft_start.steps` is zero.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011::new(i2c);
+    /// // ramp to 200 mA over 50 ms in 10 steps
+    /// driver.soft_start_torch(200, SoftStart { duration_ms: 50, steps: 10 }, &mut delay)?;
+    /// ```
+    pub fn soft_start_torch<D: DelayMs<u16>>(
+        &mut self,
+        target_ma: u16,
+        soft_start: SoftStart,
+        delay: &mut D,
+    ) -> Result<(), LM36011Error<E>> {
+        self.soft_start(
+            EnableRegisterFlags::MODE_TORCH,
+            target_ma,
+            soft_start,
+            delay,
+        )
+    }
+
+    /// Switches the IR drive on with a software soft-start.
+    ///
+    /// Works like [`LM36011::soft_start_torch`]; in IR mode the LED current is taken from the
+    /// torch brightness register, so `target_ma` uses the torch range (0 - 376 mA).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.soft_start_ir(300, SoftStart { duration_ms: 100, steps: 20 }, &mut delay)?;
+    /// ```
+    pub fn soft_start_ir<D: DelayMs<u16>>(
+        &mut self,
+        target_ma: u16,
+        soft_start: SoftStart,
+        delay: &mut D,
+    ) -> Result<(), LM36011Error<E>> {
+        self.soft_start(
+            EnableRegisterFlags::MODE_IR_DRIVE,
+            target_ma,
+            soft_start,
+            delay,
+        )
+    }
+
+    /// Ramps the torch brightness register to `target_ma` while in `mode`.
+    fn soft_start<D: DelayMs<u16>>(
+        &mut self,
+        mode: EnableRegisterFlags,
+        target_ma: u16,
+        soft_start: SoftStart,
+        delay: &mut D,
+    ) -> Result<(), LM36011Error<E>> {
+        let target = self.limit_ma(target_ma, self.torch_scale())?;
+        if soft_start.steps == 0 {
+            return Err(LM36011Error::InvalidInput);
+        }
+
+        // start from the current level if the torch or IR LED is already on, otherwise from the
+        // bottom; an ended or strobe-armed flash leaves the LED off
+        let on = Self::without_one_shot_flash(self.enable_flags) & EnableRegisterFlags::MODE_MASK;
+        let start =
+            if on == EnableRegisterFlags::MODE_TORCH || on == EnableRegisterFlags::MODE_IR_DRIVE {
+                self.torch_brightness_flags.bits() & MAX_BRIGHTNESS_CODE
+            } else {
+                0
+            };
+        self.write_torch_code(start)?;
+        self.write_mode(mode)?;
+
+        let interval = soft_start.duration_ms / soft_start.steps as u16;
+        let span = target as i16 - start as i16;
+        for step in 1..=soft_start.steps as i16 {
+            delay.delay_ms(interval);
+            let code = start as i16 + span * step / soft_start.steps as i16;
+            self.write_torch_code(code as u8)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a torch brightness code, keeping the cached reserved bit.
+    ///
+    /// Codes above the torch current limits are handled according to the range policy.
+    fn write_torch_code(&mut self, code: u8) -> Result<(), LM36011Error<E>> {
+        let code = self.limit_code(code, self.torch_scale())?;
+
+        let brightness = LedTorchBrightnessFlags::from_bits_truncate(
+            self.with_reserved(Register::LEDTorchBrightnessRegister, code),
+        );
+
+        self.set_register(Register::LEDTorchBrightnessRegister, brightness.bits())?;
+        self.torch_brightness_flags = brightness;
+
+        Ok(())
+    }
+
+    /// Replaces the reserved (RFU) bits of a value for `reg` with the cached ones.
+    ///
+    /// Reserved bits are never written as zero implicitly: they carry what the device reported
+    /// on the last [`LM36011::read_status`], so later silicon revisions assigning a meaning to
+    /// them keep their setting.
+    #[inline]
+    fn with_reserved(&self, reg: Register, value: u8) -> u8 {
+        let (reserved, cached) = match reg {
+            Register::EnableRegister => (
+                EnableRegisterFlags::ENABLE_REGISTER_RFU.bits(),
+                self.enable_flags.bits(),
+            ),
+            Register::LEDTorchBrightnessRegister => (
+                LedTorchBrightnessFlags::TORCH_BRIGHTNESS_RFU.bits(),
+                self.torch_brightness_flags.bits(),
+            ),
+            Register::DeviceIdRegister => {
+                (DeviceIdFlags::DEVICE_ID_RFU.bits(), self.device_id.bits())
+            }
+            _ => (0, 0),
+        };
+
+        (value & !reserved) | (cached & reserved)
+    }
+
+    /// Writes the mode bits of the Enable Register, keeping the other cached enable bits.
+    fn write_mode(&mut self, mode: EnableRegisterFlags) -> Result<(), LM36011Error<E>> {
+        let enable = EnableRegisterFlags::from_bits_truncate(
+            (self.enable_flags.bits() & !EnableRegisterFlags::MODE_MASK.bits())
+                | (mode.bits() & EnableRegisterFlags::MODE_MASK.bits()),
+        );
+
+        self.write_enable(enable)
+    }
+
+    /// Writes the cached Enable Register back after a test pulse, without an I2C-started
+    /// flash (see [`LM36011::without_one_shot_flash`]), and caches the value written.
+    fn restore_enable(&mut self) -> Result<(), LM36011Error<E>> {
+        let enable = Self::without_one_shot_flash(self.enable_flags);
+        self.set_register(Register::EnableRegister, enable.bits())?;
+        self.enable_flags = enable;
+
+        Ok(())
+    }
+
+    /// Returns `enable` without an I2C-started flash.
+    ///
+    /// Flash mode with the STROBE input disabled fires as soon as it is written, and the device
+    /// returns to standby once the flash ends, so a cached value in that state must never be
+    /// written back.
+    #[inline]
+    fn without_one_shot_flash(enable: EnableRegisterFlags) -> EnableRegisterFlags {
+        if enable.contains(EnableRegisterFlags::MODE_FLASH)
+            && !enable.contains(EnableRegisterFlags::STROBE_ENABLE)
+        {
+            enable - EnableRegisterFlags::MODE_MASK
+        } else {
+            enable
+        }
+    }
+
+    /// Writes the `mask` bits of the Enable Register, keeping the other cached enable bits.
+    ///
+    /// Unlike a mode change, this keeps the mode restore pending after an auto-standby. A
+    /// flash is never started: the cached mode is dropped to standby when it, or the result,
+    /// is flash mode without the STROBE input.
+    fn write_enable_field(
+        &mut self,
+        mask: EnableRegisterFlags,
+        bits: EnableRegisterFlags,
+    ) -> Result<(), LM36011Error<E>> {
+        let suspended = self.suspended_mode;
+        let cached = Self::without_one_shot_flash(self.enable_flags);
+        self.write_enable(Self::without_one_shot_flash(
+            (cached - mask) | (bits & mask),
+        ))?;
+        self.suspended_mode = suspended;
+
+        Ok(())
+    }
+
+    /// Writes the Enable Register and updates the cached copy.
+    ///
+    /// An explicit mode change cancels the mode restore pending after an auto-standby.
+    #[inline]
+    fn write_enable(&mut self, enable: EnableRegisterFlags) -> Result<(), LM36011Error<E>> {
+        self.check_cutoff(enable)?;
+        if !self.strobe_armed() {
+            self.check_flash_interlock(enable, self.device_flash_code)?;
+        }
+        let enable = EnableRegisterFlags::from_bits_truncate(
+            self.with_reserved(Register::EnableRegister, enable.bits()),
+        );
+        self.suspended_mode = None;
+        self.set_register(Register::EnableRegister, enable.bits())?;
+        self.enable_flags = enable;
+
+        Ok(())
+    }
+
+    /// Prepares the device for a flash using the given trigger mode.
+    ///
+    /// * `I2cOnly` - disables the STROBE input and leaves the device in standby.
+    /// * `HardwareStrobe` - drives the strobe pin low, then enables the STROBE input in flash mode.
+    /// * `Hybrid` - enables the STROBE input in flash mode so an external source can fire.
+    ///
+    /// The flash current and time-out are taken from the cached registers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut trigger = TriggerMode::HardwareStrobe { pin: strobe_pin };
+    /// driver.arm_flash(&mut trigger)?;
+    /// driver.fire_flash(&mut trigger)?;
+    /// delay.delay_ms(20);
+    /// driver.end_flash(&mut trigger)?;
+    /// ```
+    pub fn arm_flash<P: OutputPin>(
+        &mut self,
+        trigger: &mut TriggerMode<P>,
+    ) -> Result<(), LM36011Error<E>> {
+        let standby = self.enable_flags.bits()
+            & !(EnableRegisterFlags::MODE_MASK | EnableRegisterFlags::STROBE_ENABLE).bits();
+        let strobe_armed = standby
+            | EnableRegisterFlags::STROBE_ENABLE.bits()
+            | EnableRegisterFlags::MODE_FLASH.bits();
+
+        match trigger {
+            TriggerMode::I2cOnly => {
+                self.write_enable(EnableRegisterFlags::from_bits_truncate(standby))
+            }
+            TriggerMode::HardwareStrobe { pin } => {
+                pin.set_low().map_err(|_| LM36011Error::StrobePinError)?;
+                self.write_enable(EnableRegisterFlags::from_bits_truncate(strobe_armed))
+            }
+            TriggerMode::Hybrid => {
+                self.write_enable(EnableRegisterFlags::from_bits_truncate(strobe_armed))
+            }
+        }
+    }
+
+    /// Fires a flash armed with [`LM36011::arm_flash`].
+    ///
+    /// `HardwareStrobe` drives the strobe pin high. `I2cOnly` and `Hybrid` enter flash mode over
+    /// I2C with the STROBE input disabled, which starts the flash immediately.
+    ///
+    /// The fire path is inlined and does no formatting or allocation; its write is left out of
+    /// the `log` and `defmt` register traces. With the `cycle-count` feature its latency is
+    /// available from [`LM36011::last_fire_cycles`].
+    #[inline]
+    pub fn fire_flash<P: OutputPin>(
+        &mut self,
+        trigger: &mut TriggerMode<P>,
+    ) -> Result<(), LM36011Error<E>> {
+        #[cfg(feature = "cycle-count")]
+        let start = cycle_count();
+
+        let result = match trigger {
+            TriggerMode::HardwareStrobe { pin } => {
+                pin.set_high().map_err(|_| LM36011Error::StrobePinError)
+            }
+            TriggerMode::I2cOnly | TriggerMode::Hybrid => self
+                .fire_enable(self.device_flash_code)
+                .and_then(|enable| self.write_fire_enable(enable)),
+        };
+
+        #[cfg(feature = "cycle-count")]
+        if result.is_ok() {
+            self.fire_cycles = Some(cycle_count().wrapping_sub(start));
+        }
+
+        result
+    }
+
+    /// Ends a flash started with [`LM36011::fire_flash`] before its hardware time-out.
+    ///
+    /// `HardwareStrobe` drives the strobe pin low (ending a level-triggered flash), `I2cOnly` and
+    /// `Hybrid` return the device to standby over I2C. Arm again before the next flash.
+    pub fn end_flash<P: OutputPin>(
+        &mut self,
+        trigger: &mut TriggerMode<P>,
+    ) -> Result<(), LM36011Error<E>> {
+        match trigger {
+            TriggerMode::HardwareStrobe { pin } => {
+                pin.set_low().map_err(|_| LM36011Error::StrobePinError)
+            }
+            TriggerMode::I2cOnly | TriggerMode::Hybrid => {
+                self.write_mode(EnableRegisterFlags::empty())
+            }
+        }
+    }
+
+    /// Re-arms flash mode after a flash ended at its hardware time-out.
+    ///
+    /// Reads the Flags Register and, if the flash time-out flag is set (now or in the sticky
+    /// `flag_register_flags`), clears it from the cache, optionally lowers the flash current to
+    /// `reduced_ma` (see [`LM36011::set_flash_current_ma`]) and arms the next flash with
+    /// [`LM36011::arm_flash`]. Nothing is written when no time-out is flagged.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if a time-out was handled and the flash re-armed.
+    /// * `Ok(false)` if no time-out was flagged.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if `reduced_ma` is above the flash limit and the
+    ///   range policy is [`RangePolicy::Error`].
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut trigger = TriggerMode::HardwareStrobe { pin: strobe_pin };
+    /// driver.arm_flash(&mut trigger)?;
+    /// loop {
+    ///     camera.capture_with_strobe();
+    ///     // back off to 500 mA if the exposure outlasted the time-out
+    ///     driver.rearm_after_timeout(&mut trigger, Some(500))?;
+    /// }
+    /// ```
+    pub fn rearm_after_timeout<P: OutputPin>(
+        &mut self,
+        trigger: &mut TriggerMode<P>,
+        reduced_ma: Option<u16>,
+    ) -> Result<bool, LM36011Error<E>> {
+        self.get_register(Register::FlagsRegister)
+            .map_err(LM36011Error::I2CError)?;
+        self.report_cutoff()?;
+        if !self
+            .flag_register_flags
+            .contains(FlagRegisterFlags::FLASH_TIMEOUT_FLAG)
+        {
+            return Ok(false);
+        }
+        self.flag_register_flags
+            .remove(FlagRegisterFlags::FLASH_TIMEOUT_FLAG);
+
+        if let Some(ma) = reduced_ma {
+            self.set_flash_current_ma(ma)?;
+        }
+        self.arm_flash(trigger)?;
+
+        Ok(true)
+    }
+
+    /// Puts the device in its lowest-power standby state.
+    ///
+    /// Clears the MODE bits and disables the STROBE input and IVFM in a single Enable Register
+    /// write, so neither an I2C write of a brightness nor a stray strobe edge can turn the LED
+    /// back on. The brightness and configuration registers are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `verify` - `true` to read the Enable Register back and confirm the device reached
+    ///   standby, for battery products that must not be left drawing current.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the operation was successful.
+    /// * `Err(LM36011Error::StandbyVerifyError)` if the read-back shows a mode, the STROBE input
+    ///   or IVFM still enabled. The cached Enable Register holds the value read.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.shutdown(true)?;
+    /// mcu.enter_deep_sleep();
+    /// ```
+    pub fn shutdown(&mut self, verify: bool) -> Result<(), LM36011Error<E>> {
+        let active = EnableRegisterFlags::MODE_MASK
+            | EnableRegisterFlags::STROBE_ENABLE
+            | EnableRegisterFlags::IVFM_ENABLE;
+        self.write_enable(self.enable_flags - active)?;
+
+        if verify {
+            let enable = self
+                .get_register(Register::EnableRegister)
+                .map_err(LM36011Error::I2CError)?;
+            self.enable_flags = EnableRegisterFlags::from_bits_truncate(enable);
+            if self.enable_flags.intersects(active) {
+                return Err(LM36011Error::StandbyVerifyError);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fires a flash over I2C in a single Enable Register write.
+    ///
+    /// Enters flash mode with the STROBE input disabled, using the programmed flash current
+    /// and time-out; the IVFM enable is kept from the cache. The device returns to standby by
+    /// itself when the time-out expires, or call [`LM36011::disable_output`] to end the flash
+    /// early. The same as [`LM36011::fire_flash`] with [`TriggerMode::I2cOnly`], without the
+    /// arming step.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_flash_current_ma(1000)?;
+    /// driver.set_flash_timeout(FlashTimeout::Ms120)?;
+    /// driver.trigger_flash()?;
+    /// ```
+    pub fn trigger_flash(&mut self) -> Result<(), LM36011Error<E>> {
+        self.fire_flash(&mut TriggerMode::<NoStrobePin>::I2cOnly)
+    }
+
+    /// Reads the Device ID register and decodes the part and silicon revision.
+    ///
+    /// The cached `device_id` is updated with the value read.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DeviceInfo)` with the decoded device ID and silicon revision.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011::new(i2c);
+    /// let info = driver.probe()?;
+    /// if info.device_id != DeviceId::Lm36011 {
+    ///     println!("unexpected part: {:?}", info.device_id);
+    /// }
+    /// ```
+    pub fn probe(&mut self) -> Result<DeviceInfo, LM36011Error<E>> {
+        let id = self
+            .get_register(Register::DeviceIdRegister)
+            .map_err(LM36011Error::I2CError)?;
+        self.device_id = DeviceIdFlags::from_bits_truncate(id);
+
+        let info = DeviceInfo::from_register(id);
+        self.quirks = Quirks::for_revision(info.revision);
+        self.identity = Some(info);
+        self.identity_event = None;
+
+        Ok(info)
+    }
+
+    /// Returns the quirks of the connected silicon revision.
+    ///
+    /// Until the Device ID Register has been read with [`LM36011::probe`] or
+    /// [`LM36011::read_status`] the conservative defaults for an unknown revision are returned.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.probe()?;
+    /// let settle_us = driver.quirks().reset_settle_us;
+    /// ```
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Performs a software reset and waits until the device accepts commands again.
+    ///
+    /// The wait time is taken from [`Quirks::reset_settle_us`] of the connected revision.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` variant of `LM36011Error` if there's an I2C communication error.
+    pub fn software_reset_and_wait<D: DelayUs<u16>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), LM36011Error<E>> {
+        self.software_reset()?;
+        delay.delay_us(self.quirks.reset_settle_us);
+
+        Ok(())
+    }
+
+    /// Registers an observer called whenever a read of the Flags Register, by any driver call,
+    /// finds a fault, IVFM trip or thermal scale-back.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// static THERMAL_SHUTDOWNS: AtomicU32 = AtomicU32::new(0);
+    ///
+    /// fn log_fault(faults: Faults) {
+    ///     if faults.thermal_shutdown {
+    ///         THERMAL_SHUTDOWNS.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// }
+    ///
+    /// let mut driver = LM36011::new(i2c).with_fault_handler(&log_fault);
+    /// ```
+    ///
+    /// The handler is `Sync`, so the driver stays `Send`, e.g. for an RTIC shared resource.
+    pub fn with_fault_handler(mut self, handler: &'static (dyn FaultHandler + Sync)) -> Self {
+        self.fault_handler = Some(handler);
+        self
+    }
+
+    /// Registers named current-limit profiles and activates the first one.
+    ///
+    /// While a profile is active every flash and torch setter rejects currents above its
+    /// limits (in addition to `MAX_FLASH_MA`). Switch profiles at runtime with
+    /// [`LM36011::set_profile`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// static PROFILES: [LimitProfile; 3] = [
+    ///     LimitProfile { name: "Indoor", max_flash_ma: 750, max_torch_ma: 150 },
+    ///     LimitProfile { name: "Outdoor", max_flash_ma: 1500, max_torch_ma: 376 },
+    ///     LimitProfile { name: "Thermal-restricted", max_flash_ma: 300, max_torch_ma: 100 },
+    /// ];
+    ///
+    /// let mut driver = LM36011::new(i2c).with_profiles(&PROFILES);
+    /// driver.set_profile("Outdoor")?;
+    /// ```
+    pub fn with_profiles(mut self, profiles: &'static [LimitProfile]) -> Self {
+        self.profiles = profiles;
+        self.active_profile = if profiles.is_empty() { None } else { Some(0) };
+        self
+    }
+
+    /// Activates the registered profile called `name`.
+    ///
+    /// A programmed flash or torch current above the new limits is lowered to the highest step
+    /// within them, so the next torch or flash cannot exceed the profile.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the profile was activated.
+    /// * `Err(LM36011Error::InvalidInput)` if no registered profile has that name.
+    /// * `Err(LM36011Error::I2CError(E))` if lowering a programmed current failed.
+    pub fn set_profile(&mut self, name: &str) -> Result<(), LM36011Error<E>> {
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or(LM36011Error::InvalidInput)?;
+        self.active_profile = Some(index);
+
+        self.lower_to_limits()
+    }
+
+    /// Returns the active current-limit profile, if any.
+    pub fn active_profile(&self) -> Option<&LimitProfile> {
+        self.active_profile.map(|index| &self.profiles[index])
+    }
+
+    /// The flash current scale capped at `MAX_FLASH_MA`, the active profile and the derating.
+    fn flash_scale(&self) -> CurrentScale {
+        let mut scale = Self::FLASH_LIMIT;
+        if let Some(profile) = self.active_profile() {
+            scale.max_ua = scale.max_ua.min(profile.max_flash_ma as u32 * 1000);
+        }
+        if let Some(point) = self.derating_point() {
+            scale.max_ua = scale.max_ua.min(point.max_flash_ma as u32 * 1000);
+        }
+        scale
+    }
+
+    /// The torch current scale capped at the active profile and the derating.
+    fn torch_scale(&self) -> CurrentScale {
+        let mut scale = TORCH_SCALE;
+        if let Some(profile) = self.active_profile() {
+            scale.max_ua = scale.max_ua.min(profile.max_torch_ma as u32 * 1000);
+        }
+        if let Some(point) = self.derating_point() {
+            scale.max_ua = scale.max_ua.min(point.max_torch_ma as u32 * 1000);
+        }
+        scale
+    }
+
+    /// Enables or disables the fault cut-off.
+    ///
+    /// With the cut-off enabled, whenever a read of the Flags Register (by any driver call)
+    /// finds a thermal shutdown or VLED short, the device is put in standby immediately. The
+    /// call that read the flags fails with [`LM36011Error::FaultShutdown`] if it returns an
+    /// [`LM36011Error`], and every attempt to turn the output on fails with the same error until
+    /// [`LM36011::clear_faults`], so firmware cannot keep re-enabling a faulting LED. Disabled by
+    /// default.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_fault_cutoff(true);
+    /// match driver.read_faults() {
+    ///     Err(LM36011Error::FaultShutdown) => service.report(driver.read_faults()?),
+    ///     result => { result?; }
+    /// }
+    /// ```
+    pub fn set_fault_cutoff(&mut self, enabled: bool) {
+        self.fault_cutoff = enabled;
+        if !enabled {
+            self.cut_off = false;
+            self.cutoff_event = false;
+        }
+    }
+
+    /// Returns whether the fault cut-off is enabled.
+    pub fn fault_cutoff(&self) -> bool {
+        self.fault_cutoff
+    }
+
+    /// Returns whether the fault cut-off is holding the output off.
+    pub fn is_cut_off(&self) -> bool {
+        self.cut_off
+    }
+
+    /// Enables or disables auto-standby.
+    ///
+    /// With auto-standby enabled, setting a current of 0 mA with the mA based setters drops the
+    /// device to standby instead of programming the lowest brightness code, and a previous
+    /// torch or IR mode is restored when a non-zero current is next set. Flash mode is one-shot
+    /// and never restored. Changing the mode explicitly in between cancels the restore.
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_auto_standby(true);
+    /// driver.enable_torch()?;
+    /// driver.set_torch_current(0.0)?; // standby
+    /// driver.set_torch_current(150.0)?; // torch again
+    /// ```
+    pub fn set_auto_standby(&mut self, enabled: bool) {
+        self.auto_standby = enabled;
+        if !enabled {
+            self.suspended_mode = None;
+        }
+    }
+
+    /// Returns whether auto-standby is enabled.
+    pub fn auto_standby(&self) -> bool {
+        self.auto_standby
+    }
+
+    /// Drops the device to standby for a zero current, remembering an active torch or IR mode.
+    fn standby_for_zero(&mut self) -> Result<(), LM36011Error<E>> {
+        let mode = self.enable_flags & EnableRegisterFlags::MODE_MASK;
+        if mode.is_empty() {
+            return Ok(());
+        }
+
+        self.write_mode(EnableRegisterFlags::empty())?;
+        if mode != EnableRegisterFlags::MODE_FLASH {
+            self.suspended_mode = Some(mode);
+        }
+
+        Ok(())
+    }
+
+    /// Restores the mode that was active before an auto-standby, if any.
+    fn resume_mode(&mut self) -> Result<(), LM36011Error<E>> {
+        match self.suspended_mode.take() {
+            Some(mode) => self.write_mode(mode),
+            None => Ok(()),
+        }
+    }
+
+    /// Sets how out-of-range current and time-out requests are handled.
+    ///
+    /// With [`RangePolicy::Error`] (the default) they fail with
+    /// [`LM36011Error::CurrentOutOfRange`] or [`LM36011Error::TimeoutOutOfRange`], which suits
+    /// calibration tools. With [`RangePolicy::ClampToLimit`] they are clamped to the flash or
+    /// torch limit in force and the 1600 ms time-out, so interactive products degrade
+    /// gracefully. Compile-time checked currents are unaffected.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver: LM36011<_, 800> = LM36011::new_limited(i2c);
+    /// driver.set_range_policy(RangePolicy::ClampToLimit);
+    /// driver.set_flash_current(1200.0)?; // programs 796 mA, the highest step within 800 mA
+    /// ```
+    pub fn set_range_policy(&mut self, policy: RangePolicy) {
+        self.range_policy = policy;
+    }
+
+    /// Returns the out-of-range policy.
+    pub fn range_policy(&self) -> RangePolicy {
+        self.range_policy
+    }
+
+    /// Checks a brightness code against `scale`, applying the range policy.
+    fn limit_code(&self, code: u8, scale: CurrentScale) -> Result<u8, LM36011Error<E>> {
+        if code <= MAX_BRIGHTNESS_CODE && scale.ua(code) <= scale.max_ua {
+            return Ok(code);
+        }
+
+        self.out_of_range(scale)
+    }
+
+    /// Converts a current in mA to a code on `scale`, applying the range policy.
+    fn limit_ma(&self, ma: u16, scale: CurrentScale) -> Result<u8, LM36011Error<E>> {
+        self.limit_ma_rounded(ma, scale, Rounding::Floor)
+    }
+
+    /// Converts a current in mA to a code on `scale` with `rounding`, applying the range policy.
+    fn limit_ma_rounded(
+        &self,
+        ma: u16,
+        scale: CurrentScale,
+        rounding: Rounding,
+    ) -> Result<u8, LM36011Error<E>> {
+        match scale.code_for_ma_rounded(ma, rounding) {
+            Some(code) => Ok(code),
+            None => self.out_of_range(scale),
+        }
+    }
+
+    /// Handles a request above `scale` according to the range policy.
+    fn out_of_range(&self, scale: CurrentScale) -> Result<u8, LM36011Error<E>> {
+        match self.range_policy {
+            RangePolicy::Error => Err(LM36011Error::CurrentOutOfRange),
+            RangePolicy::ClampToLimit => Ok(scale.max_code()),
+        }
+    }
+
+    /// Plans a current in mA on `scale`, applying the range policy.
+    ///
+    /// A clamped plan reports its error against the original request.
+    #[cfg(all(feature = "float", not(target_arch = "avr")))]
+    fn limit_plan(
+        &self,
+        current: f32,
+        scale: CurrentScale,
+    ) -> Result<CurrentPlan, LM36011Error<E>> {
+        let requested = match self.range_policy {
+            RangePolicy::Error => current,
+            RangePolicy::ClampToLimit => current.clamp(0.0, ua_to_ma(scale.max_ua)),
+        };
+
+        let plan = CurrentPlan::new(requested, scale).ok_or(LM36011Error::CurrentOutOfRange)?;
+        Ok(CurrentPlan {
+            error_ma: plan.actual_ma - current,
+            ..plan
+        })
+    }
+
+    /// Sets the torch brightness from a code, a current or a percentage.
+    ///
+    /// The mode is not changed, apart from auto-standby (see [`LM36011::set_auto_standby`]),
+    /// which treats `Milliamps(0)` and `Percent(0)` as off.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the brightness was written.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the brightness is above the torch limits or
+    ///   100 %, and the range policy is [`RangePolicy::Error`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_torch(Brightness::Milliamps(150))?;
+    /// driver.set_torch(Brightness::Percent(50))?;
+    /// driver.set_torch(Brightness::Code(0x20))?;
+    /// ```
+    pub fn set_torch(&mut self, brightness: Brightness) -> Result<(), LM36011Error<E>> {
+        match self.brightness_code(brightness, self.torch_scale())? {
+            Some(code) => {
+                self.write_torch_code(code)?;
+                self.resume_mode()
+            }
+            None => self.standby_for_zero(),
+        }
+    }
+
+    /// Sets the flash brightness from a code, a current or a percentage.
+    ///
+    /// Behaves like [`LM36011::set_torch`] against the flash limits.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_flash(Brightness::Percent(80))?;
+    /// ```
+    pub fn set_flash(&mut self, brightness: Brightness) -> Result<(), LM36011Error<E>> {
+        match self.brightness_code(brightness, self.flash_scale())? {
+            Some(code) => {
+                self.write_flash_code(code)?;
+                self.resume_mode()
+            }
+            None => self.standby_for_zero(),
+        }
+    }
+
+    /// Converts a brightness to a code on `scale`, or `None` when auto-standby turns it off.
+    fn brightness_code(
+        &self,
+        brightness: Brightness,
+        scale: CurrentScale,
+    ) -> Result<Option<u8>, LM36011Error<E>> {
+        if self.auto_standby && brightness.is_zero() {
+            return Ok(None);
+        }
+
+        let code = match brightness {
+            Brightness::Code(code) => self.limit_code(code, scale)?,
+            Brightness::Milliamps(ma) => self.limit_ma(ma, scale)?,
+            Brightness::Percent(percent) if percent <= 100 => {
+                scale.code_floor(scale.max_ua * percent as u32 / 100)
+            }
+            Brightness::Percent(_) => self.out_of_range(scale)?,
+        };
+
+        Ok(Some(code))
+    }
+
+    /// Registers a board-level thermal derating table.
+    ///
+    /// The table lists points sorted by rising temperature. After a temperature is reported
+    /// with [`LM36011::apply_temperature`] or [`LM36011::update_temperature`], the limits of the
+    /// last point at or below it apply on top of `MAX_FLASH_MA` and the active profile; below
+    /// the first point nothing is derated. This complements the chip's internal thermal
+    /// scale-back with limits derived from the board's own thermal design.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// static DERATING: [DeratingPoint; 2] = [
+    ///     DeratingPoint { temperature_c: 60, max_flash_ma: 1000, max_torch_ma: 250 },
+    ///     DeratingPoint { temperature_c: 80, max_flash_ma: 400, max_torch_ma: 100 },
+    /// ];
+    ///
+    /// let mut driver = LM36011::new(i2c).with_derating(&DERATING);
+    /// driver.update_temperature(&mut ntc)?;
+    /// ```
+    pub fn with_derating(mut self, table: &'static [DeratingPoint]) -> Self {
+        self.derating = table;
+        self.derated = None;
+        self
+    }
+
+    /// Applies the derating for a board temperature in °C.
+    ///
+    /// A programmed flash or torch current above the new limits is lowered to the highest step
+    /// within them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` variant of `LM36011Error` if there's an I2C communication error.
+    pub fn apply_temperature(&mut self, celsius: i16) -> Result<(), LM36011Error<E>> {
+        self.derated = self
+            .derating
+            .iter()
+            .rposition(|point| point.temperature_c <= celsius);
+
+        self.lower_to_limits()
+    }
+
+    /// Lowers a programmed flash or torch current above the limits in force to the highest step
+    /// within them.
+    fn lower_to_limits(&mut self) -> Result<(), LM36011Error<E>> {
+        let flash = self.flash_scale();
+        if flash.ua(self.flash_brightness_flags.bits() & MAX_BRIGHTNESS_CODE) > flash.max_ua {
+            self.write_flash_code(flash.max_code())?;
+        }
+        let torch = self.torch_scale();
+        if torch.ua(self.torch_brightness_flags.bits() & MAX_BRIGHTNESS_CODE) > torch.max_ua {
+            self.write_torch_code(torch.max_code())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads an external temperature sensor and applies the derating for its reading.
+    ///
+    /// # Errors
+    ///
+    /// * `LM36011Error::TemperatureSensorError` if the sensor could not be read.
+    /// * `LM36011Error::I2CError` if lowering a programmed current failed.
+    pub fn update_temperature<S: TemperatureSensor>(
+        &mut self,
+        sensor: &mut S,
+    ) -> Result<(), LM36011Error<E>> {
+        let celsius = sensor
+            .read_celsius()
+            .map_err(|_| LM36011Error::TemperatureSensorError)?;
+
+        self.apply_temperature(celsius)
+    }
+
+    /// Returns the derating point in force, if any.
+    pub fn derating_point(&self) -> Option<&DeratingPoint> {
+        self.derated.map(|index| &self.derating[index])
+    }
+
+    /// Fires a single flash of `duration` over I2C and returns to standby.
+    ///
+    /// The smallest hardware time-out that is at least `duration` is programmed first, so the
+    /// time-out only acts as a backstop should the end of the pulse be missed. The flash current
+    /// is taken from the cached Flash Brightness Register. Like [`LM36011::trigger_flash`] the
+    /// STROBE input is disabled, so an armed strobe does not hold the flash back.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - The flash pulse length in ms (at most 1600 ms).
+    /// * `delay` - A delay provider timing the pulse.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once the flash has ended.
+    /// * `Err(LM36011Error::TimeoutOutOfRange)` if `duration` is above 1600 ms and the range
+    ///   policy is [`RangePolicy::Error`]; with [`RangePolicy::ClampToLimit`] the pulse is
+    ///   shortened to 1600 ms.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_flash(Brightness::Milliamps(800))?;
+    /// driver.flash_once(250, &mut delay)?; // 280 ms hardware time-out
+    /// ```
+    pub fn flash_once<D: DelayMs<u16>>(
+        &mut self,
+        duration: impl IntoMillis,
+        delay: &mut D,
+    ) -> Result<(), LM36011Error<E>> {
+        let duration_ms = duration.into_millis();
+        let (timeout, duration_ms) = match (FlashTimeout::at_least(duration_ms), self.range_policy)
+        {
+            (Some(timeout), _) => (timeout, duration_ms),
+            (None, RangePolicy::Error) => return Err(LM36011Error::TimeoutOutOfRange),
+            (None, RangePolicy::ClampToLimit) => {
+                (FlashTimeout::Ms1600, FlashTimeout::Ms1600.ms() as u32)
+            }
+        };
+
+        self.write_flash_timeout(timeout)?;
+        self.trigger_flash()?;
+        delay.delay_ms(duration_ms as u16);
+        self.write_mode(EnableRegisterFlags::empty())
+    }
+
+    /// Writes the time-out bits of the Configuration Register, keeping the other cached bits.
+    fn write_flash_timeout(&mut self, timeout: FlashTimeout) -> Result<(), LM36011Error<E>> {
+        self.write_config_field(FLASH_TIMEOUT_MASK, timeout.bits())
+    }
+
+    /// Writes the `mask` bits of the Configuration Register, keeping the other cached bits.
+    fn write_config_field(&mut self, mask: u8, bits: u8) -> Result<(), LM36011Error<E>> {
+        let config = ConfigurationRegisterFlags::from_bits_truncate(
+            (self.config_flags.bits() & !mask) | (bits & mask),
+        );
+
+        self.set_register(Register::ConfigurationRegister, config.bits())?;
+        self.config_flags = config;
+
+        Ok(())
+    }
+
+    /// Reads and decodes the Flags Register (0x05), and nothing else.
+    ///
+    /// The register clears on read, so any read of it (e.g. by [`LM36011::read_status`])
+    /// consumes the pending flags. The driver keeps the flags consumed by other reads in the
+    /// sticky `flag_register_flags` and returns them here too, so no fault is lost; they are
+    /// then taken, see [`LM36011::take_faults`]. The flags read are also latched by
+    /// [`LM36011::last_faults`] and counted by [`LM36011::fault_counters`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let faults = driver.read_faults()?;
+    /// if faults.thermal_shutdown {
+    ///     cooldown.start();
+    /// }
+    /// ```
+    pub fn read_faults(&mut self) -> Result<Faults, LM36011Error<E>> {
+        self.get_register(Register::FlagsRegister)
+            .map_err(LM36011Error::I2CError)?;
+        self.report_cutoff()?;
+
+        Ok(self.take_faults())
+    }
+
+    /// Returns whether the device reports a fault (see [`Faults::is_fault`]), with a single
+    /// one-byte read of the Flags Register (0x05) rather than the full [`LM36011::read_status`].
+    ///
+    /// Meant as a go/no-go check in tight control loops. The flags read stay in the sticky
+    /// `flag_register_flags` for [`LM36011::take_faults`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// while driver.get_mode()? == OperatingMode::Torch {
+    ///     if driver.has_fault()? {
+    ///         driver.disable_output()?;
+    ///         break;
+    ///     }
+    ///     delay.delay_ms(1);
+    /// }
+    /// ```
+    pub fn has_fault(&mut self) -> Result<bool, LM36011Error<E>> {
+        let flags = self
+            .get_register(Register::FlagsRegister)
+            .map_err(LM36011Error::I2CError)?;
+        self.report_cutoff()?;
+
+        Ok(Faults::from_flags(FlagRegisterFlags::from_bits_truncate(flags)).is_fault())
+    }
 
-        // update internal struct state
-        self.flash_brightness_flags = brightness_bitflags;
+    /// Samples the Flags Register `iterations` times, `interval_ms` apart, and returns every
+    /// fault seen, e.g. over a long torch session.
+    ///
+    /// Each sample is a one-byte read of register 0x05. Like [`LM36011::read_faults`], the
+    /// result includes flags consumed by earlier reads, which are then cleared. With the
+    /// `async` feature, `LM36011Async::monitor_faults` does the same on an async bus and delay.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.enable_torch()?;
+    /// // watch the torch for 10 s
+    /// let faults = driver.monitor_faults(&mut delay, 100, 100)?;
+    /// driver.disable_output()?;
+    /// ```
+    pub fn monitor_faults<D: DelayMs<u16>>(
+        &mut self,
+        delay: &mut D,
+        interval_ms: u16,
+        iterations: u16,
+    ) -> Result<Faults, LM36011Error<E>> {
+        for _ in 0..iterations {
+            delay.delay_ms(interval_ms);
+            self.get_register(Register::FlagsRegister)
+                .map_err(LM36011Error::I2CError)?;
+            self.report_cutoff()?;
+        }
+
+        Ok(self.take_faults())
+    }
+
+    /// Returns and clears the sticky `flag_register_flags`, without reading the device.
+    ///
+    /// Every read of the Flags Register ([`LM36011::read_status`], [`LM36011::get_register`],
+    /// ...) merges the flags it finds into the sticky cache, so calls made for unrelated
+    /// reasons do not destroy the fault evidence. Take them where they are handled.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.read_status()?; // e.g. to show the current mode
+    /// // ...
+    /// let faults = driver.take_faults();
+    /// if faults.is_fault() {
+    ///     log.fault(faults);
+    /// }
+    /// ```
+    pub fn take_faults(&mut self) -> Faults {
+        Faults::from_flags(core::mem::replace(
+            &mut self.flag_register_flags,
+            FlagRegisterFlags::empty(),
+        ))
+    }
+
+    /// Fires a flash over I2C, waits for the flash time-out and returns the faults of the pulse.
+    ///
+    /// Pending faults are cleared first, so the result only covers this flash. The flash uses
+    /// the programmed flash current and time-out, see [`LM36011::trigger_flash`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Faults)` read after the pulse. `flash_timeout` is set when the pulse ran to its
+    ///   time-out; [`Faults::is_fault`] tells whether it was cut short by a thermal shutdown,
+    ///   VLED short or UVLO, and `ivfm_trip` / `thermal_scaleback` whether the current was
+    ///   reduced.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let faults = driver.flash_and_check(&mut delay)?;
+    /// if faults.is_fault() || faults.ivfm_trip {
+    ///     camera.discard_frame();
+    /// }
+    /// ```
+    pub fn flash_and_check<D: DelayMs<u16>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<Faults, LM36011Error<E>> {
+        self.clear_faults()?;
+        self.trigger_flash()?;
+
+        delay.delay_ms(self.get_flash_timeout().ms() + FLASH_END_MARGIN_MS);
+
+        self.read_faults()
+    }
+
+    /// Returns the flags consumed by reads of the Flags Register since the last
+    /// [`LM36011::read_faults`] or [`LM36011::clear_faults`], without reading the device.
+    pub fn pending_faults(&self) -> Faults {
+        Faults::from_flags(self.flag_register_flags)
+    }
+
+    /// Clears the faults: reads the Flags Register to clear it on the device and discards the
+    /// flags consumed by earlier reads. A fault cut-off is released, unless the fault is still
+    /// present.
+    ///
+    /// [`LM36011::last_faults`] and the fault counters are kept.
+    pub fn clear_faults(&mut self) -> Result<(), LM36011Error<E>> {
+        self.cut_off = false;
+        self.get_register(Register::FlagsRegister)
+            .map_err(LM36011Error::I2CError)?;
+        self.report_cutoff()?;
+        self.flag_register_flags = FlagRegisterFlags::empty();
 
         Ok(())
     }
 
-    /// Retrieves the device ID from the LM36011.
+    /// Recovers from an under-voltage lockout, following the datasheet sequence.
     ///
-    /// This function reads the `DeviceIdRegister` of the LM36011 device to obtain its ID.
-    /// It uses the I2C `write_read` method to request and retrieve the device ID.
+    /// Clears the faults (see [`LM36011::clear_faults`]), re-applies the cached configuration
+    /// and current registers in standby, then re-enables the previous mode only if
+    /// `supply_mv`, the input voltage measured by the caller, is above the IVFM threshold.
+    /// Returns whether the previous mode was restored; on `Ok(false)` the device is left in
+    /// standby. An I2C-started flash has ended with the lockout and is not fired again.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if driver.read_faults()?.uvlo && !driver.recover_from_uvlo(adc.vin_mv())? {
+    ///     ui.show_low_battery();
+    /// }
+    /// ```
+    pub fn recover_from_uvlo(&mut self, supply_mv: u16) -> Result<bool, LM36011Error<E>> {
+        let previous = self.settings();
+        self.clear_faults()?;
+
+        let mut standby = previous;
+        standby.enable.remove(EnableRegisterFlags::MODE_MASK);
+        self.apply_settings(&standby)?;
+
+        if supply_mv <= self.get_ivfm_threshold().millivolts() {
+            return Ok(false);
+        }
+        self.write_enable(Self::without_one_shot_flash(previous.enable))?;
+
+        Ok(true)
+    }
+
+    /// Returns the most recent non-empty fault state, latched until
+    /// [`LM36011::clear_last_faults`].
+    ///
+    /// Every read of the Flags Register updates the snapshot when it shows a fault, IVFM trip
+    /// or thermal scale-back, so the flags survive the read-to-clear of the hardware register
+    /// and later clean reads. UI and telemetry layers polling less often than the driver reads
+    /// the flags therefore never miss a transient fault.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let faults = driver.last_faults();
+    /// if !faults.is_empty() {
+    ///     display.show_fault(faults);
+    ///     driver.clear_last_faults();
+    /// }
+    /// ```
+    pub fn last_faults(&self) -> FlagRegisterFlags {
+        self.last_faults
+    }
+
+    /// Clears the latched fault snapshot.
+    pub fn clear_last_faults(&mut self) {
+        self.last_faults = FlagRegisterFlags::empty();
+    }
+
+    /// Returns every fault, IVFM trip and thermal scale-back seen by any read of the Flags
+    /// Register since the driver was created or [`LM36011::reset_fault_history`] was called.
+    ///
+    /// Unlike [`LM36011::last_faults`], which holds the latest fault state, the history
+    /// accumulates, so an intermittent UVLO between two polls is still visible after later
+    /// faults. The flash time-out flag marks a normal flash end and is not recorded.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // once a minute
+    /// let history = driver.fault_history();
+    /// if history.uvlo || history.thermal_scaleback {
+    ///     log.record(history);
+    ///     driver.reset_fault_history();
+    /// }
+    /// ```
+    pub fn fault_history(&self) -> Faults {
+        Faults::from_flags(self.fault_history)
+    }
+
+    /// Clears the fault history.
+    pub fn reset_fault_history(&mut self) {
+        self.fault_history = FlagRegisterFlags::empty();
+    }
+
+    /// Caches a Flags Register value read from the device, and latches and counts any faults.
+    ///
+    /// With the fault cut-off enabled, a thermal shutdown or VLED short puts the device in
+    /// standby straight away.
+    fn record_flags(&mut self, flags: FlagRegisterFlags) -> Result<(), E> {
+        self.flag_register_flags |= flags;
+        self.fault_counters.count(flags);
+        if let Some(stats) = &mut self.fault_stats {
+            stats.count(flags);
+        }
+
+        let faults = flags & LATCHED_FLAGS;
+        if !faults.is_empty() {
+            self.last_faults = faults;
+            self.fault_history |= faults;
+            if let Some(handler) = self.fault_handler {
+                handler.on_fault(Faults::from_flags(flags));
+            }
+        }
+
+        if self.fault_cutoff && flags.intersects(CUTOFF_FLAGS) {
+            let standby = self.enable_flags - EnableRegisterFlags::MODE_MASK;
+            let standby = EnableRegisterFlags::from_bits_truncate(
+                self.with_reserved(Register::EnableRegister, standby.bits()),
+            );
+            self.write_registers(&[Register::EnableRegister as u8, standby.bits()])?;
+            self.enable_flags = standby;
+            self.suspended_mode = None;
+            self.cut_off = true;
+            self.cutoff_event = true;
+        }
+
+        Ok(())
+    }
+
+    /// Reports a fault cut-off that happened since the last report as
+    /// [`LM36011Error::FaultShutdown`].
+    fn report_cutoff(&mut self) -> Result<(), LM36011Error<E>> {
+        if core::mem::take(&mut self.cutoff_event) {
+            return Err(LM36011Error::FaultShutdown);
+        }
+
+        Ok(())
+    }
+
+    /// Rejects flash and torch brightness register values above the current limits in force,
+    /// or raising the flash current past the interlock, or entering flash mode above it with
+    /// `enable`, without an unlock.
+    fn check_currents(
+        &mut self,
+        enable: EnableRegisterFlags,
+        flash: u8,
+        torch: u8,
+    ) -> Result<(), LM36011Error<E>> {
+        let flash_code = flash & MAX_BRIGHTNESS_CODE;
+        let torch_code = torch & MAX_BRIGHTNESS_CODE;
+        if self.flash_scale().ua(flash_code) > self.flash_scale().max_ua
+            || self.torch_scale().ua(torch_code) > self.torch_scale().max_ua
+        {
+            return Err(LM36011Error::CurrentOutOfRange);
+        }
+
+        self.check_interlock(flash_code)?;
+        self.check_flash_interlock(enable, flash_code)
+    }
+
+    /// Rejects turning the output on while the fault cut-off holds it off.
+    fn check_cutoff(&self, enable: EnableRegisterFlags) -> Result<(), LM36011Error<E>> {
+        if self.cut_off && enable.intersects(EnableRegisterFlags::MODE_MASK) {
+            return Err(LM36011Error::FaultShutdown);
+        }
+
+        Ok(())
+    }
+
+    /// Programs the flash brightness and enters flash mode, in two back-to-back I2C writes.
+    ///
+    /// The Flash Brightness Register (0x03) is written first, then the Enable Register (0x01),
+    /// so the flash never starts at the previous, possibly higher or interlock-blocked,
+    /// brightness code. Like
+    /// [`LM36011::fire_flash`] the STROBE input is disabled, so the flash starts immediately,
+    /// and the writes are left out of the register traces.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The 7-bit flash brightness code.
     ///
     /// # Returns
     ///
-    /// * `Ok(u8)` containing the device ID if the read operation was successful.
-    /// * `Err(E)` if there was an error during the read operation. The error type `E` is determined by the I2C interface.
+    /// * `Ok(())` once the flash has been started.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the code is above the flash current limits
+    ///   and the range policy is [`RangePolicy::Error`].
+    /// * `Err(LM36011Error::InterlockEngaged)` if the code is above the interlock threshold
+    ///   and the interlock is not unlocked; an unlock allows one flash.
+    /// * `Err(LM36011Error::I2CError(E))` if the write failed.
     ///
     /// # Example
     ///
+    /// ```ignore
+    /// let code = lm36011::flash_code_for_ma(1000).unwrap();
+    /// driver.write_current_and_fire(code)?;
     /// ```
-    /// // Some initialization to get the device instance
-    /// //(I2C needs to be initialized first)
-    /// let mut driver = lm36011::LM36011::new(i2c); // Some initialization to get the device instance
-    /// match driver.get_device_id() {
-    ///     Ok(id) => println!("LM36011 device ID: {}", id),
-    ///     Err(e) => eprintln!("Error reading device ID: {:?}", e),
+    #[inline]
+    pub fn write_current_and_fire(&mut self, code: u8) -> Result<(), LM36011Error<E>> {
+        #[cfg(feature = "cycle-count")]
+        let start = cycle_count();
+
+        let code = self.limit_code(code, self.flash_scale())?;
+        self.check_interlock(code)?;
+
+        let enable = self.fire_enable(code)?;
+        let thermal = self.flash_brightness_flags.bits() & !MAX_BRIGHTNESS_CODE;
+        let brightness = LedFlashBrightnessFlags::from_bits_truncate(thermal | code);
+
+        // the new brightness must be in place before the mode change starts the flash
+        let buffer = [
+            Register::LEDFlashBrightnessRegister as u8,
+            brightness.bits(),
+        ];
+        self.write_registers_untraced(&buffer)
+            .map_err(LM36011Error::I2CError)?;
+        self.flash_brightness_flags = brightness;
+        self.write_fire_enable(enable)?;
+
+        #[cfg(feature = "cycle-count")]
+        {
+            self.fire_cycles = Some(cycle_count().wrapping_sub(start));
+        }
+
+        Ok(())
+    }
+
+    /// Selects the orderable variant fitted on the board, the LM36011 by default.
+    ///
+    /// [`LM36011::verify_device_id`] only accepts the ID reported by the selected variant, so
+    /// AEC-Q100 designs using the LM36011-Q1 keep the full ID verification.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut driver = LM36011::new(i2c).with_variant(Variant::Lm36011Q1);
+    /// driver.verify_device_id()?;
+    /// ```
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Returns the selected variant.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Reads back the Enable and Flags Registers after a trigger to confirm the flash.
+    ///
+    /// Useful when the strobe line or the bus may be marginal: a flash that never started shows
+    /// up as [`FlashOutcome::NotFired`] instead of going unnoticed. The cached registers are
+    /// refreshed. Call it once the flash should have timed out, e.g. after the programmed
+    /// [`FlashTimeout`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FlashOutcome)` describing what the device reports.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.fire_flash(&mut trigger)?;
+    /// delay.delay_ms(driver.get_flash_timeout().ms() + 5);
+    /// match driver.confirm_flash_fired()? {
+    ///     FlashOutcome::TimedOut => {}
+    ///     outcome => log_flash_failure(outcome),
+    /// }
+    /// ```
+    pub fn confirm_flash_fired(&mut self) -> Result<FlashOutcome, LM36011Error<E>> {
+        let flags = self.read_all()?;
+        let faults = FlagRegisterFlags::VLED_SHORT_FAULT
+            | FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT
+            | FlagRegisterFlags::UVLO_FAULT;
+        let mode = self.enable_flags & EnableRegisterFlags::MODE_MASK;
+
+        let outcome = if flags.intersects(faults) {
+            FlashOutcome::Fault(flags)
+        } else if flags.contains(FlagRegisterFlags::FLASH_TIMEOUT_FLAG) {
+            FlashOutcome::TimedOut
+        } else if mode == EnableRegisterFlags::MODE_FLASH {
+            FlashOutcome::InProgress
+        } else {
+            FlashOutcome::NotFired
+        };
+
+        Ok(outcome)
+    }
+
+    /// Sets how often [`LM36011::poll_identity`] re-reads the Device ID Register.
+    ///
+    /// With an interval of `n` every `n`-th poll reads the identity; 0 disables the reads, so
+    /// only identity changes seen by [`LM36011::read_status`] are reported.
+    pub fn set_identity_check_interval(&mut self, polls: u16) {
+        self.identity_interval = polls;
+        self.identity_countdown = polls;
+    }
+
+    /// Lightweight identity check for detecting hot-swapped heads or intermittent connectors.
+    ///
+    /// Call it from a periodic task. It reports an identity change already seen by
+    /// [`LM36011::read_status`] (which reads the Device ID Register anyway), otherwise reads the
+    /// Device ID Register when the interval set with
+    /// [`LM36011::set_identity_check_interval`] has elapsed. The identity is compared with the
+    /// one seen by [`LM36011::probe`] or the first check.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(IdentityEvent::DeviceChanged { .. })` if a different device answers.
+    /// * `Some(IdentityEvent::DeviceLost)` if the identity read failed.
+    /// * `None` if nothing changed or no check was due.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.probe()?;
+    /// driver.set_identity_check_interval(10);
+    ///
+    /// loop {
+    ///     if let Some(event) = driver.poll_identity() {
+    ///         handle_head_change(event);
+    ///     }
+    ///     delay.delay_ms(100u16);
+    /// }
+    /// ```
+    pub fn poll_identity(&mut self) -> Option<IdentityEvent> {
+        if let Some(event) = self.identity_event.take() {
+            return Some(event);
+        }
+        if self.identity_interval == 0 {
+            return None;
+        }
+
+        self.identity_countdown = self.identity_countdown.saturating_sub(1);
+        if self.identity_countdown > 0 {
+            return None;
+        }
+        self.identity_countdown = self.identity_interval;
+
+        self.check_identity()
+    }
+
+    /// Reads the Device ID Register now and compares it with the known identity.
+    ///
+    /// See [`LM36011::poll_identity`] for the returned events.
+    pub fn check_identity(&mut self) -> Option<IdentityEvent> {
+        match self.get_register(Register::DeviceIdRegister) {
+            Ok(id) => {
+                self.device_id = DeviceIdFlags::from_bits_truncate(id);
+                let info = DeviceInfo::from_register(id);
+                self.quirks = Quirks::for_revision(info.revision);
+                self.compare_identity(info)
+            }
+            Err(_) => Some(IdentityEvent::DeviceLost),
+        }
+    }
+
+    /// Compares an identity with the known one, adopting it if none is known yet.
+    fn compare_identity(&mut self, found: DeviceInfo) -> Option<IdentityEvent> {
+        match self.identity {
+            Some(expected) if expected != found => {
+                Some(IdentityEvent::DeviceChanged { expected, found })
+            }
+            Some(_) => None,
+            None => {
+                self.identity = Some(found);
+                None
+            }
+        }
+    }
+
+    /// Sets the flash current above which writes need the two-step interlock, or `None` to
+    /// disable the interlock.
+    ///
+    /// Above the threshold a flash current is only written, and a flash only fired or armed
+    /// for the STROBE input, after [`LM36011::request_unlock`] and [`LM36011::unlock`]. Each
+    /// unlock allows one flash, fired over I2C or armed for the STROBE input, and the interlock
+    /// then re-engages. Locked writes fail with [`LM36011Error::InterlockEngaged`] regardless
+    /// of the range policy. This gives products with optical eye-safety requirements a software
+    /// two-step arming pattern.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_interlock_threshold(Some(500));
+    ///
+    /// // e.g. on the first press of the arming button, valid for 2 s
+    /// let token = driver.request_unlock(now_ms(), Some(2000));
+    /// // on the second press
+    /// driver.unlock(token, now_ms())?;
+    /// driver.set_flash(Brightness::Milliamps(1200))?;
+    /// driver.trigger_flash()?; // the next flash at 1200 mA needs another unlock
+    /// ```
+    pub fn set_interlock_threshold(&mut self, threshold_ma: Option<u16>) {
+        self.interlock_ma = threshold_ma;
+        self.unlocked = false;
+    }
+
+    /// Issues the token for the first step of the interlock.
+    ///
+    /// `now` is a timestamp in ms from the caller's clock; with `valid_ms` set, the token
+    /// expires that long after `now`. Issuing a token invalidates earlier ones.
+    pub fn request_unlock(&mut self, now: u32, valid_ms: Option<u32>) -> UnlockToken {
+        self.interlock_nonce = self.interlock_nonce.wrapping_add(1);
+
+        UnlockToken {
+            nonce: self.interlock_nonce,
+            issued: now,
+            valid_ms,
+        }
+    }
+
+    /// Completes the interlock with a token from [`LM36011::request_unlock`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the next high-current flash is allowed.
+    /// * `Err(LM36011Error::InterlockEngaged)` if the token was superseded or has expired.
+    pub fn unlock(&mut self, token: UnlockToken, now: u32) -> Result<(), LM36011Error<E>> {
+        let expired = token
+            .valid_ms
+            .is_some_and(|valid_ms| now.wrapping_sub(token.issued) > valid_ms);
+        if token.nonce != self.interlock_nonce || expired {
+            return Err(LM36011Error::InterlockEngaged);
+        }
+
+        // a token is good for one unlock
+        self.interlock_nonce = self.interlock_nonce.wrapping_add(1);
+        self.unlocked = true;
+
+        Ok(())
+    }
+
+    /// Re-engages the interlock without using a pending unlock.
+    pub fn lock(&mut self) {
+        self.unlocked = false;
+    }
+
+    /// Returns whether the next high-current flash is allowed.
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked
+    }
+
+    /// Returns whether a flash code is above the interlock threshold.
+    fn above_interlock(&self, code: u8) -> bool {
+        self.interlock_ma
+            .is_some_and(|threshold_ma| FLASH_SCALE.ua(code) > threshold_ma as u32 * 1000)
+    }
+
+    /// Checks a flash code against the interlock before it is written: raising the current
+    /// above the threshold needs an unlock, which is kept for the flash it allows.
+    fn check_interlock(&self, code: u8) -> Result<(), LM36011Error<E>> {
+        // lowering the current on the device never needs an unlock
+        if !self.above_interlock(code) || code <= self.device_flash_code || self.unlocked {
+            return Ok(());
+        }
+
+        Err(LM36011Error::InterlockEngaged)
+    }
+
+    /// Checks writing the Enable Register value `enable` at the flash code `code` against the
+    /// interlock: firing or arming a flash above the threshold needs an unlock, which it
+    /// consumes.
+    fn check_flash_interlock(
+        &mut self,
+        enable: EnableRegisterFlags,
+        code: u8,
+    ) -> Result<(), LM36011Error<E>> {
+        if !enable.contains(EnableRegisterFlags::MODE_FLASH) || !self.above_interlock(code) {
+            return Ok(());
+        }
+        if !self.unlocked {
+            return Err(LM36011Error::InterlockEngaged);
+        }
+        self.unlocked = false;
+
+        Ok(())
+    }
+
+    /// Returns whether the cached Enable Register is armed for a STROBE-triggered flash, which
+    /// the interlock already allowed when it was armed.
+    fn strobe_armed(&self) -> bool {
+        self.enable_flags
+            .contains(EnableRegisterFlags::MODE_FLASH | EnableRegisterFlags::STROBE_ENABLE)
+    }
+
+    /// Sets the photobiological exposure budget enforced by [`LM36011::exposure_tick`], or
+    /// `None` to stop enforcing one.
+    pub fn set_exposure_budget(&mut self, budget: Option<ExposureBudget>) {
+        self.exposure_budget = budget;
+        self.exposure_dose = 0;
+    }
+
+    /// Returns the maximum continuous torch or IR on-time in ms at the programmed torch
+    /// current, or `None` if no budget is set or the on-time is unlimited.
+    pub fn max_torch_on_time_ms(&self) -> Option<u32> {
+        let current_ma = torch_ma_for_code(self.torch_brightness_flags.bits());
+        self.exposure_budget?.max_on_time_ms(current_ma)
+    }
+
+    /// Accounts `elapsed_ms` of operation against the exposure budget.
+    ///
+    /// Call it periodically. While in torch or IR mode the dose at the programmed torch current
+    /// accumulates; once the budget is used up the output is switched to standby. The dose is
+    /// reset whenever the output is found off, so the budget limits continuous on-time.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if the output was switched off because the budget was used up.
+    /// * `Ok(false)` otherwise.
+    /// * `Err(LM36011Error::I2CError(E))` if switching off failed.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_exposure_budget(Some(budget));
+    ///
+    /// loop {
+    ///     if driver.exposure_tick(100)? {
+    ///         ui.show("Torch switched off for eye safety");
+    ///     }
+    ///     delay.delay_ms(100u16);
     /// }
     /// ```
-    pub fn get_device_id(&mut self) -> Result<u8, E> {
-        let mut buffer = [0u8; 1];
-        self.i2c.write_read(
-            LM36011_I2C_ADDRESS,
-            &[Register::DeviceIdRegister as u8],
-            &mut buffer,
-        )?;
-        Ok(buffer[0])
+    pub fn exposure_tick(&mut self, elapsed_ms: u32) -> Result<bool, LM36011Error<E>> {
+        let Some(budget) = self.exposure_budget else {
+            return Ok(false);
+        };
+
+        let mode = self.enable_flags & EnableRegisterFlags::MODE_MASK;
+        if mode != EnableRegisterFlags::MODE_TORCH && mode != EnableRegisterFlags::MODE_IR_DRIVE {
+            self.exposure_dose = 0;
+            return Ok(false);
+        }
+
+        let current_ma = torch_ma_for_code(self.torch_brightness_flags.bits());
+        let rate = budget.rate_per_ma.saturating_mul(current_ma as u32);
+        self.exposure_dose = self
+            .exposure_dose
+            .saturating_add(rate.saturating_mul(elapsed_ms));
+        if self.exposure_dose < budget.dose_limit {
+            return Ok(false);
+        }
+
+        self.write_mode(EnableRegisterFlags::empty())?;
+        self.exposure_dose = 0;
+
+        Ok(true)
+    }
+
+    /// Returns the transport, e.g. to read the transcript of a
+    /// [`Recorder`](transcript::Recorder).
+    pub fn bus(&self) -> &I2C {
+        &self.i2c
+    }
+
+    /// Returns the transport mutably.
+    pub fn bus_mut(&mut self) -> &mut I2C {
+        &mut self.i2c
+    }
+
+    /// Destroys the driver and returns the transport, so the bus can be handed to other
+    /// drivers.
+    ///
+    /// The device keeps its current state; call [`LM36011::shutdown`] first to leave it in
+    /// standby.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.shutdown(false)?;
+    /// let i2c = driver.free();
+    /// let mut sensor = Tmp117::new(i2c);
+    /// ```
+    pub fn free(self) -> I2C {
+        self.i2c
+    }
+
+    /// Returns the CPU cycles spent in the last successful [`LM36011::fire_flash`],
+    /// [`LM36011::write_current_and_fire`] or `await_trigger_and_fire` (from the trigger edge),
+    /// up to the end of the bus write or strobe pin change.
+    ///
+    /// Cycles are read from the DWT cycle counter (Cortex-M3 and later), which the application
+    /// must enable:
+    ///
+    /// ```ignore
+    /// cp.DCB.enable_trace();
+    /// cp.DWT.enable_cycle_counter();
+    ///
+    /// driver.write_current_and_fire(code)?;
+    /// let latency_us = driver.last_fire_cycles().unwrap() / (sysclk_hz / 1_000_000);
+    /// ```
+    #[cfg(feature = "cycle-count")]
+    pub fn last_fire_cycles(&self) -> Option<u32> {
+        self.fire_cycles
     }
 
-    /// Retrieves the value of a specified register from the device.
+    /// Measures the LED current and compares it with the current of the cached mode.
     ///
-    /// This function reads a byte of data from a specified register on the LM36011 device.
-    /// It uses the I2C `write_read` method to request and retrieve the data.
+    /// Call it once the output has settled after setting a torch, IR or flash level. The expected
+    /// current is the torch current in torch and IR modes, the flash current in flash mode and 0
+    /// in standby. The tolerance is a percentage of the expected current, but at least 3 mA (one
+    /// torch step) to absorb measurement noise.
     ///
     /// # Arguments
     ///
-    /// * `reg` - The register from which the data should be read. This is specified using the `Register` enum.
+    /// * `sense` - The current measurement.
+    /// * `tolerance_percent` - The accepted deviation in percent of the expected current.
     ///
     /// # Returns
     ///
-    /// * `Ok(u8)` containing the byte value read from the specified register if the read operation was successful.
-    /// * `Err(E)` if there was an error during the read operation. The error type `E` is determined by the I2C interface.
+    /// * `Ok(CurrentCheck)` with the expected and measured currents.
+    /// * `Err(LM36011Error::CurrentSenseError)` if the current could not be measured.
     ///
     /// # Example
     ///
-    /// ```
-    /// // Some initialization to get the device instance
-    /// //(I2C needs to be initialized first)
-    /// let mut driver = lm36011::LM36011::new(i2c);
-    /// match driver.get_register(Register::DeviceIdRegister) {
-    ///     Ok(value) => println!("Register value: {}", value),
-    ///     Err(e) => eprintln!("Error reading register: {:?}", e),
+    /// ```ignore
+    /// driver.set_torch(Brightness::Milliamps(150))?;
+    /// delay.delay_ms(5u16);
+    /// if let CurrentCheck::Deviation { measured_ma, .. } = driver.verify_current(&mut adc, 10)? {
+    ///     report_led_fault(measured_ma);
     /// }
     /// ```
-    pub fn get_register(&mut self, reg: Register) -> Result<u8, E> {
-        let mut buffer = [0u8; 1];
-        self.i2c
-            .write_read(LM36011_I2C_ADDRESS, &[reg as u8], &mut buffer)?;
-        Ok(buffer[0])
+    pub fn verify_current<S: CurrentSense>(
+        &mut self,
+        sense: &mut S,
+        tolerance_percent: u8,
+    ) -> Result<CurrentCheck, LM36011Error<E>> {
+        let measured_ma = sense
+            .read_ma()
+            .map_err(|_| LM36011Error::CurrentSenseError)?;
+
+        let mode = self.enable_flags & EnableRegisterFlags::MODE_MASK;
+        let expected_ma = if mode == EnableRegisterFlags::MODE_FLASH {
+            flash_ma_for_code(self.flash_brightness_flags.bits())
+        } else if mode.is_empty() {
+            0
+        } else {
+            torch_ma_for_code(self.torch_brightness_flags.bits())
+        };
+
+        let tolerance_ma = (expected_ma as u32 * tolerance_percent as u32 / 100).max(3);
+        if expected_ma.abs_diff(measured_ma) as u32 <= tolerance_ma {
+            Ok(CurrentCheck::WithinTolerance {
+                expected_ma,
+                measured_ma,
+            })
+        } else {
+            Ok(CurrentCheck::Deviation {
+                expected_ma,
+                measured_ma,
+            })
+        }
     }
 
-    /// Sets the value of a specified register on the device.
+    /// Sets the operating mode, changing only the MODE bits of the Enable Register.
     ///
-    /// This function writes a given data byte to a specified register on the LM36011 device.
-    /// It uses the I2C `write` method to send the data.
+    /// The other enable bits (IVFM, strobe) are kept from the cache.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `reg` - The register to which the data should be written. This is specified using the `Register` enum.
-    /// * `data` - The data byte to be written to the specified register.
+    /// ```ignore
+    /// driver.set_mode(OperatingMode::Torch)?;
+    /// ```
+    pub fn set_mode(&mut self, mode: OperatingMode) -> Result<(), LM36011Error<E>> {
+        self.write_mode(EnableRegisterFlags::from_bits_truncate(mode.bits()))
+    }
+
+    /// Reads the operating mode from the Enable Register.
     ///
-    /// # Returns
+    /// The device returns to standby by itself, e.g. at the end of a flash or on a fault, so
+    /// the register is read rather than the cache. The cached Enable Register is updated.
+    pub fn get_mode(&mut self) -> Result<OperatingMode, LM36011Error<E>> {
+        let enable = self
+            .get_register(Register::EnableRegister)
+            .map_err(LM36011Error::I2CError)?;
+        self.enable_flags = EnableRegisterFlags::from_bits_truncate(enable);
+
+        Ok(OperatingMode::from_bits(enable))
+    }
+
+    /// Turns the torch on at the programmed torch current in a single Enable Register write.
     ///
-    /// * `Ok(())` if the write operation was successful.
-    /// * `Err(E)` if there was an error during the write operation. The error type `E` is determined by the I2C interface.
+    /// Selects torch mode and disables the STROBE input, so the torch lights immediately
+    /// instead of waiting for a strobe. The IVFM enable is kept from the cache. Like
+    /// [`LM36011::enable_ir_drive`], the cached torch code is checked against the torch limits
+    /// in force first; with [`RangePolicy::ClampToLimit`] an out-of-range code is lowered.
     ///
     /// # Example
     ///
+    /// ```ignore
+    /// driver.set_torch_current_ma(100)?;
+    /// driver.enable_torch()?;
+    /// // ...
+    /// driver.disable_output()?;
     /// ```
-    /// // Some initialization to get the device instance
-    /// //(I2C needs to be initialized first)
-    /// let mut driver = lm36011::LM36011::new(i2c);
-    /// let result = driver.set_register(Register::DeviceIdRegister, 0x01);
-    /// if result.is_err() {
-    ///     // Handle the error
-    /// }
-    /// ```
-    pub fn set_register(&mut self, reg: Register, data: u8) -> Result<(), LM36011Error<E>> {
-        let buffer: [u8; 2] = [reg as u8, data];
-        self.i2c
-            .write(LM36011_I2C_ADDRESS, &buffer)
-            .map_err(LM36011Error::I2CError)
+    pub fn enable_torch(&mut self) -> Result<(), LM36011Error<E>> {
+        let code = self.torch_brightness_flags.bits() & MAX_BRIGHTNESS_CODE;
+        let limited = self.limit_code(code, self.torch_scale())?;
+        if limited != code {
+            self.write_torch_code(limited)?;
+        }
+
+        let enable = (self.enable_flags
+            - EnableRegisterFlags::MODE_MASK
+            - EnableRegisterFlags::STROBE_ENABLE)
+            | EnableRegisterFlags::MODE_TORCH;
+
+        self.write_enable(enable)
     }
 
-    /// Reads all the registers of the LM36011 and saves the register states to the respective bitflag structs.
+    /// Switches the device to IR drive mode in a single Enable Register write.
     ///
-    /// This function performs a single I2C read operation starting from the `EnableRegister` and reads 6 bytes,
-    /// which correspond to the 6 registers of the LM36011. The read values are then saved to the respective
-    /// bitflag structs for easy access and manipulation.
+    /// In IR mode the LED current comes from the Torch Brightness Register, not the flash
+    /// register, so program it with the torch setters (0 - 376 mA). The cached torch code is
+    /// checked against the torch limits in force before the mode changes; with
+    /// [`RangePolicy::ClampToLimit`] an out-of-range code is lowered first.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// * `Ok(())` if the I2C read operation is successful.
-    /// * `Err(E)` if the I2C read operation fails, where `E` is the error type of the I2C operations.
+    /// * `strobe` - `true` to use the STROBE input as the level-sensitive IR trigger, so the LED
+    ///   is on while STROBE is high; `false` to drive the LED immediately and disable STROBE.
     ///
-    /// # Usage
+    /// # Returns
     ///
-    /// ```rust
-    /// let mut driver = LM36011::new(i2c_instance);
+    /// * `Ok(())` if the operation was successful.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the torch code is above the torch limits and
+    ///   the range policy is [`RangePolicy::Error`]; the mode is not changed.
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
     ///
-    /// if let Err(e) = driver.read_status() {
-    ///     // Handle the error `e` here.
-    /// }
+    /// # Example
+    ///
+    /// ```ignore
+    /// driver.set_torch_current_ma(300)?;
+    /// driver.enable_ir_drive(true)?; // camera sync drives STROBE
     /// ```
-    pub fn read_status(&mut self) -> Result<(), LM36011Error<E>> {
-        // Read all 6 LM36011 registers
-        let mut buffer = [0u8; 6];
-        self.i2c
-            .write_read(
-                LM36011_I2C_ADDRESS,
-                &[Register::EnableRegister as u8],
-                &mut buffer,
-            )
-            .map_err(LM36011Error::I2CError)?;
+    pub fn enable_ir_drive(&mut self, strobe: bool) -> Result<(), LM36011Error<E>> {
+        let code = self.torch_brightness_flags.bits() & MAX_BRIGHTNESS_CODE;
+        let limited = self.limit_code(code, self.torch_scale())?;
+        if limited != code {
+            self.write_torch_code(limited)?;
+        }
 
-        // Save registers to the struct
-        self.enable_flags = EnableRegisterFlags::from_bits_truncate(buffer[0]);
-        self.config_flags = ConfigurationRegisterFlags::from_bits_truncate(buffer[1]);
-        self.flash_brightness_flags = LedFlashBrightnessFlags::from_bits_truncate(buffer[2]);
-        self.torch_brightness_flags = LedTorchBrightnessFlags::from_bits_truncate(buffer[3]);
-        self.flag_register_flags = FlagRegisterFlags::from_bits_truncate(buffer[4]);
-        self.device_id = DeviceIdFlags::from_bits_truncate(buffer[5]);
+        let mut enable = (self.enable_flags
+            - EnableRegisterFlags::MODE_MASK
+            - EnableRegisterFlags::STROBE_ENABLE
+            - EnableRegisterFlags::STROBE_TYPE_EDGE_TRIGGERED)
+            | EnableRegisterFlags::MODE_IR_DRIVE;
+        if strobe {
+            enable |= EnableRegisterFlags::STROBE_ENABLE;
+        }
 
-        Ok(())
+        self.write_enable(enable)
     }
 
-    /// Writes the bitflags settings to the LM36011 device.
+    /// Turns the output off by returning the device to standby.
     ///
-    /// This function will take the current settings stored in the bitflag structs and write them to the
-    /// respective registers on the LM36011 device using I2C.
+    /// Only the MODE bits of the Enable Register change; the brightness settings are kept, so
+    /// a later [`LM36011::enable_torch`] or [`LM36011::enable_ir_drive`] lights at the same
+    /// current.
+    pub fn disable_output(&mut self) -> Result<(), LM36011Error<E>> {
+        self.write_mode(EnableRegisterFlags::empty())
+    }
+
+    /// Sets the hardware flash time-out, changing only bits 4:1 of the Configuration Register.
     ///
-    /// # Examples
+    /// The IVFM level and torch ramp bits are kept from the cache.
     ///
-    /// ```rust
-    /// // Assuming `i2c` is an initialized I2C instance`
-    /// let mut driver = LM36011::new(i2c_instance);
-    /// // Modify some settings
-    /// driver.enable_flags.insert(EnableRegisterFlags::MODE_TORCH);
-    /// driver.config_flags.insert(ConfigurationRegisterFlags::IVFM_3_4V);
+    /// # Example
     ///
-    /// // Write the modified settings to the device
-    /// match lm36011.write_status() {
-    ///     Ok(_) => println!("Settings written successfully!"),
-    ///     Err(e) => println!("Failed to write settings: {:?}", e),
-    /// }
+    /// ```ignore
+    /// driver.set_flash_timeout(FlashTimeout::Ms200)?;
     /// ```
+    pub fn set_flash_timeout(&mut self, timeout: FlashTimeout) -> Result<(), LM36011Error<E>> {
+        self.write_flash_timeout(timeout)
+    }
+
+    /// Returns the flash time-out of the cached Configuration Register.
+    pub fn get_flash_timeout(&self) -> FlashTimeout {
+        FlashTimeout::from_bits(self.config_flags.bits())
+    }
+
+    /// Sets the IVFM threshold, changing only bits 7:5 of the Configuration Register.
     ///
-    pub fn write_status(&mut self) -> Result<(), LM36011Error<E>> {
-        // create a buffer with all of the settings
-        let buffer = [
-            0x01,
-            self.enable_flags.bits(),
-            self.config_flags.bits(),
-            self.flash_brightness_flags.bits(),
-            self.torch_brightness_flags.bits(),
-            //self.flag_register_flags.bits(),
-            //self.device_id.bits(),
-        ];
+    /// The flash time-out and torch ramp bits are kept from the cache. IVFM itself is enabled
+    /// by `EnableRegisterFlags::IVFM_ENABLE`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // the battery is sagging, trip the monitor later for brighter flashes
+    /// driver.set_ivfm_threshold(IvfmThreshold::V3_0)?;
+    /// ```
+    pub fn set_ivfm_threshold(&mut self, threshold: IvfmThreshold) -> Result<(), LM36011Error<E>> {
+        self.write_config_field(IVFM_THRESHOLD_MASK, threshold.bits())
+    }
 
-        self.i2c
-            .write(LM36011_I2C_ADDRESS, &buffer)
-            .map_err(LM36011Error::I2CError)
+    /// Returns the IVFM threshold of the cached Configuration Register.
+    pub fn get_ivfm_threshold(&self) -> IvfmThreshold {
+        IvfmThreshold::from_bits(self.config_flags.bits())
     }
 
-    /// Performs a software reset on the LM36011 device.
+    /// Sets the torch ramp, changing only bit 0 of the Configuration Register.
     ///
-    /// This function sends a specific command to the LM36011 device to initiate a software reset.
-    /// The reset command is sent to the address `0x06` with the data `0b1000_0000`.
+    /// The IVFM threshold and flash time-out bits are kept from the cache.
+    pub fn set_torch_ramp(&mut self, ramp: TorchRamp) -> Result<(), LM36011Error<E>> {
+        self.write_config_field(
+            ConfigurationRegisterFlags::TORCH_RAMP_1MS.bits(),
+            ramp.bits(),
+        )
+    }
+
+    /// Returns the torch ramp of the cached Configuration Register.
+    pub fn get_torch_ramp(&self) -> TorchRamp {
+        TorchRamp::from_bits(self.config_flags.bits())
+    }
+
+    /// Configures the STROBE input, changing only bits 3:2 of the Enable Register.
     ///
-    /// # Examples
+    /// The mode and IVFM bits are kept from the cache, so a strobe-triggered flash can be set
+    /// up (or torn down) without touching the mode. The STROBE input only fires the LED in
+    /// flash, torch or IR mode. The flash mode left by an I2C-started flash is written as
+    /// standby, and disabling the STROBE input while armed for a flash drops to standby, so
+    /// this never fires a flash itself.
     ///
-    /// ```rust
-    /// let mut device = LM36011::new(i2c_instance);
+    /// # Example
     ///
-    /// match device.software_reset() {
-    ///     Ok(_) => println!("Software reset successful!"),
-    ///     Err(e) => println!("Software reset failed with error: {:?}", e),
-    /// }
+    /// ```ignore
+    /// driver.configure_strobe(StrobeConfig {
+    ///     enabled: true,
+    ///     trigger: StrobeTrigger::Edge,
+    /// })?;
+    /// driver.set_mode(OperatingMode::Flash)?; // waits for the STROBE edge
     /// ```
+    pub fn configure_strobe(&mut self, strobe: StrobeConfig) -> Result<(), LM36011Error<E>> {
+        self.write_enable_field(
+            EnableRegisterFlags::STROBE_ENABLE | EnableRegisterFlags::STROBE_TYPE_EDGE_TRIGGERED,
+            EnableRegisterFlags::from_bits_truncate(strobe.bits()),
+        )
+    }
+
+    /// Enables input voltage flash monitoring, changing only bit 4 of the Enable Register.
     ///
-    /// # Errors
+    /// The mode and strobe bits are kept from the cache, apart from the flash mode left by an
+    /// I2C-started flash, which has already ended and is written as standby. The threshold is set with
+    /// [`LM36011::set_ivfm_threshold`].
     ///
-    /// Returns an `Err` variant of `LM36011Error` if there's an I2C communication error.
+    /// # Example
+    ///
+    /// ```ignore
+    /// if running_on_battery {
+    ///     driver.enable_ivfm()?;
+    /// } else {
+    ///     driver.disable_ivfm()?;
+    /// }
+    /// ```
+    pub fn enable_ivfm(&mut self) -> Result<(), LM36011Error<E>> {
+        self.write_enable_field(
+            EnableRegisterFlags::IVFM_ENABLE,
+            EnableRegisterFlags::IVFM_ENABLE,
+        )
+    }
 
-    pub fn software_reset(&mut self) -> Result<(), LM36011Error<E>> {
-        let buffer = [0x06, 0b1000_0000];
-        self.i2c
-            .write(LM36011_I2C_ADDRESS, &buffer)
-            .map_err(LM36011Error::I2CError)
+    /// Disables input voltage flash monitoring, changing only bit 4 of the Enable Register.
+    ///
+    /// See [`LM36011::enable_ivfm`].
+    pub fn disable_ivfm(&mut self) -> Result<(), LM36011Error<E>> {
+        self.write_enable_field(
+            EnableRegisterFlags::IVFM_ENABLE,
+            EnableRegisterFlags::empty(),
+        )
     }
 
-    /// Verifies the device ID of the LM36011.
+    /// Returns whether IVFM is enabled in the cached Enable Register.
+    pub fn ivfm_enabled(&self) -> bool {
+        self.enable_flags.contains(EnableRegisterFlags::IVFM_ENABLE)
+    }
+
+    /// Returns the STROBE input settings of the cached Enable Register.
+    pub fn get_strobe_config(&self) -> StrobeConfig {
+        StrobeConfig::from_bits(self.enable_flags.bits())
+    }
+
+    /// Enables or disables the thermal current scale-back, bit 7 of the Flash Brightness
+    /// Register.
     ///
-    /// This function reads the current status of the LM36011, including its device ID,
-    /// and then checks if the silicon revision mask matches the expected value.
+    /// The register is read from the device and only bit 7 is changed, so the flash code the
+    /// device holds is kept even if the cache is stale. The cached register is updated with
+    /// the value written. Later flash current writes keep the setting.
     ///
-    /// # Returns
+    /// # Example
     ///
-    /// * `Ok(true)` if the device ID matches the expected value.
-    /// * `Err(LM36011Error::InvalidInput)` if the device ID does not match the expected value.
-    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    /// ```ignore
+    /// driver.set_thermal_scaleback(false)?;
+    /// ```
+    pub fn set_thermal_scaleback(&mut self, enabled: bool) -> Result<(), LM36011Error<E>> {
+        let mut brightness = LedFlashBrightnessFlags::from_bits_truncate(
+            self.get_register(Register::LEDFlashBrightnessRegister)
+                .map_err(LM36011Error::I2CError)?,
+        );
+        brightness.set(LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED, enabled);
+
+        self.set_register(Register::LEDFlashBrightnessRegister, brightness.bits())?;
+        self.flash_brightness_flags = brightness;
+
+        Ok(())
+    }
+
+    /// Returns whether the thermal current scale-back is enabled in the cached Flash
+    /// Brightness Register.
+    pub fn get_thermal_scaleback(&self) -> bool {
+        self.flash_brightness_flags
+            .contains(LedFlashBrightnessFlags::THERMAL_SCALEBACK_ENABLED)
+    }
+
+    /// Returns the flash current of the cached Flash Brightness Register in mA, rounded down.
+    ///
+    /// Call [`LM36011::read_status`] first to decode what the device holds rather than what
+    /// the driver last wrote.
     ///
     /// # Example
     ///
-    /// ```rust
-    /// let mut driver = LM36011::new(i2c);
-    /// match driver.verify_device_id() {
-    ///     Ok(true) => println!("Device ID verified!"),
-    ///     Err(LM36011Error::DeviceIDError) => println!("Device ID does not match!"),
-    ///     Err(LM36011Error::I2CError(_)) => println!("Error verifying device ID due to I2C communication"),
-    ///     _ => println!("Some other error occurred"),
-    /// }
+    /// ```ignore
+    /// driver.set_flash_current_ma(500)?;
+    /// display.show(driver.get_flash_current_ma()); // 491
     /// ```
-    pub fn verify_device_id(&mut self) -> Result<bool, LM36011Error<E>> {
-        match self.read_status() {
-            Ok(_) => (),
-            Err(e) => return Err(e),
-        }
+    pub fn get_flash_current_ma(&self) -> u16 {
+        flash_ma_for_code(self.flash_brightness_flags.bits())
+    }
 
-        // Check if the read value matches the expected device ID
-        if self.device_id & DeviceIdFlags::SILICON_REVISION_MASK
-            == DeviceIdFlags::from_bits_truncate(0x01)
-        {
-            Ok(true)
-        } else {
-            Err(LM36011Error::DeviceIDError)
-        }
+    /// Returns the torch (and IR) current of the cached Torch Brightness Register in mA,
+    /// rounded down.
+    ///
+    /// See [`LM36011::get_flash_current_ma`].
+    pub fn get_torch_current_ma(&self) -> u16 {
+        torch_ma_for_code(self.torch_brightness_flags.bits())
     }
+
     // similarly, you can add other methods with detailed documentation.
 }
+
+#[cfg(feature = "eh0")]
+impl<I2C, E> LM36011<WriteThenRead<I2C>>
+where
+    I2C: i2c::Write<Error = E> + i2c::Read<Error = E>,
+{
+    /// Creates a new instance of the LM36011 for I2C masters without a combined `WriteRead`.
+    ///
+    /// Register reads are issued as a write of the register address followed by a separate read,
+    /// see [`WriteThenRead`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // `i2c` only implements `Write` and `Read` (e.g. a bit-banged bus)
+    /// let mut driver = lm36011::LM36011::new_write_then_read(i2c);
+    /// driver.read_status()?;
+    /// ```
+    pub fn new_write_then_read(i2c: I2C) -> Self {
+        Self::new(WriteThenRead::new(i2c))
+    }
+}
+
+/// Reads the DWT cycle counter for the fire-latency instrumentation.
+#[cfg(feature = "cycle-count")]
+#[inline(always)]
+pub(crate) fn cycle_count() -> u32 {
+    cortex_m::peripheral::DWT::cycle_count()
+}
+
+/// Transport for I2C masters that implement the embedded-hal 0.2 `Transactional` trait.
+///
+/// Every register access goes through `Transactional::exec` as one transaction, multi-register
+/// writes and reads included: a read is the register address write and the data read, joined
+/// by a repeated start. Suits HALs that offer `Transactional` without the separate `Write` and
+/// `WriteRead` traits the default transport needs.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut driver = LM36011::new(TransactionalBus::new(i2c));
+/// ```
+pub struct TransactionalBus<I2C> {
+    i2c: I2C,
+}
+
+impl<I2C> TransactionalBus<I2C> {
+    /// Wraps an I2C master implementing `Transactional`.
+    pub fn new(i2c: I2C) -> Self {
+        Self { i2c }
+    }
+
+    /// Returns the wrapped I2C master.
+    pub fn into_inner(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C: i2c::Transactional> BusDevice for TransactionalBus<I2C> {
+    type Error = I2C::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.i2c.exec(address, &mut [i2c::Operation::Write(bytes)])
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.i2c.exec(
+            address,
+            &mut [i2c::Operation::Write(bytes), i2c::Operation::Read(buffer)],
+        )
+    }
+}
+
+/// Adapter for I2C masters that implement `Write` and `Read` but not `WriteRead`.
+///
+/// Bit-banged buses and some I2C bridges cannot issue a repeated start. This adapter implements
+/// `WriteRead` as a write of the register address followed by a separate read transaction. The
+/// LM36011 keeps its register pointer across the STOP condition, so the read returns the
+/// addressed register.
+pub struct WriteThenRead<I2C> {
+    i2c: I2C,
+}
+
+impl<I2C> WriteThenRead<I2C> {
+    /// Wraps an I2C master that lacks `WriteRead`.
+    pub fn new(i2c: I2C) -> Self {
+        Self { i2c }
+    }
+
+    /// Returns the wrapped I2C master.
+    pub fn into_inner(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C: i2c::Write> i2c::Write for WriteThenRead<I2C> {
+    type Error = I2C::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.i2c.write(address, bytes)
+    }
+}
+
+impl<I2C, E> i2c::WriteRead for WriteThenRead<I2C>
+where
+    I2C: i2c::Write<Error = E> + i2c::Read<Error = E>,
+{
+    type Error = E;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), E> {
+        // set the register pointer, then read back without a repeated start
+        self.i2c.write(address, bytes)?;
+        self.i2c.read(address, buffer)
+    }
+}
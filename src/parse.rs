@@ -0,0 +1,173 @@
+//! Parsing of human-written currents and durations.
+//!
+//! Accepts strings such as `"150mA"`, `"1.2A"`, `"25%"`, `"400ms"` or `"1.5s"`, as typed into a
+//! CLI, a debug console or a configuration file. Units are case-insensitive and may be separated
+//! from the number by whitespace. Decimal values are converted with integer arithmetic and
+//! rounded down.
+
+use crate::Brightness;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str::FromStr;
+
+/// Why a string could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The string is empty.
+    Empty,
+    /// The number part is not a valid decimal number.
+    InvalidNumber,
+    /// The unit is missing or not accepted for the value.
+    UnknownUnit,
+    /// The value does not fit the target type.
+    OutOfRange,
+}
+
+/// Error returned by the parsers, holding the rejected input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The string that was parsed.
+    pub input: String,
+    /// Why it was rejected.
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(input: &str, kind: ParseErrorKind) -> Self {
+        Self {
+            input: input.to_string(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            ParseErrorKind::Empty => "empty value",
+            ParseErrorKind::InvalidNumber => "invalid number",
+            ParseErrorKind::UnknownUnit => "missing or unknown unit",
+            ParseErrorKind::OutOfRange => "value out of range",
+        };
+        write!(f, "{} in {:?}", reason, self.input)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Parses a current (`"150mA"`, `"1.2A"`) or a percentage (`"25%"`) into a [`Brightness`].
+///
+/// # Example
+///
+/// ```
+/// use lm36011::parse::parse_current;
+/// use lm36011::Brightness;
+///
+/// assert_eq!(parse_current("150mA"), Ok(Brightness::Milliamps(150)));
+/// assert_eq!(parse_current("1.2 A"), Ok(Brightness::Milliamps(1200)));
+/// assert_eq!(parse_current("25%"), Ok(Brightness::Percent(25)));
+/// assert!(parse_current("150").is_err());
+/// ```
+pub fn parse_current(input: &str) -> Result<Brightness, ParseError> {
+    let (number, unit) = split_unit(input)?;
+
+    let (scale, percent) = match unit.to_ascii_lowercase().as_str() {
+        "ma" => (1, false),
+        "a" => (1000, false),
+        "%" => (1, true),
+        _ => return Err(ParseError::new(input, ParseErrorKind::UnknownUnit)),
+    };
+    let value = parse_scaled(input, number, scale)?;
+
+    if percent {
+        u8::try_from(value)
+            .ok()
+            .filter(|percent| *percent <= 100)
+            .map(Brightness::Percent)
+    } else {
+        u16::try_from(value).ok().map(Brightness::Milliamps)
+    }
+    .ok_or_else(|| ParseError::new(input, ParseErrorKind::OutOfRange))
+}
+
+/// Parses a duration (`"400ms"`, `"1.5s"`, `"2500us"`) into milliseconds.
+///
+/// The result can be passed to every API taking an [`IntoMillis`](crate::IntoMillis).
+///
+/// # Example
+///
+/// ```
+/// use lm36011::parse::parse_duration_ms;
+///
+/// assert_eq!(parse_duration_ms("400ms"), Ok(400));
+/// assert_eq!(parse_duration_ms("1.5s"), Ok(1500));
+/// assert_eq!(parse_duration_ms("2500us"), Ok(2));
+/// ```
+pub fn parse_duration_ms(input: &str) -> Result<u32, ParseError> {
+    let (number, unit) = split_unit(input)?;
+
+    match unit.to_ascii_lowercase().as_str() {
+        "ms" => parse_scaled(input, number, 1),
+        "s" => parse_scaled(input, number, 1000),
+        "us" | "µs" => parse_scaled(input, number, 1).map(|us| us / 1000),
+        _ => Err(ParseError::new(input, ParseErrorKind::UnknownUnit)),
+    }
+}
+
+impl FromStr for Brightness {
+    type Err = ParseError;
+
+    /// Parses a brightness with [`parse_current`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_current(s)
+    }
+}
+
+/// Splits a trimmed input into its number and unit parts.
+fn split_unit(input: &str) -> Result<(&str, &str), ParseError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(ParseError::new(input, ParseErrorKind::Empty));
+    }
+
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    Ok((number, unit.trim_start()))
+}
+
+/// Parses a decimal number multiplied by `scale` (a power of ten), dropping finer digits.
+fn parse_scaled(input: &str, number: &str, scale: u32) -> Result<u32, ParseError> {
+    let invalid = || ParseError::new(input, ParseErrorKind::InvalidNumber);
+    let out_of_range = || ParseError::new(input, ParseErrorKind::OutOfRange);
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid());
+    }
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let whole = match whole {
+        "" => 0,
+        digits => digits.parse::<u32>().map_err(|_| out_of_range())?,
+    };
+    let mut value = whole.checked_mul(scale).ok_or_else(out_of_range)?;
+
+    let mut place = scale;
+    for digit in fraction.bytes() {
+        place /= 10;
+        if place == 0 {
+            break;
+        }
+        value = value
+            .checked_add((digit - b'0') as u32 * place)
+            .ok_or_else(out_of_range)?;
+    }
+
+    Ok(value)
+}
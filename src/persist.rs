@@ -0,0 +1,253 @@
+//! Save and restore of the LM36011 light settings.
+//!
+//! [`Settings`] is a snapshot of the writable registers that can be encoded into a small
+//! checksummed record. A [`SettingsStore`] keeps that record in non-volatile memory so the
+//! settings survive a power cycle; with the `embedded-storage` feature, `NorFlashStore`
+//! provides one for any `embedded-storage` NOR flash.
+
+use crate::{
+    BusDevice, ConfigurationRegisterFlags, EnableRegisterFlags, LM36011Error,
+    LedFlashBrightnessFlags, LedTorchBrightnessFlags, Register, LM36011,
+};
+
+/// Marks the start of an encoded settings record.
+const MAGIC: [u8; 2] = *b"LM";
+
+/// Version of the encoded settings record layout.
+const VERSION: u8 = 1;
+
+/// Snapshot of the writable LM36011 registers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Settings {
+    pub enable: EnableRegisterFlags,
+    pub config: ConfigurationRegisterFlags,
+    pub flash_brightness: LedFlashBrightnessFlags,
+    pub torch_brightness: LedTorchBrightnessFlags,
+}
+
+impl Settings {
+    /// Length of an encoded settings record in bytes.
+    pub const ENCODED_LEN: usize = 8;
+
+    /// The register values after a power-on or software reset, per the datasheet: standby with
+    /// IVFM and the STROBE input disabled, IVFM at 2.9 V, a 600 ms time-out, the 1 ms torch ramp,
+    /// the lowest flash and torch currents and the thermal scale-back disabled. Bit 5 of the
+    /// Enable Register is reserved and reads back as 1.
+    pub const POWER_ON_RESET: Self = Self {
+        enable: EnableRegisterFlags::from_bits_truncate(0x20),
+        config: ConfigurationRegisterFlags::from_bits_truncate(0x15),
+        flash_brightness: LedFlashBrightnessFlags::FLASH_11MA,
+        torch_brightness: LedTorchBrightnessFlags::TORCH_2_4MA,
+    };
+
+    /// Encodes the settings into a record with a magic, version and checksum.
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut record = [
+            MAGIC[0],
+            MAGIC[1],
+            VERSION,
+            self.enable.bits(),
+            self.config.bits(),
+            self.flash_brightness.bits(),
+            self.torch_brightness.bits(),
+            0,
+        ];
+        record[Self::ENCODED_LEN - 1] = crate::checksum(&record[..Self::ENCODED_LEN - 1]);
+
+        record
+    }
+
+    /// Decodes a record produced by [`Settings::encode`].
+    ///
+    /// Returns `None` if the record is too short, has a different magic or version, or fails
+    /// the checksum, e.g. when reading erased memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lm36011::persist::Settings;
+    ///
+    /// assert_eq!(Settings::decode(&[0xFF; Settings::ENCODED_LEN]), None);
+    /// ```
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let record = bytes.get(..Self::ENCODED_LEN)?;
+        let (body, sum) = record.split_at(Self::ENCODED_LEN - 1);
+
+        if body[..2] != MAGIC || body[2] != VERSION || sum[0] != crate::checksum(body) {
+            return None;
+        }
+
+        Some(Self {
+            enable: EnableRegisterFlags::from_bits_truncate(body[3]),
+            config: ConfigurationRegisterFlags::from_bits_truncate(body[4]),
+            flash_brightness: LedFlashBrightnessFlags::from_bits_truncate(body[5]),
+            torch_brightness: LedTorchBrightnessFlags::from_bits_truncate(body[6]),
+        })
+    }
+}
+
+impl Default for Settings {
+    /// Returns [`Settings::POWER_ON_RESET`].
+    fn default() -> Self {
+        Self::POWER_ON_RESET
+    }
+}
+
+/// Non-volatile storage for a [`Settings`] record.
+pub trait SettingsStore {
+    /// Error type of the underlying storage.
+    type Error;
+
+    /// Stores the settings, replacing any previously saved settings.
+    fn save(&mut self, settings: &Settings) -> Result<(), Self::Error>;
+
+    /// Loads the saved settings, or `None` if nothing valid has been saved.
+    fn load(&mut self) -> Result<Option<Settings>, Self::Error>;
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Returns a snapshot of the cached writable registers.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            enable: self.enable_flags,
+            config: self.config_flags,
+            flash_brightness: self.flash_brightness_flags,
+            torch_brightness: self.torch_brightness_flags,
+        }
+    }
+
+    /// Writes the given settings to the device and updates the cached registers.
+    ///
+    /// The reserved bits of the Enable and Torch Brightness Registers are kept from the cache
+    /// rather than taken from `settings`, which may come from another unit. A snapshot taken
+    /// while a flash started over I2C was cached is applied in standby, so restoring it does
+    /// not fire the flash again.
+    ///
+    /// Brightness codes above the current flash or torch limits, a flash current that needs
+    /// the eye-safety interlock unlocked, or turning the output on while the fault cut-off
+    /// holds it off, are rejected before anything is written or cached.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if let Some(settings) = store.load()? {
+    ///     driver.apply_settings(&settings)?;
+    /// }
+    /// ```
+    pub fn apply_settings(&mut self, settings: &Settings) -> Result<(), LM36011Error<E>> {
+        let enable = Self::without_one_shot_flash(settings.enable);
+        self.check_currents(
+            enable,
+            settings.flash_brightness.bits(),
+            settings.torch_brightness.bits(),
+        )?;
+        self.check_cutoff(enable)?;
+
+        self.enable_flags = EnableRegisterFlags::from_bits_truncate(
+            self.with_reserved(Register::EnableRegister, enable.bits()),
+        );
+        self.config_flags = settings.config;
+        self.flash_brightness_flags = settings.flash_brightness;
+        self.torch_brightness_flags =
+            LedTorchBrightnessFlags::from_bits_truncate(self.with_reserved(
+                Register::LEDTorchBrightnessRegister,
+                settings.torch_brightness.bits(),
+            ));
+        self.suspended_mode = None;
+
+        self.write_cached_registers()
+    }
+}
+
+#[cfg(feature = "embedded-storage")]
+pub use self::nor_flash::NorFlashStore;
+
+#[cfg(feature = "embedded-storage")]
+mod nor_flash {
+    use super::{Settings, SettingsStore};
+    use embedded_storage::nor_flash::NorFlash;
+
+    /// Largest write or read granularity supported by [`NorFlashStore`].
+    const MAX_WORD: usize = 64;
+
+    /// [`SettingsStore`] on one erase sector of an `embedded-storage` NOR flash.
+    ///
+    /// The record is written at the start of the sector, padded to the flash write size. The
+    /// sector is only erased and rewritten when the settings differ from the stored ones.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // the last sector of the flash holds the light settings
+    /// let offset = (flash.capacity() - MyFlash::ERASE_SIZE) as u32;
+    /// let mut store = NorFlashStore::new(flash, offset).unwrap();
+    ///
+    /// store.save(&driver.settings())?;
+    /// ```
+    pub struct NorFlashStore<F> {
+        flash: F,
+        offset: u32,
+    }
+
+    impl<F: NorFlash> NorFlashStore<F> {
+        /// Creates a store on the erase sector starting at `offset`.
+        ///
+        /// Returns `None` if `offset` is not aligned to the erase size or the sector does not
+        /// fit in the flash.
+        pub fn new(flash: F, offset: u32) -> Option<Self> {
+            const {
+                assert!(
+                    F::WRITE_SIZE <= MAX_WORD && F::READ_SIZE <= MAX_WORD,
+                    "flash write or read size is too large"
+                )
+            };
+
+            let end = offset as usize + F::ERASE_SIZE;
+            if offset as usize % F::ERASE_SIZE != 0 || end > flash.capacity() {
+                return None;
+            }
+
+            Some(Self { flash, offset })
+        }
+
+        /// Releases the flash.
+        pub fn into_inner(self) -> F {
+            self.flash
+        }
+
+        /// Length of the record on flash, a multiple of the write and read sizes.
+        fn record_len() -> usize {
+            let word = F::WRITE_SIZE.max(F::READ_SIZE);
+            Settings::ENCODED_LEN.div_ceil(word) * word
+        }
+    }
+
+    impl<F: NorFlash> SettingsStore for NorFlashStore<F> {
+        type Error = F::Error;
+
+        fn save(&mut self, settings: &Settings) -> Result<(), Self::Error> {
+            if self.load()? == Some(*settings) {
+                return Ok(());
+            }
+
+            let mut buffer = [0xFF; Settings::ENCODED_LEN + MAX_WORD];
+            buffer[..Settings::ENCODED_LEN].copy_from_slice(&settings.encode());
+
+            self.flash
+                .erase(self.offset, self.offset + F::ERASE_SIZE as u32)?;
+            self.flash.write(self.offset, &buffer[..Self::record_len()])
+        }
+
+        fn load(&mut self) -> Result<Option<Settings>, Self::Error> {
+            let mut buffer = [0xFF; Settings::ENCODED_LEN + MAX_WORD];
+            self.flash
+                .read(self.offset, &mut buffer[..Self::record_len()])?;
+
+            Ok(Settings::decode(&buffer))
+        }
+    }
+}
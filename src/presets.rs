@@ -0,0 +1,103 @@
+//! Board presets.
+//!
+//! [`BOARD_DEFAULT`] is generated at build time from environment variables, so per-product
+//! defaults and limits live in the build configuration (e.g. `.cargo/config.toml`) instead of
+//! constants scattered through the firmware:
+//!
+//! | Variable                     | Default | Field          |
+//! |------------------------------|---------|----------------|
+//! | `LM36011_DEFAULT_TORCH_MA`   | 2       | `torch_ma`     |
+//! | `LM36011_DEFAULT_FLASH_MA`   | 10      | `flash_ma`     |
+//! | `LM36011_DEFAULT_TIMEOUT_MS` | 600     | `timeout`      |
+//! | `LM36011_MAX_FLASH_MA`       | 1500    | `max_flash_ma` |
+//! | `LM36011_MAX_TORCH_MA`       | 376     | `max_torch_ma` |
+//!
+//! The time-out is rounded up to the next selectable [`FlashTimeout`]. Values outside the
+//! device limits, or defaults above the maximums, fail the build.
+//!
+//! ```toml
+//! # .cargo/config.toml
+//! [env]
+//! LM36011_DEFAULT_TORCH_MA = "120"
+//! LM36011_MAX_FLASH_MA = "800"
+//! ```
+
+use crate::{
+    BusDevice, FlashTimeout, LM36011Error, LimitProfile, FLASH_SCALE, LM36011, TORCH_SCALE,
+};
+
+/// Power-on defaults and limits of a board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardPreset {
+    /// Torch current programmed by [`LM36011::apply_preset`], in mA.
+    pub torch_ma: u16,
+    /// Flash current programmed by [`LM36011::apply_preset`], in mA.
+    pub flash_ma: u16,
+    /// Flash time-out programmed by [`LM36011::apply_preset`].
+    pub timeout: FlashTimeout,
+    /// Highest flash current the board allows, in mA.
+    pub max_flash_ma: u16,
+    /// Highest torch (and IR) current the board allows, in mA.
+    pub max_torch_ma: u16,
+}
+
+impl BoardPreset {
+    /// Returns the preset's limits as a current-limit profile called `"board"`.
+    pub const fn profile(&self) -> LimitProfile {
+        LimitProfile {
+            name: "board",
+            max_flash_ma: self.max_flash_ma,
+            max_torch_ma: self.max_torch_ma,
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/board_default.rs"));
+
+/// [`BOARD_DEFAULT`]'s limits, ready for [`LM36011::with_profiles`].
+///
+/// # Example
+///
+/// ```ignore
+/// let mut driver: LM36011<_, { BOARD_DEFAULT.max_flash_ma }> =
+///     LM36011::new_limited(i2c).with_profiles(&BOARD_PROFILES);
+/// driver.apply_preset(&BOARD_DEFAULT)?;
+/// ```
+pub static BOARD_PROFILES: [LimitProfile; 1] = [BOARD_DEFAULT.profile()];
+
+const _: () = {
+    assert!(
+        BOARD_DEFAULT.max_flash_ma as u32 * 1000 <= FLASH_SCALE.max_ua,
+        "LM36011_MAX_FLASH_MA is above the 1500 mA flash maximum"
+    );
+    assert!(
+        BOARD_DEFAULT.max_torch_ma as u32 * 1000 <= TORCH_SCALE.max_ua,
+        "LM36011_MAX_TORCH_MA is above the 376 mA torch maximum"
+    );
+    assert!(
+        BOARD_DEFAULT.flash_ma <= BOARD_DEFAULT.max_flash_ma,
+        "LM36011_DEFAULT_FLASH_MA is above LM36011_MAX_FLASH_MA"
+    );
+    assert!(
+        BOARD_DEFAULT.torch_ma <= BOARD_DEFAULT.max_torch_ma,
+        "LM36011_DEFAULT_TORCH_MA is above LM36011_MAX_TORCH_MA"
+    );
+};
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Programs the preset's flash current, torch current and flash time-out.
+    ///
+    /// The mode is not changed. The currents are checked against the limits in force like any
+    /// other request.
+    pub fn apply_preset(&mut self, preset: &BoardPreset) -> Result<(), LM36011Error<E>> {
+        let flash = self.limit_ma(preset.flash_ma, self.flash_scale())?;
+        let torch = self.limit_ma(preset.torch_ma, self.torch_scale())?;
+
+        self.write_flash_code(flash)?;
+        self.write_torch_code(torch)?;
+        self.write_flash_timeout(preset.timeout)
+    }
+}
@@ -0,0 +1,137 @@
+//! Interrupt-to-main-loop command queue.
+//!
+//! Interrupt handlers should not share the I2C bus with the main loop. Instead, an ISR enqueues
+//! [`Command`]s through a [`CommandSender`] and the main loop executes them against the driver
+//! with [`CommandReceiver::drain`]. The queue is a lock-free single-producer single-consumer
+//! queue from `heapless`, so enqueueing is safe from interrupt context.
+//!
+//! # Example
+//!
+//! ```ignore
+//! static mut COMMANDS: CommandQueue<8> = CommandQueue::new();
+//!
+//! // in main, before enabling the interrupt
+//! let (sender, mut receiver) = unsafe { COMMANDS.split() };
+//! // hand `sender` to the ISR, e.g. through a `Mutex<RefCell<Option<_>>>`
+//!
+//! // in the button ISR
+//! sender.enqueue(Command::Fire).ok();
+//!
+//! // in the main loop
+//! receiver.drain(&mut driver)?;
+//! ```
+
+use crate::{BusDevice, EnableRegisterFlags, LM36011Error, LM36011};
+use heapless::spsc::{Consumer, Producer, Queue};
+
+/// An action queued for the main loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Starts a flash over I2C with the configured current and time-out, see
+    /// [`LM36011::trigger_flash`].
+    Fire,
+    /// Sets the flash current in mA without changing the mode.
+    FlashCurrent(u16),
+    /// Switches the torch on at the given current in mA.
+    TorchOn(u16),
+    /// Switches the IR drive on at the given current in mA.
+    IrOn(u16),
+    /// Puts the device in standby.
+    Off,
+}
+
+/// Storage for a command queue holding up to `N - 1` commands.
+pub struct CommandQueue<const N: usize> {
+    queue: Queue<Command, N>,
+}
+
+impl<const N: usize> CommandQueue<N> {
+    /// Creates an empty queue, usable in a `static`.
+    pub const fn new() -> Self {
+        Self {
+            queue: Queue::new(),
+        }
+    }
+
+    /// Splits the queue into the ISR side and the main loop side.
+    pub fn split(&mut self) -> (CommandSender<'_, N>, CommandReceiver<'_, N>) {
+        let (producer, consumer) = self.queue.split();
+        (CommandSender { producer }, CommandReceiver { consumer })
+    }
+}
+
+impl<const N: usize> Default for CommandQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Enqueueing side of a [`CommandQueue`], for interrupt context.
+pub struct CommandSender<'a, const N: usize> {
+    producer: Producer<'a, Command, N>,
+}
+
+impl<const N: usize> CommandSender<'_, N> {
+    /// Queues a command, handing it back if the queue is full.
+    pub fn enqueue(&mut self, command: Command) -> Result<(), Command> {
+        self.producer.enqueue(command)
+    }
+}
+
+/// Executing side of a [`CommandQueue`], for the main loop.
+pub struct CommandReceiver<'a, const N: usize> {
+    consumer: Consumer<'a, Command, N>,
+}
+
+impl<const N: usize> CommandReceiver<'_, N> {
+    /// Executes all queued commands in order against the driver.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(count)` with the number of executed commands.
+    /// * `Err(LM36011Error)` from the first failing command; that command is dropped and the
+    ///   ones after it stay queued for the next call.
+    pub fn drain<I2C, E, const MAX_FLASH_MA: u16>(
+        &mut self,
+        driver: &mut LM36011<I2C, MAX_FLASH_MA>,
+    ) -> Result<usize, LM36011Error<E>>
+    where
+        I2C: BusDevice<Error = E>,
+    {
+        let mut count = 0;
+        while let Some(command) = self.consumer.dequeue() {
+            execute(driver, command)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+/// Executes one command against the driver.
+fn execute<I2C, E, const MAX_FLASH_MA: u16>(
+    driver: &mut LM36011<I2C, MAX_FLASH_MA>,
+    command: Command,
+) -> Result<(), LM36011Error<E>>
+where
+    I2C: BusDevice<Error = E>,
+{
+    match command {
+        Command::Fire => driver.trigger_flash(),
+        Command::FlashCurrent(ma) => {
+            let code = driver.limit_ma(ma, driver.flash_scale())?;
+            driver.write_flash_code(code)
+        }
+        Command::TorchOn(ma) => {
+            let code = driver.limit_ma(ma, driver.torch_scale())?;
+            driver.write_torch_code(code)?;
+            driver.write_mode(EnableRegisterFlags::MODE_TORCH)
+        }
+        Command::IrOn(ma) => {
+            let code = driver.limit_ma(ma, driver.torch_scale())?;
+            driver.write_torch_code(code)?;
+            driver.write_mode(EnableRegisterFlags::MODE_IR_DRIVE)
+        }
+        Command::Off => driver.write_mode(EnableRegisterFlags::empty()),
+    }
+}
@@ -0,0 +1,173 @@
+//! Driver operations on an RTIC shared resource.
+//!
+//! In an RTIC application the driver is a `#[shared]` resource, and every access goes through
+//! the resource's `lock`, which holds off all tasks up to the resource ceiling. Holding the lock
+//! for a multi-register update would delay a flash trigger in a higher-priority task by as
+//! long.
+//!
+//! [`SharedLM36011`] wraps the driver methods for any [`Mutex`] holding an [`LM36011`], taking
+//! the lock for one operation at a time, and most operations are a single register transaction.
+//! [`SharedLM36011::write_cached`] is the one multi-register operation, and takes the lock once
+//! per register. No call waits on anything but the bus.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use lm36011::rtic::SharedLM36011;
+//!
+//! #[task(shared = [flash], priority = 1)]
+//! async fn poll_faults(mut cx: poll_faults::Context) {
+//!     loop {
+//!         Mono::delay(100.millis()).await;
+//!         if cx.shared.flash.read_faults().unwrap().is_fault() {
+//!             cx.shared.flash.disable_output().unwrap();
+//!         }
+//!     }
+//! }
+//!
+//! #[task(binds = IO_IRQ_BANK0, shared = [flash], priority = 2)]
+//! fn frame_sync(mut cx: frame_sync::Context) {
+//!     cx.shared.flash.trigger_flash().unwrap();
+//! }
+//! ```
+
+use crate::{BusDevice, Faults, FlashTimeout, LM36011Error, OperatingMode, Register, LM36011};
+use rtic_core::Mutex;
+
+/// [`LM36011`] operations that lock a shared driver for one operation at a time.
+///
+/// Implemented for every [`Mutex`] over an [`LM36011`], i.e. the shared resource proxies of an
+/// RTIC task context, and `rtic_core::Exclusive` for a driver owned by the task.
+pub trait SharedLM36011 {
+    /// The error type of the I2C bus.
+    type Error;
+
+    /// Fires a flash over I2C in a single Enable Register write, see
+    /// [`LM36011::trigger_flash`].
+    fn trigger_flash(&mut self) -> Result<(), LM36011Error<Self::Error>>;
+
+    /// Sets the operating mode in a single Enable Register write, see [`LM36011::set_mode`].
+    fn set_mode(&mut self, mode: OperatingMode) -> Result<(), LM36011Error<Self::Error>>;
+
+    /// Returns the device to standby in a single Enable Register write, see
+    /// [`LM36011::disable_output`].
+    fn disable_output(&mut self) -> Result<(), LM36011Error<Self::Error>>;
+
+    /// Reads the operating mode from the Enable Register, see [`LM36011::get_mode`].
+    fn get_mode(&mut self) -> Result<OperatingMode, LM36011Error<Self::Error>>;
+
+    /// Sets the flash current in mA, see [`LM36011::set_flash_current_ma`].
+    ///
+    /// A single Flash Brightness Register write, unless the auto-standby is on and the mode
+    /// changes with the current; all writes happen under the one lock.
+    fn set_flash_current_ma(&mut self, ma: u16) -> Result<(), LM36011Error<Self::Error>>;
+
+    /// Sets the torch (and IR) current in mA, see [`LM36011::set_torch_current_ma`].
+    ///
+    /// Like [`SharedLM36011::set_flash_current_ma`], usually a single register write.
+    fn set_torch_current_ma(&mut self, ma: u16) -> Result<(), LM36011Error<Self::Error>>;
+
+    /// Sets the hardware flash time-out in a single Configuration Register write, see
+    /// [`LM36011::set_flash_timeout`].
+    fn set_flash_timeout(&mut self, timeout: FlashTimeout)
+        -> Result<(), LM36011Error<Self::Error>>;
+
+    /// Reads and takes the faults with a single Flags Register read, see
+    /// [`LM36011::read_faults`].
+    fn read_faults(&mut self) -> Result<Faults, LM36011Error<Self::Error>>;
+
+    /// Returns whether the device reports a fault, with a single Flags Register read, see
+    /// [`LM36011::has_fault`].
+    fn has_fault(&mut self) -> Result<bool, LM36011Error<Self::Error>>;
+
+    /// Writes the cached Configuration, Flash Brightness, Torch Brightness and Enable
+    /// Registers, taking the lock once per register.
+    ///
+    /// The split counterpart of [`LM36011::write_status`], with the same checks of the cached
+    /// brightness codes before anything is written. The Enable Register is written last,
+    /// so a flash armed here starts with the new current and time-out. Between the writes a
+    /// higher-priority task may use the driver and see the device half updated.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// cx.shared.flash.lock(|flash| {
+    ///     flash.config_flags = ConfigurationRegisterFlags::TIMEOUT_200MS;
+    ///     flash.enable_flags = EnableRegisterFlags::STROBE_ENABLE | EnableRegisterFlags::MODE_FLASH;
+    /// });
+    /// cx.shared.flash.write_cached()?;
+    /// ```
+    fn write_cached(&mut self) -> Result<(), LM36011Error<Self::Error>>;
+}
+
+impl<M, I2C, const MAX_FLASH_MA: u16> SharedLM36011 for M
+where
+    M: Mutex<T = LM36011<I2C, MAX_FLASH_MA>>,
+    I2C: BusDevice,
+{
+    type Error = I2C::Error;
+
+    fn trigger_flash(&mut self) -> Result<(), LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.trigger_flash())
+    }
+
+    fn set_mode(&mut self, mode: OperatingMode) -> Result<(), LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.set_mode(mode))
+    }
+
+    fn disable_output(&mut self) -> Result<(), LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.disable_output())
+    }
+
+    fn get_mode(&mut self) -> Result<OperatingMode, LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.get_mode())
+    }
+
+    fn set_flash_current_ma(&mut self, ma: u16) -> Result<(), LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.set_flash_current_ma(ma))
+    }
+
+    fn set_torch_current_ma(&mut self, ma: u16) -> Result<(), LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.set_torch_current_ma(ma))
+    }
+
+    fn set_flash_timeout(
+        &mut self,
+        timeout: FlashTimeout,
+    ) -> Result<(), LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.set_flash_timeout(timeout))
+    }
+
+    fn read_faults(&mut self) -> Result<Faults, LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.read_faults())
+    }
+
+    fn has_fault(&mut self) -> Result<bool, LM36011Error<Self::Error>> {
+        self.lock(|driver| driver.has_fault())
+    }
+
+    fn write_cached(&mut self) -> Result<(), LM36011Error<Self::Error>> {
+        self.lock(|driver| {
+            let (flash, torch) = (
+                driver.flash_brightness_flags.bits(),
+                driver.torch_brightness_flags.bits(),
+            );
+            driver.check_currents(driver.enable_flags, flash, torch)?;
+            let config = driver.config_flags.bits();
+            driver.set_register(Register::ConfigurationRegister, config)
+        })?;
+        self.lock(|driver| {
+            let flash = driver.flash_brightness_flags.bits();
+            driver.set_register(Register::LEDFlashBrightnessRegister, flash)
+        })?;
+        self.lock(|driver| {
+            let torch = driver.torch_brightness_flags.bits();
+            driver.set_register(Register::LEDTorchBrightnessRegister, torch)
+        })?;
+        self.lock(|driver| {
+            driver.check_cutoff(driver.enable_flags)?;
+            let enable = driver.enable_flags.bits();
+            driver.set_register(Register::EnableRegister, enable)
+        })
+    }
+}
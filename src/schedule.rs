@@ -0,0 +1,172 @@
+//! Schedule-driven torch / IR level control.
+//!
+//! A [`ScheduleController`] asks a [`ScheduleSource`] which output level applies at the current
+//! time and programs the LM36011 on every [`ScheduleController::tick`], for illuminators (e.g.
+//! security-camera IR) that follow day/night or duty-cycle plans.
+
+use crate::{BusDevice, EnableRegisterFlags, LM36011Error, LM36011};
+
+/// Output level requested by a schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduledLevel {
+    /// The LED is off (standby).
+    Off,
+    /// Torch mode at the given current in mA.
+    Torch(u16),
+    /// IR drive mode at the given current in mA.
+    Ir(u16),
+}
+
+/// Source of the output level to apply at a given time.
+///
+/// The unit of `now` is defined by the source and the caller, e.g. seconds since midnight
+/// from an RTC.
+pub trait ScheduleSource {
+    /// Returns the level that applies at `now`.
+    fn level_at(&mut self, now: u32) -> ScheduledLevel;
+}
+
+/// Time-of-day schedule of `(start, level)` entries, with `now` in seconds since midnight.
+///
+/// The level of the latest entry starting at or before `now` applies; before the first entry
+/// of the day the last entry (from the previous day) applies. Entries must be sorted by start.
+///
+/// # Example
+///
+/// ```
+/// use lm36011::schedule::{ScheduleSource, ScheduledLevel, TimeOfDaySchedule};
+///
+/// // IR on at night, off during the day
+/// let mut schedule = TimeOfDaySchedule::new(&[
+///     (7 * 3600, ScheduledLevel::Off),
+///     (19 * 3600, ScheduledLevel::Ir(200)),
+/// ]);
+/// assert_eq!(schedule.level_at(12 * 3600), ScheduledLevel::Off);
+/// assert_eq!(schedule.level_at(3 * 3600), ScheduledLevel::Ir(200));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TimeOfDaySchedule<'a> {
+    entries: &'a [(u32, ScheduledLevel)],
+}
+
+impl<'a> TimeOfDaySchedule<'a> {
+    /// Seconds in a day, `now` wraps at this value.
+    const SECONDS_PER_DAY: u32 = 24 * 3600;
+
+    /// Creates a schedule from entries sorted by start time.
+    pub fn new(entries: &'a [(u32, ScheduledLevel)]) -> Self {
+        Self { entries }
+    }
+}
+
+impl ScheduleSource for TimeOfDaySchedule<'_> {
+    fn level_at(&mut self, now: u32) -> ScheduledLevel {
+        let now = now % Self::SECONDS_PER_DAY;
+        self.entries
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= now)
+            .or(self.entries.last())
+            .map_or(ScheduledLevel::Off, |(_, level)| *level)
+    }
+}
+
+/// Repeating duty plan: `level` for `on_time` out of every `period` (same unit as `now`).
+///
+/// # Example
+///
+/// ```
+/// use lm36011::schedule::{DutySchedule, ScheduleSource, ScheduledLevel};
+///
+/// let mut duty = DutySchedule { period: 10, on_time: 2, level: ScheduledLevel::Torch(100) };
+/// assert_eq!(duty.level_at(11), ScheduledLevel::Torch(100));
+/// assert_eq!(duty.level_at(15), ScheduledLevel::Off);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutySchedule {
+    /// Length of one cycle.
+    pub period: u32,
+    /// Time at the start of each cycle during which `level` applies.
+    pub on_time: u32,
+    /// Level applied during the on time.
+    pub level: ScheduledLevel,
+}
+
+impl ScheduleSource for DutySchedule {
+    fn level_at(&mut self, now: u32) -> ScheduledLevel {
+        if self.period != 0 && now % self.period < self.on_time {
+            self.level
+        } else {
+            ScheduledLevel::Off
+        }
+    }
+}
+
+/// Applies the level of a [`ScheduleSource`] to the driver on every tick.
+///
+/// The device is only written when the scheduled level changes.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut controller = ScheduleController::new(TimeOfDaySchedule::new(&NIGHT_IR));
+/// loop {
+///     controller.tick(&mut driver, rtc.seconds_since_midnight())?;
+///     delay.delay_ms(1000u16);
+/// }
+/// ```
+pub struct ScheduleController<S> {
+    source: S,
+    applied: Option<ScheduledLevel>,
+}
+
+impl<S: ScheduleSource> ScheduleController<S> {
+    /// Creates a controller; the first tick always programs the device.
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            applied: None,
+        }
+    }
+
+    /// Returns the level applied by the last successful tick.
+    pub fn applied(&self) -> Option<ScheduledLevel> {
+        self.applied
+    }
+
+    /// Looks up the level for `now` and programs the device if it changed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` if the device was reprogrammed, `Ok(false)` if the level was unchanged.
+    /// * `Err(LM36011Error::CurrentOutOfRange)` if the scheduled current exceeds the torch limits
+    ///   and the driver's range policy is [`RangePolicy::Error`](crate::RangePolicy::Error).
+    /// * `Err(LM36011Error::I2CError(E))` if there's an error during the I2C communication.
+    pub fn tick<I2C, E, const MAX_FLASH_MA: u16>(
+        &mut self,
+        driver: &mut LM36011<I2C, MAX_FLASH_MA>,
+        now: u32,
+    ) -> Result<bool, LM36011Error<E>>
+    where
+        I2C: BusDevice<Error = E>,
+    {
+        let level = self.source.level_at(now);
+        if self.applied == Some(level) {
+            return Ok(false);
+        }
+
+        let (mode, ma) = match level {
+            ScheduledLevel::Off => (EnableRegisterFlags::empty(), None),
+            ScheduledLevel::Torch(ma) => (EnableRegisterFlags::MODE_TORCH, Some(ma)),
+            ScheduledLevel::Ir(ma) => (EnableRegisterFlags::MODE_IR_DRIVE, Some(ma)),
+        };
+        if let Some(ma) = ma {
+            let code = driver.limit_ma(ma, driver.torch_scale())?;
+            driver.write_torch_code(code)?;
+        }
+        driver.write_mode(mode)?;
+        self.applied = Some(level);
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,91 @@
+//! Sharing the I2C bus with other devices.
+//!
+//! The LM36011 almost always shares its bus with a camera sensor or an IMU. The driver owns its
+//! transport, so these adapters let it own a borrow of the bus instead:
+//!
+//! * [`RefCellBus`] - a bus in a [`RefCell`], shared by several drivers in one execution
+//!   context. Each transaction borrows the bus for its duration only.
+//! * [`BorrowedBus`] - a `&mut` borrow of the bus, for a driver that only lives as long as the
+//!   borrow.
+//!
+//! Both work with any [`BusDevice`]. With the `eh1` feature, the `embedded-hal-bus` devices
+//! (`RefCellDevice`, `CriticalSectionDevice`, `MutexDevice`, ...) are `embedded-hal` 1.0 I2C
+//! buses themselves and can be used as the transport directly, including across interrupts.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let bus = RefCell::new(i2c);
+//! let mut flash = LM36011::new(RefCellBus::new(&bus));
+//! flash.read_status()?;
+//! // the camera sensor is programmed on the same bus in between
+//! bus.borrow_mut().write(CAMERA_ADDRESS, &[STREAM_REGISTER, 0x01])?;
+//! flash.trigger_flash()?;
+//! ```
+
+use core::cell::RefCell;
+
+use crate::BusDevice;
+
+/// Transport sharing a bus in a [`RefCell`] with other drivers.
+///
+/// A transaction panics if the bus is already borrowed, e.g. when a driver call is made from a
+/// callback running inside another driver's transaction.
+#[derive(Debug)]
+pub struct RefCellBus<'a, B> {
+    bus: &'a RefCell<B>,
+}
+
+impl<'a, B> RefCellBus<'a, B> {
+    /// Shares `bus` with the driver.
+    pub fn new(bus: &'a RefCell<B>) -> Self {
+        Self { bus }
+    }
+}
+
+impl<B: BusDevice> BusDevice for RefCellBus<'_, B> {
+    type Error = B::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().write(address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().write_read(address, bytes, buffer)
+    }
+}
+
+/// Transport borrowing the bus mutably for the lifetime of the driver.
+#[derive(Debug)]
+pub struct BorrowedBus<'a, B> {
+    bus: &'a mut B,
+}
+
+impl<'a, B> BorrowedBus<'a, B> {
+    /// Lends `bus` to the driver.
+    pub fn new(bus: &'a mut B) -> Self {
+        Self { bus }
+    }
+}
+
+impl<B: BusDevice> BusDevice for BorrowedBus<'_, B> {
+    type Error = B::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.bus.write(address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.bus.write_read(address, bytes, buffer)
+    }
+}
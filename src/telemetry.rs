@@ -0,0 +1,253 @@
+//! Compact binary telemetry frames for live monitoring.
+//!
+//! A [`TelemetryEncoder`] turns the cached driver state into a fixed-size [`TelemetryFrame`]
+//! that can be streamed over RTT, a UART or CAN at a fixed rate. Frames are decoded with
+//! [`TelemetryFrame::decode`]; with the `std` feature, `StreamDecoder` also finds frames in a
+//! raw byte stream on the host.
+
+use crate::{
+    flash_ma_for_code, torch_ma_for_code, ConfigurationRegisterFlags, EnableRegisterFlags,
+    FlagRegisterFlags, LedFlashBrightnessFlags, LedTorchBrightnessFlags, LM36011,
+};
+
+/// First byte of every frame.
+const SYNC: u8 = 0xA5;
+
+/// Version of the frame layout.
+const VERSION: u8 = 1;
+
+/// Flags counted as faults by the encoder.
+const FAULT_FLAGS: FlagRegisterFlags = FlagRegisterFlags::VLED_SHORT_FAULT
+    .union(FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT)
+    .union(FlagRegisterFlags::UVLO_FAULT);
+
+/// One telemetry sample.
+///
+/// Encoded as 16 bytes, multi-byte values little endian:
+///
+/// | Byte  | Content                         |
+/// |-------|---------------------------------|
+/// | 0     | sync `0xA5`                     |
+/// | 1     | layout version                  |
+/// | 2-3   | sequence number                 |
+/// | 4-8   | Enable, Configuration, Flash Brightness, Torch Brightness and Flags Registers |
+/// | 9-10  | configured flash current in mA  |
+/// | 11-12 | configured torch current in mA  |
+/// | 13-14 | fault count                     |
+/// | 15    | checksum                        |
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TelemetryFrame {
+    /// Frame counter, wraps at `u16::MAX`.
+    pub sequence: u16,
+    pub enable: EnableRegisterFlags,
+    pub config: ConfigurationRegisterFlags,
+    pub flash_brightness: LedFlashBrightnessFlags,
+    pub torch_brightness: LedTorchBrightnessFlags,
+    pub flags: FlagRegisterFlags,
+    /// Configured flash current in mA.
+    pub flash_ma: u16,
+    /// Configured torch current in mA.
+    pub torch_ma: u16,
+    /// Number of times a fault flag has been raised since the encoder was created.
+    pub fault_count: u16,
+}
+
+impl TelemetryFrame {
+    /// Length of an encoded frame in bytes.
+    pub const LEN: usize = 16;
+
+    /// Encodes the frame.
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let sequence = self.sequence.to_le_bytes();
+        let flash_ma = self.flash_ma.to_le_bytes();
+        let torch_ma = self.torch_ma.to_le_bytes();
+        let fault_count = self.fault_count.to_le_bytes();
+
+        let mut frame = [
+            SYNC,
+            VERSION,
+            sequence[0],
+            sequence[1],
+            self.enable.bits(),
+            self.config.bits(),
+            self.flash_brightness.bits(),
+            self.torch_brightness.bits(),
+            self.flags.bits(),
+            flash_ma[0],
+            flash_ma[1],
+            torch_ma[0],
+            torch_ma[1],
+            fault_count[0],
+            fault_count[1],
+            0,
+        ];
+        frame[Self::LEN - 1] = crate::checksum(&frame[..Self::LEN - 1]);
+
+        frame
+    }
+
+    /// Decodes a frame from the start of `bytes`.
+    ///
+    /// Returns `None` if `bytes` is too short or does not start with a valid frame.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let frame = bytes.get(..Self::LEN)?;
+        let (body, sum) = frame.split_at(Self::LEN - 1);
+
+        if body[0] != SYNC || body[1] != VERSION || sum[0] != crate::checksum(body) {
+            return None;
+        }
+
+        Some(Self {
+            sequence: u16::from_le_bytes([body[2], body[3]]),
+            enable: EnableRegisterFlags::from_bits_truncate(body[4]),
+            config: ConfigurationRegisterFlags::from_bits_truncate(body[5]),
+            flash_brightness: LedFlashBrightnessFlags::from_bits_truncate(body[6]),
+            torch_brightness: LedTorchBrightnessFlags::from_bits_truncate(body[7]),
+            flags: FlagRegisterFlags::from_bits_truncate(body[8]),
+            flash_ma: u16::from_le_bytes([body[9], body[10]]),
+            torch_ma: u16::from_le_bytes([body[11], body[12]]),
+            fault_count: u16::from_le_bytes([body[13], body[14]]),
+        })
+    }
+}
+
+/// Builds telemetry frames from the cached driver state and keeps the frame counters.
+///
+/// The encoder does not access the bus; call [`LM36011::read_status`] before encoding to
+/// sample fresh flags.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut telemetry = TelemetryEncoder::new();
+///
+/// loop {
+///     driver.read_status()?;
+///     uart.write_all(&telemetry.encode(&driver))?;
+///     delay.delay_ms(100u16);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TelemetryEncoder {
+    sequence: u16,
+    fault_count: u16,
+    faults: FlagRegisterFlags,
+}
+
+impl TelemetryEncoder {
+    /// Creates an encoder with cleared counters.
+    pub fn new() -> Self {
+        Self {
+            sequence: 0,
+            fault_count: 0,
+            faults: FlagRegisterFlags::empty(),
+        }
+    }
+
+    /// Samples the driver state into a frame and advances the counters.
+    pub fn frame<I2C, const MAX_FLASH_MA: u16>(
+        &mut self,
+        driver: &LM36011<I2C, MAX_FLASH_MA>,
+    ) -> TelemetryFrame {
+        let faults = driver.flag_register_flags & FAULT_FLAGS;
+        if !faults.difference(self.faults).is_empty() {
+            self.fault_count = self.fault_count.wrapping_add(1);
+        }
+        self.faults = faults;
+
+        let frame = TelemetryFrame {
+            sequence: self.sequence,
+            enable: driver.enable_flags,
+            config: driver.config_flags,
+            flash_brightness: driver.flash_brightness_flags,
+            torch_brightness: driver.torch_brightness_flags,
+            flags: driver.flag_register_flags,
+            flash_ma: flash_ma_for_code(driver.flash_brightness_flags.bits()),
+            torch_ma: torch_ma_for_code(driver.torch_brightness_flags.bits()),
+            fault_count: self.fault_count,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+
+        frame
+    }
+
+    /// Samples the driver state and encodes it into a frame.
+    pub fn encode<I2C, const MAX_FLASH_MA: u16>(
+        &mut self,
+        driver: &LM36011<I2C, MAX_FLASH_MA>,
+    ) -> [u8; TelemetryFrame::LEN] {
+        self.frame(driver).encode()
+    }
+}
+
+impl Default for TelemetryEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+pub use self::host::StreamDecoder;
+
+#[cfg(feature = "std")]
+mod host {
+    use super::{TelemetryFrame, SYNC};
+    use std::vec::Vec;
+
+    /// Host-side decoder that extracts frames from a raw byte stream.
+    ///
+    /// Bytes are fed in chunks as they arrive; bytes that do not form a valid frame are
+    /// skipped, so the decoder resynchronises after dropped or corrupted bytes.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut decoder = StreamDecoder::new();
+    ///
+    /// loop {
+    ///     let n = port.read(&mut chunk)?;
+    ///     decoder.push(&chunk[..n]);
+    ///     while let Some(frame) = decoder.next_frame() {
+    ///         println!("{:?}", frame);
+    ///     }
+    /// }
+    /// ```
+    #[derive(Debug, Default)]
+    pub struct StreamDecoder {
+        buffer: Vec<u8>,
+    }
+
+    impl StreamDecoder {
+        /// Creates an empty decoder.
+        pub fn new() -> Self {
+            Self { buffer: Vec::new() }
+        }
+
+        /// Appends received bytes.
+        pub fn push(&mut self, bytes: &[u8]) {
+            self.buffer.extend_from_slice(bytes);
+        }
+
+        /// Returns the next complete frame, or `None` if more bytes are needed.
+        pub fn next_frame(&mut self) -> Option<TelemetryFrame> {
+            loop {
+                let start = self.buffer.iter().position(|b| *b == SYNC);
+                self.buffer.drain(..start.unwrap_or(self.buffer.len()));
+
+                if self.buffer.len() < TelemetryFrame::LEN {
+                    return None;
+                }
+
+                match TelemetryFrame::decode(&self.buffer) {
+                    Some(frame) => {
+                        self.buffer.drain(..TelemetryFrame::LEN);
+                        return Some(frame);
+                    }
+                    None => {
+                        self.buffer.remove(0);
+                    }
+                }
+            }
+        }
+    }
+}
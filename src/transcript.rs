@@ -0,0 +1,192 @@
+//! Register transcripts for reproducing field issues.
+//!
+//! A [`Recorder`] wraps the driver's transport and keeps the last `N` bus transactions,
+//! including the bytes read back. The captured [`Transaction`]s can be stored or sent along
+//! with a bug report, then re-issued on the bench with [`LM36011::replay`] against real
+//! hardware or a mock.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut driver = LM36011::new(Recorder::<_, 32>::new(i2c));
+//! driver.read_status()?;
+//! // ... the failure happens ...
+//! let transcript: heapless::Vec<Transaction, 32> = driver.bus().transactions().collect();
+//!
+//! // on the bench
+//! if let Some(index) = bench_driver.replay(&transcript)? {
+//!     println!("read {} returned different data", index);
+//! }
+//! ```
+
+use crate::{BusDevice, LM36011Error, LM36011};
+
+/// Largest number of bytes kept per direction of a transaction.
+const MAX_BYTES: usize = 8;
+
+/// One captured bus transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transaction {
+    /// 7-bit I2C address of the transaction.
+    pub address: u8,
+    written: [u8; MAX_BYTES],
+    written_len: u8,
+    read: [u8; MAX_BYTES],
+    /// Number of bytes read, `None` for a plain write.
+    read_len: Option<u8>,
+}
+
+impl Transaction {
+    /// Largest number of bytes a transaction can write or read.
+    pub const MAX_BYTES: usize = MAX_BYTES;
+
+    /// Creates a write transaction.
+    ///
+    /// Returns `None` if `bytes` is longer than [`Transaction::MAX_BYTES`].
+    pub fn write(address: u8, bytes: &[u8]) -> Option<Self> {
+        let mut written = [0u8; MAX_BYTES];
+        written.get_mut(..bytes.len())?.copy_from_slice(bytes);
+
+        Some(Self {
+            address,
+            written,
+            written_len: bytes.len() as u8,
+            read: [0u8; MAX_BYTES],
+            read_len: None,
+        })
+    }
+
+    /// Creates a write-read transaction that wrote `bytes` and read back `read`.
+    ///
+    /// Returns `None` if `bytes` or `read` is longer than [`Transaction::MAX_BYTES`].
+    pub fn write_read(address: u8, bytes: &[u8], read: &[u8]) -> Option<Self> {
+        let mut transaction = Self::write(address, bytes)?;
+        transaction
+            .read
+            .get_mut(..read.len())?
+            .copy_from_slice(read);
+        transaction.read_len = Some(read.len() as u8);
+
+        Some(transaction)
+    }
+
+    /// Returns the written bytes.
+    pub fn written(&self) -> &[u8] {
+        &self.written[..self.written_len as usize]
+    }
+
+    /// Returns the bytes read back, or `None` for a plain write.
+    pub fn read(&self) -> Option<&[u8]> {
+        self.read_len.map(|len| &self.read[..len as usize])
+    }
+}
+
+/// Transport wrapper that records the last `N` transactions.
+///
+/// Transactions are recorded whether they succeed or not; a failed read keeps zeros for the
+/// data. Transactions longer than [`Transaction::MAX_BYTES`] are passed through but not
+/// recorded.
+pub struct Recorder<B, const N: usize> {
+    bus: B,
+    transactions: [Option<Transaction>; N],
+    next: usize,
+}
+
+impl<B, const N: usize> Recorder<B, N> {
+    /// Wraps a transport with an empty transcript.
+    pub fn new(bus: B) -> Self {
+        Self {
+            bus,
+            transactions: [None; N],
+            next: 0,
+        }
+    }
+
+    /// Returns the recorded transactions, oldest first.
+    pub fn transactions(&self) -> impl Iterator<Item = Transaction> + '_ {
+        let (newer, older) = self.transactions.split_at(self.next);
+        older.iter().chain(newer).flatten().copied()
+    }
+
+    /// Clears the transcript.
+    pub fn clear(&mut self) {
+        self.transactions = [None; N];
+        self.next = 0;
+    }
+
+    /// Returns the wrapped transport.
+    pub fn into_inner(self) -> B {
+        self.bus
+    }
+
+    fn push(&mut self, transaction: Option<Transaction>) {
+        if N == 0 {
+            return;
+        }
+        if let Some(transaction) = transaction {
+            self.transactions[self.next] = Some(transaction);
+            self.next = (self.next + 1) % N;
+        }
+    }
+}
+
+impl<B: BusDevice, const N: usize> BusDevice for Recorder<B, N> {
+    type Error = B::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        let result = self.bus.write(address, bytes);
+        self.push(Transaction::write(address, bytes));
+        result
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let result = self.bus.write_read(address, bytes, buffer);
+        self.push(Transaction::write_read(address, bytes, buffer));
+        result
+    }
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> LM36011<I2C, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Re-issues a captured transcript on the bus, in order.
+    ///
+    /// Reads are compared with the recorded data. The cached register state is not updated;
+    /// call [`LM36011::read_status`] afterwards to resynchronise it.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(None)` if every read returned the recorded data.
+    /// * `Ok(Some(index))` with the index of the first read that returned different data.
+    /// * `Err(LM36011Error::I2CError(E))` if a transaction failed; the replay stops there.
+    pub fn replay(&mut self, transcript: &[Transaction]) -> Result<Option<usize>, LM36011Error<E>> {
+        let mut diverged = None;
+
+        for (index, transaction) in transcript.iter().enumerate() {
+            match transaction.read() {
+                None => self
+                    .i2c
+                    .write(transaction.address, transaction.written())
+                    .map_err(LM36011Error::I2CError)?,
+                Some(expected) => {
+                    let mut buffer = [0u8; MAX_BYTES];
+                    let buffer = &mut buffer[..expected.len()];
+                    self.i2c
+                        .write_read(transaction.address, transaction.written(), buffer)
+                        .map_err(LM36011Error::I2CError)?;
+                    if diverged.is_none() && buffer != expected {
+                        diverged = Some(index);
+                    }
+                }
+            }
+        }
+
+        Ok(diverged)
+    }
+}
@@ -0,0 +1,166 @@
+//! Typestate wrapper for safety-critical lighting.
+//!
+//! [`Driver`] tracks the operating mode in its type, so using the LED in the wrong mode is a
+//! compile error rather than a runtime mistake: a flash can only be ended on a
+//! `Driver<_, Flash>`, the torch current can only be changed while the torch is off or on, and
+//! the underlying [`LM36011`] can only be released in standby. `Driver<_, Flash>` is
+//! `#[must_use]`, so dropping it without leaving flash mode is flagged.
+//!
+//! Failed transitions return the driver in its previous state together with the error.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let standby = Driver::new(LM36011::new(i2c)).map_err(|(_, e)| e)?;
+//! let standby = standby.set_flash_current_ma(1000).map_err(|(_, e)| e)?;
+//! let flash = standby.into_flash().map_err(|(_, e)| e)?;
+//! delay.delay_ms(20);
+//! let standby = flash.into_standby().map_err(|(_, e)| e)?;
+//! // standby.into_standby() does not compile
+//! ```
+
+// failed transitions hand the driver back rather than dropping the bus with it
+#![allow(clippy::result_large_err)]
+
+use core::marker::PhantomData;
+
+use crate::{BusDevice, EnableRegisterFlags, LM36011Error, OperatingMode, LM36011};
+
+/// The LED is off.
+pub struct Standby;
+
+/// The LED is on in torch mode.
+pub struct Torch;
+
+/// A flash was fired over I2C.
+pub struct Flash;
+
+/// Result of a transition: the driver in the new state, or in the old state with the error.
+pub type Transition<I2C, From, To, E, const MAX_FLASH_MA: u16> =
+    Result<Driver<I2C, To, MAX_FLASH_MA>, (Driver<I2C, From, MAX_FLASH_MA>, LM36011Error<E>)>;
+
+/// An [`LM36011`] whose operating mode is tracked in the type `S`.
+#[must_use = "the LED stays in this mode until the driver is moved to another state"]
+pub struct Driver<I2C, S = Standby, const MAX_FLASH_MA: u16 = 1500> {
+    driver: LM36011<I2C, MAX_FLASH_MA>,
+    state: PhantomData<S>,
+}
+
+impl<I2C, S, const MAX_FLASH_MA: u16> Driver<I2C, S, MAX_FLASH_MA> {
+    /// Returns the wrapped driver for reading cached state.
+    pub fn inner(&self) -> &LM36011<I2C, MAX_FLASH_MA> {
+        &self.driver
+    }
+
+    fn into_state<T>(self) -> Driver<I2C, T, MAX_FLASH_MA> {
+        Driver {
+            driver: self.driver,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<I2C, E, S, const MAX_FLASH_MA: u16> Driver<I2C, S, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Runs `operation` on the wrapped driver and moves to state `T` if it succeeds.
+    fn transition<T>(
+        mut self,
+        operation: impl FnOnce(&mut LM36011<I2C, MAX_FLASH_MA>) -> Result<(), LM36011Error<E>>,
+    ) -> Transition<I2C, S, T, E, MAX_FLASH_MA> {
+        match operation(&mut self.driver) {
+            Ok(()) => Ok(self.into_state()),
+            Err(error) => Err((self, error)),
+        }
+    }
+
+    /// Returns the device to standby.
+    fn standby(self) -> Transition<I2C, S, Standby, E, MAX_FLASH_MA> {
+        self.transition(|driver| driver.write_mode(EnableRegisterFlags::empty()))
+    }
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> Driver<I2C, Standby, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Takes over a driver, putting the device in standby.
+    ///
+    /// Auto-standby is disabled, as it would change the mode behind the typestate.
+    pub fn new(
+        mut driver: LM36011<I2C, MAX_FLASH_MA>,
+    ) -> Result<Self, (LM36011<I2C, MAX_FLASH_MA>, LM36011Error<E>)> {
+        driver.set_auto_standby(false);
+        match driver.write_mode(EnableRegisterFlags::empty()) {
+            Ok(()) => Ok(Self {
+                driver,
+                state: PhantomData,
+            }),
+            Err(error) => Err((driver, error)),
+        }
+    }
+
+    /// Sets the flash current in mA for the next flash, see [`LM36011::set_flash_current_ma`].
+    pub fn set_flash_current_ma(
+        self,
+        ma: u16,
+    ) -> Transition<I2C, Standby, Standby, E, MAX_FLASH_MA> {
+        self.transition(|driver| driver.set_flash_current_ma(ma))
+    }
+
+    /// Sets the torch current in mA, see [`LM36011::set_torch_current_ma`].
+    pub fn set_torch_current_ma(
+        self,
+        ma: u16,
+    ) -> Transition<I2C, Standby, Standby, E, MAX_FLASH_MA> {
+        self.transition(|driver| driver.set_torch_current_ma(ma))
+    }
+
+    /// Turns the torch on, see [`LM36011::enable_torch`].
+    pub fn into_torch(self) -> Transition<I2C, Standby, Torch, E, MAX_FLASH_MA> {
+        self.transition(LM36011::enable_torch)
+    }
+
+    /// Fires a flash over I2C, see [`LM36011::trigger_flash`].
+    pub fn into_flash(self) -> Transition<I2C, Standby, Flash, E, MAX_FLASH_MA> {
+        self.transition(LM36011::trigger_flash)
+    }
+
+    /// Returns the wrapped driver.
+    pub fn release(self) -> LM36011<I2C, MAX_FLASH_MA> {
+        self.driver
+    }
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> Driver<I2C, Torch, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Changes the torch current while the torch is on, see
+    /// [`LM36011::set_torch_current_ma`].
+    pub fn set_torch_current_ma(self, ma: u16) -> Transition<I2C, Torch, Torch, E, MAX_FLASH_MA> {
+        self.transition(|driver| driver.set_torch_current_ma(ma))
+    }
+
+    /// Turns the torch off.
+    pub fn into_standby(self) -> Transition<I2C, Torch, Standby, E, MAX_FLASH_MA> {
+        self.standby()
+    }
+}
+
+impl<I2C, E, const MAX_FLASH_MA: u16> Driver<I2C, Flash, MAX_FLASH_MA>
+where
+    I2C: BusDevice<Error = E>,
+{
+    /// Returns whether the flash has ended, i.e. the device returned to standby at the end of
+    /// its time-out.
+    pub fn is_done(&mut self) -> Result<bool, LM36011Error<E>> {
+        Ok(self.driver.get_mode()? == OperatingMode::Standby)
+    }
+
+    /// Ends the flash, or acknowledges that its time-out ended it.
+    pub fn into_standby(self) -> Transition<I2C, Flash, Standby, E, MAX_FLASH_MA> {
+        self.standby()
+    }
+}
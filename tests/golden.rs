@@ -0,0 +1,1562 @@
+//! Golden-trace tests of the wire behavior of the public API.
+//!
+//! Every test runs one API against a simulated LM36011 and compares the I2C transactions it
+//! issued with a fixture in `tests/golden/`. A change of register order, an extra read or a
+//! different value fails the test. After an intended change, regenerate the fixtures with
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --test golden
+//! ```
+//!
+//! and review the fixture diff.
+
+// the fire paths read the DWT cycle counter, which only exists on Cortex-M
+#![cfg(not(feature = "cycle-count"))]
+
+use core::convert::Infallible;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::digital::v2::OutputPin;
+use lm36011::config::Lm36011Config;
+use lm36011::persist::Settings;
+use lm36011::presets::BOARD_DEFAULT;
+use lm36011::schedule::{ScheduleController, ScheduledLevel, TimeOfDaySchedule};
+use lm36011::transcript::{Recorder, Transaction};
+use lm36011::typestate;
+use lm36011::{
+    Brightness, ConfigurationRegisterFlags, DeratingPoint, EnableRegisterFlags, ExposureBudget,
+    Faults, FlashTimeout, IvfmThreshold, LedCheck, LedFlashBrightnessFlags,
+    LedTorchBrightnessFlags, OperatingMode, Register, Rounding, SoftStart, StrobeConfig,
+    StrobeTrigger, TorchRamp, TriggerMode, LM36011,
+};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Register file of a simulated LM36011 with the datasheet reset values.
+struct FakeDevice {
+    registers: [u8; 7],
+}
+
+impl FakeDevice {
+    fn new() -> Self {
+        Self {
+            registers: [0x00, 0x20, 0x15, 0x00, 0x00, 0x00, 0x01],
+        }
+    }
+
+    /// A device with pending flags in the Flags Register.
+    fn with_flags(flags: u8) -> Self {
+        let mut device = Self::new();
+        device.registers[Register::FlagsRegister as usize] = flags;
+        device
+    }
+}
+
+impl lm36011::BusDevice for FakeDevice {
+    type Error = Infallible;
+
+    fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+        let (pointer, data) = bytes.split_first().unwrap();
+        for (offset, value) in data.iter().enumerate() {
+            let reg = *pointer as usize + offset;
+            if (1..=4).contains(&reg) {
+                self.registers[reg] = *value;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_read(
+        &mut self,
+        _address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Infallible> {
+        for (offset, value) in buffer.iter_mut().enumerate() {
+            let reg = bytes[0] as usize + offset;
+            *value = self.registers.get(reg).copied().unwrap_or(0);
+            // the Flags Register clears on read
+            if reg == Register::FlagsRegister as usize {
+                self.registers[reg] = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+type Driver = LM36011<Recorder<FakeDevice, 64>>;
+
+struct NoDelay;
+
+impl DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
+
+impl DelayMs<u16> for NoDelay {
+    fn delay_ms(&mut self, _ms: u16) {}
+}
+
+impl DelayUs<u16> for NoDelay {
+    fn delay_us(&mut self, _us: u16) {}
+}
+
+struct Pin;
+
+impl OutputPin for Pin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+fn driver() -> Driver {
+    LM36011::new(Recorder::new(FakeDevice::new()))
+}
+
+/// Formats the recorded transactions, one per line.
+fn trace(bus: &Recorder<FakeDevice, 64>) -> String {
+    let mut trace = String::new();
+    for transaction in bus.transactions() {
+        let written = hex(transaction.written());
+        match transaction.read() {
+            None => writeln!(trace, "W {:02x} {}", transaction.address, written),
+            Some(read) => writeln!(
+                trace,
+                "R {:02x} {} -> {}",
+                transaction.address,
+                written,
+                hex(read)
+            ),
+        }
+        .unwrap();
+    }
+    trace
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Compares the driver's transactions with `tests/golden/<name>.trace`.
+fn check(name: &str, driver: &Driver) {
+    let actual = trace(driver.bus());
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("trace");
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing fixture {}", path.display()));
+    assert_eq!(actual, expected, "wire trace of {} changed", name);
+}
+
+#[test]
+fn set_flash_current_hex() {
+    let mut driver = driver();
+    driver.set_flash_current_hex(0x20).unwrap();
+    check("set_flash_current_hex", &driver);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn set_flash_current() {
+    let mut driver = driver();
+    driver.set_flash_current(500.0).unwrap();
+    check("set_flash_current", &driver);
+}
+
+#[test]
+fn set_flash_current_const() {
+    let mut driver = driver();
+    driver.set_flash_current_const::<750>().unwrap();
+    check("set_flash_current_const", &driver);
+}
+
+#[test]
+fn set_flash() {
+    let mut driver = driver();
+    driver.set_flash(Brightness::Percent(50)).unwrap();
+    check("set_flash", &driver);
+}
+
+#[test]
+fn set_torch() {
+    let mut driver = driver();
+    driver.set_torch(Brightness::Milliamps(100)).unwrap();
+    check("set_torch", &driver);
+}
+
+#[test]
+fn get_device_id() {
+    let mut driver = driver();
+    driver.get_device_id().unwrap();
+    check("get_device_id", &driver);
+}
+
+#[test]
+fn get_register() {
+    let mut driver = driver();
+    driver.get_register(Register::FlagsRegister).unwrap();
+    check("get_register", &driver);
+}
+
+#[test]
+fn set_register() {
+    let mut driver = driver();
+    driver
+        .set_register(Register::ConfigurationRegister, 0x35)
+        .unwrap();
+    check("set_register", &driver);
+}
+
+#[test]
+fn read_status() {
+    let mut driver = driver();
+    driver.read_status().unwrap();
+    check("read_status", &driver);
+}
+
+#[test]
+fn write_status() {
+    let mut driver = driver();
+    driver.write_status().unwrap();
+    check("write_status", &driver);
+}
+
+#[test]
+fn software_reset() {
+    let mut driver = driver();
+    driver.software_reset().unwrap();
+    check("software_reset", &driver);
+}
+
+#[test]
+fn software_reset_and_wait() {
+    let mut driver = driver();
+    driver.software_reset_and_wait(&mut NoDelay).unwrap();
+    check("software_reset_and_wait", &driver);
+}
+
+#[test]
+fn verify_device_id() {
+    let mut driver = driver();
+    assert!(driver.verify_device_id().unwrap());
+    check("verify_device_id", &driver);
+}
+
+#[test]
+fn probe() {
+    let mut driver = driver();
+    driver.probe().unwrap();
+    check("probe", &driver);
+}
+
+#[test]
+fn led_connectivity_check() {
+    let mut driver = driver();
+    driver.led_connectivity_check(&mut NoDelay).unwrap();
+    check("led_connectivity_check", &driver);
+}
+
+#[test]
+fn soft_start_torch() {
+    let mut driver = driver();
+    driver
+        .soft_start_torch(100, SoftStart::new(40u32, 4), &mut NoDelay)
+        .unwrap();
+    check("soft_start_torch", &driver);
+}
+
+#[test]
+fn soft_start_ir() {
+    let mut driver = driver();
+    driver
+        .soft_start_ir(100, SoftStart::new(40u32, 4), &mut NoDelay)
+        .unwrap();
+    check("soft_start_ir", &driver);
+}
+
+#[test]
+fn i2c_flash() {
+    let mut driver = driver();
+    let mut trigger: TriggerMode = TriggerMode::I2cOnly;
+    driver.arm_flash(&mut trigger).unwrap();
+    driver.fire_flash(&mut trigger).unwrap();
+    driver.end_flash(&mut trigger).unwrap();
+    check("i2c_flash", &driver);
+}
+
+#[test]
+fn strobe_flash() {
+    let mut driver = driver();
+    let mut trigger = TriggerMode::HardwareStrobe { pin: Pin };
+    driver.arm_flash(&mut trigger).unwrap();
+    driver.fire_flash(&mut trigger).unwrap();
+    driver.end_flash(&mut trigger).unwrap();
+    check("strobe_flash", &driver);
+}
+
+#[test]
+fn flash_once() {
+    let mut driver = driver();
+    driver.flash_once(200u32, &mut NoDelay).unwrap();
+    check("flash_once", &driver);
+}
+
+#[test]
+fn flash_once_strobe_armed() {
+    let mut driver = driver();
+    driver
+        .arm_flash(&mut TriggerMode::HardwareStrobe { pin: Pin })
+        .unwrap();
+    driver.bus_mut().clear();
+    driver.flash_once(200u32, &mut NoDelay).unwrap();
+    check("flash_once_strobe_armed", &driver);
+}
+
+#[test]
+fn write_current_and_fire() {
+    let mut driver = driver();
+    // a cached strobe enable would hold the flash until a STROBE edge
+    driver
+        .enable_flags
+        .insert(EnableRegisterFlags::STROBE_ENABLE);
+    driver.write_current_and_fire(0x30).unwrap();
+    check("write_current_and_fire", &driver);
+}
+
+#[test]
+fn confirm_flash_fired() {
+    let mut driver = driver();
+    driver.confirm_flash_fired().unwrap();
+    check("confirm_flash_fired", &driver);
+}
+
+#[test]
+fn check_identity() {
+    let mut driver = driver();
+    driver.probe().unwrap();
+    driver.bus_mut().clear();
+    driver.check_identity();
+    check("check_identity", &driver);
+}
+
+#[test]
+fn check_identity_quirks() {
+    let mut probed = driver();
+    probed.probe().unwrap();
+
+    // the quirks follow the revision read here, as after `probe`
+    let mut driver = driver();
+    let before = driver.quirks();
+    driver.check_identity();
+    assert_ne!(driver.quirks(), before);
+    assert_eq!(driver.quirks(), probed.quirks());
+}
+
+#[test]
+fn apply_temperature() {
+    static DERATING: [DeratingPoint; 1] = [DeratingPoint {
+        temperature_c: 60,
+        max_flash_ma: 500,
+        max_torch_ma: 100,
+    }];
+
+    let mut driver = driver().with_derating(&DERATING);
+    driver.set_flash(Brightness::Milliamps(1000)).unwrap();
+    driver.set_torch(Brightness::Milliamps(200)).unwrap();
+    driver.bus_mut().clear();
+    driver.apply_temperature(70).unwrap();
+    check("apply_temperature", &driver);
+}
+
+#[test]
+fn write_status_limits() {
+    static DERATING: [DeratingPoint; 1] = [DeratingPoint {
+        temperature_c: 60,
+        max_flash_ma: 500,
+        max_torch_ma: 100,
+    }];
+
+    let mut driver = driver().with_derating(&DERATING);
+    driver.apply_temperature(70).unwrap();
+    driver.bus_mut().clear();
+    // 1000 mA written through the cache instead of a current setter
+    driver.flash_brightness_flags = LedFlashBrightnessFlags::from_bits_truncate(0xD4);
+    assert!(matches!(
+        driver.write_status(),
+        Err(lm36011::LM36011Error::CurrentOutOfRange)
+    ));
+    check("write_status_limits", &driver);
+}
+
+#[test]
+fn write_status_interlock() {
+    let mut driver = driver();
+    driver.set_interlock_threshold(Some(500));
+    // 1200 mA written through the cache instead of a current setter
+    driver.flash_brightness_flags = LedFlashBrightnessFlags::from_bits_truncate(0xE5);
+    assert!(matches!(
+        driver.write_status(),
+        Err(lm36011::LM36011Error::InterlockEngaged)
+    ));
+
+    let token = driver.request_unlock(0, None);
+    driver.unlock(token, 0).unwrap();
+    driver.write_status().unwrap();
+    // the current is already on the device, writing it again needs no unlock
+    driver.write_status().unwrap();
+    check("write_status_interlock", &driver);
+}
+
+#[test]
+fn exposure_tick() {
+    let mut driver = driver();
+    driver.set_exposure_budget(Some(ExposureBudget {
+        rate_per_ma: 1000,
+        dose_limit: 100_000,
+    }));
+    driver
+        .soft_start_torch(100, SoftStart::new(0u32, 1), &mut NoDelay)
+        .unwrap();
+    driver.bus_mut().clear();
+    assert!(driver.exposure_tick(2000).unwrap());
+    check("exposure_tick", &driver);
+}
+
+#[test]
+fn apply_settings() {
+    let mut driver = driver();
+    let settings = Settings {
+        enable: EnableRegisterFlags::from_bits_truncate(0x22),
+        config: ConfigurationRegisterFlags::from_bits_truncate(0x35),
+        flash_brightness: LedFlashBrightnessFlags::from_bits_truncate(0x20),
+        torch_brightness: LedTorchBrightnessFlags::from_bits_truncate(0x10),
+    };
+    driver.apply_settings(&settings).unwrap();
+    check("apply_settings", &driver);
+}
+
+#[test]
+fn apply_preset() {
+    let mut driver = driver();
+    driver.apply_preset(&BOARD_DEFAULT).unwrap();
+    check("apply_preset", &driver);
+}
+
+#[test]
+fn schedule_tick() {
+    let mut driver = driver();
+    let mut controller =
+        ScheduleController::new(TimeOfDaySchedule::new(&[(0, ScheduledLevel::Ir(150))]));
+    controller.tick(&mut driver, 0).unwrap();
+    controller.tick(&mut driver, 1).unwrap();
+    check("schedule_tick", &driver);
+}
+
+#[test]
+fn replay() {
+    let mut driver = driver();
+    let transcript = [
+        Transaction::write(0x64, &[0x01, 0x02]).unwrap(),
+        Transaction::write_read(0x64, &[0x01], &[0x02]).unwrap(),
+    ];
+    assert_eq!(driver.replay(&transcript).unwrap(), None);
+    check("replay", &driver);
+}
+
+#[test]
+fn diagnostics() {
+    let mut driver = driver();
+    driver.diagnostics().unwrap();
+    check("diagnostics", &driver);
+}
+
+#[test]
+fn reserved_bits_preserved() {
+    let mut driver = driver();
+    driver.read_status().unwrap();
+    driver
+        .soft_start_torch(100, SoftStart::new(0u32, 1), &mut NoDelay)
+        .unwrap();
+    driver.software_reset().unwrap();
+    check("reserved_bits_preserved", &driver);
+}
+
+#[test]
+fn set_mode() {
+    let mut driver = driver();
+    driver.set_mode(OperatingMode::IrDrive).unwrap();
+    driver.set_mode(OperatingMode::Torch).unwrap();
+    check("set_mode", &driver);
+}
+
+#[test]
+fn get_mode() {
+    let mut driver = driver();
+    assert_eq!(driver.get_mode().unwrap(), OperatingMode::Standby);
+    check("get_mode", &driver);
+}
+
+#[test]
+fn set_flash_timeout() {
+    let mut driver = driver();
+    driver.set_flash_timeout(FlashTimeout::Ms1000).unwrap();
+    assert_eq!(driver.get_flash_timeout(), FlashTimeout::Ms1000);
+    check("set_flash_timeout", &driver);
+}
+
+#[test]
+fn set_ivfm_threshold() {
+    let mut driver = driver();
+    driver.set_ivfm_threshold(IvfmThreshold::V3_4).unwrap();
+    assert_eq!(driver.get_ivfm_threshold(), IvfmThreshold::V3_4);
+    check("set_ivfm_threshold", &driver);
+}
+
+#[test]
+fn set_torch_ramp() {
+    let mut driver = driver();
+    driver.set_torch_ramp(TorchRamp::Off).unwrap();
+    assert_eq!(driver.get_torch_ramp(), TorchRamp::Off);
+    check("set_torch_ramp", &driver);
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn set_torch_current() {
+    let mut driver = driver();
+    driver.set_torch_current(150.0).unwrap();
+    assert!(driver.set_torch_current(400.0).is_err());
+    check("set_torch_current", &driver);
+}
+
+#[cfg(feature = "libm")]
+#[test]
+fn libm_rounded_currents() {
+    let driver = driver();
+    // 128.15 mA and 1042.7 mA fall just below their step in `f32`
+    for (code, ma) in lm36011::flash_currents().enumerate() {
+        assert_eq!(driver.plan_flash_current(ma).unwrap().code as usize, code);
+    }
+    for (code, ma) in lm36011::torch_currents().enumerate() {
+        assert_eq!(driver.plan_torch_current(ma).unwrap().code as usize, code);
+    }
+}
+
+#[test]
+fn set_torch_level() {
+    let mut driver = driver();
+    driver.set_torch_level(0x3F).unwrap();
+    assert!(driver.set_torch_level(0x80).is_err());
+    check("set_torch_level", &driver);
+}
+
+#[test]
+fn set_current_ma() {
+    let mut driver = driver();
+    driver.set_flash_current_ma(1200).unwrap();
+    driver.set_torch_current_ma(100).unwrap();
+    check("set_current_ma", &driver);
+}
+
+#[test]
+fn set_current_with() {
+    let mut driver = driver();
+    driver
+        .set_flash_current_with(500, Rounding::Nearest)
+        .unwrap();
+    driver.set_flash_current_with(500, Rounding::Ceil).unwrap();
+    driver.set_torch_current_with(376, Rounding::Ceil).unwrap();
+    check("set_current_with", &driver);
+}
+
+#[test]
+fn set_brightness_percent() {
+    let mut driver = driver();
+    driver.set_torch_brightness_percent(50).unwrap();
+    driver.set_flash_brightness_percent(100).unwrap();
+    assert!(driver.set_flash_brightness_percent(101).is_err());
+    check("set_brightness_percent", &driver);
+}
+
+#[test]
+fn enable_torch() {
+    let mut driver = driver();
+    driver.enable_flags = EnableRegisterFlags::IVFM_ENABLE | EnableRegisterFlags::STROBE_ENABLE;
+    driver.enable_torch().unwrap();
+    driver.disable_output().unwrap();
+    check("enable_torch", &driver);
+}
+
+#[test]
+fn enable_ir_drive() {
+    let mut driver = driver();
+    driver.set_torch_current_ma(300).unwrap();
+    driver.enable_ir_drive(true).unwrap();
+    driver.enable_ir_drive(false).unwrap();
+    check("enable_ir_drive", &driver);
+}
+
+#[test]
+fn trigger_flash() {
+    let mut driver = driver();
+    driver.enable_flags = EnableRegisterFlags::IVFM_ENABLE | EnableRegisterFlags::STROBE_ENABLE;
+    driver.trigger_flash().unwrap();
+    check("trigger_flash", &driver);
+}
+
+#[test]
+fn shutdown() {
+    let mut driver = driver();
+    driver.enable_torch().unwrap();
+    driver.shutdown(true).unwrap();
+    check("shutdown", &driver);
+}
+
+#[test]
+fn set_thermal_scaleback() {
+    let mut driver = driver();
+    driver.set_thermal_scaleback(false).unwrap();
+    assert!(!driver.get_thermal_scaleback());
+    driver.set_thermal_scaleback(true).unwrap();
+    check("set_thermal_scaleback", &driver);
+}
+
+#[test]
+fn configure_strobe() {
+    let mut driver = driver();
+    driver.set_mode(OperatingMode::Flash).unwrap();
+    let strobe = StrobeConfig {
+        enabled: true,
+        trigger: StrobeTrigger::Edge,
+    };
+    driver.configure_strobe(strobe).unwrap();
+    assert_eq!(driver.get_strobe_config(), strobe);
+    check("configure_strobe", &driver);
+}
+
+#[test]
+fn ivfm_enable() {
+    let mut driver = driver();
+    driver.enable_torch().unwrap();
+    driver.disable_ivfm().unwrap();
+    assert!(!driver.ivfm_enabled());
+    driver.enable_ivfm().unwrap();
+    check("ivfm_enable", &driver);
+}
+
+#[test]
+fn apply_config() {
+    let mut driver = driver();
+    let mut config = driver.read_config().unwrap();
+    config.mode = OperatingMode::Torch;
+    config.torch_ma = 150;
+    config.flash_timeout = FlashTimeout::Ms400;
+    driver.apply_config(&config).unwrap();
+    let round_trip = driver.read_config().unwrap();
+    assert_eq!(round_trip.mode, OperatingMode::Torch);
+    assert_eq!(round_trip.flash_timeout, FlashTimeout::Ms400);
+    // applying the read-back configuration programs the same registers
+    driver.apply_config(&round_trip).unwrap();
+    check("apply_config", &driver);
+}
+
+#[test]
+fn builder() {
+    let driver = LM36011::builder(Recorder::new(FakeDevice::new()))
+        .torch_current_ma(150)
+        .flash_timeout(FlashTimeout::Ms600)
+        .ivfm(IvfmThreshold::V3_2)
+        .build()
+        .unwrap();
+    check("builder", &driver);
+
+    let flash = LM36011::builder(FakeDevice::new())
+        .mode(OperatingMode::Flash)
+        .build();
+    assert!(matches!(flash, Err(lm36011::LM36011Error::InvalidInput)));
+}
+
+#[test]
+fn with_por_defaults() {
+    let mut driver: Driver = LM36011::with_por_defaults(Recorder::new(FakeDevice::new()));
+    let cached = driver.settings();
+    driver.read_status().unwrap();
+    assert_eq!(driver.settings(), cached);
+    assert_eq!(cached, Settings::default());
+}
+
+#[test]
+fn typestate() {
+    let standby = typestate::Driver::new(driver()).ok().unwrap();
+    let torch = standby.into_torch().ok().unwrap();
+    let torch = torch.set_torch_current_ma(100).ok().unwrap();
+    let standby = torch.into_standby().ok().unwrap();
+    let mut flash = standby.into_flash().ok().unwrap();
+    assert!(!flash.is_done().unwrap());
+    let standby = flash.into_standby().ok().unwrap();
+    check("typestate", &standby.release());
+}
+
+#[test]
+fn free() {
+    let mut driver = driver();
+    driver.shutdown(false).unwrap();
+    let recorder = driver.free();
+    assert_eq!(recorder.transactions().count(), 1);
+}
+
+#[test]
+fn with_config() {
+    let mut config = Lm36011Config::from_settings(&Settings::POWER_ON_RESET);
+    config.torch_ma = 100;
+    let driver: Driver = LM36011::with_config(Recorder::new(FakeDevice::new()), &config).unwrap();
+    check("with_config", &driver);
+}
+
+#[test]
+fn configure() {
+    let mut driver = driver();
+    driver
+        .configure()
+        .torch_current_ma(150)
+        .flash_timeout(FlashTimeout::Ms600)
+        .commit()
+        .unwrap();
+    check("configure", &driver);
+}
+
+#[test]
+fn config_encoding() {
+    let mut config = Lm36011Config::from_settings(&Settings::POWER_ON_RESET);
+    config.mode = OperatingMode::Torch;
+    config.ivfm_enabled = true;
+    config.flash_timeout = FlashTimeout::Ms1600;
+    config.flash_ma = 1000;
+    config.torch_ma = 101;
+    config.thermal_scaleback = true;
+
+    let bytes = config.encode();
+    assert_eq!(bytes, [1, 0x92, 0x1F, 0xE8, 0x03, 0x65, 0x00]);
+    assert_eq!(Lm36011Config::decode(&bytes), Some(config));
+
+    let mut reserved = bytes;
+    reserved[1] |= 0x20;
+    assert_eq!(Lm36011Config::decode(&reserved), None);
+    assert_eq!(Lm36011Config::decode(&bytes[..6]), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn config_postcard() {
+    let config = Lm36011Config::from_settings(&Settings::POWER_ON_RESET);
+    let mut buffer = [0; 16];
+    let bytes = postcard::to_slice(&config, &mut buffer).unwrap();
+    assert_eq!(bytes, &config.encode());
+    assert_eq!(
+        postcard::from_bytes::<Lm36011Config>(bytes).unwrap(),
+        config
+    );
+    assert!(postcard::from_bytes::<Lm36011Config>(&[9; 7]).is_err());
+}
+
+#[test]
+fn telemetry_round_trip() {
+    use lm36011::telemetry::{TelemetryEncoder, TelemetryFrame};
+
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x02)));
+    driver.set_torch_current_ma(100).unwrap();
+    driver.read_status().unwrap();
+
+    let mut encoder = TelemetryEncoder::new();
+    let frame = encoder.frame(&driver);
+    assert_eq!(
+        (frame.sequence, frame.torch_ma, frame.fault_count),
+        (0, 99, 1)
+    );
+    assert_eq!(TelemetryFrame::decode(&frame.encode()), Some(frame));
+
+    // the UVLO flag is still raised, so it is not counted again
+    let bytes = encoder.encode(&driver);
+    let frame = TelemetryFrame::decode(&bytes).unwrap();
+    assert_eq!((frame.sequence, frame.fault_count), (1, 1));
+    assert_eq!(frame.flags.bits(), 0x02);
+}
+
+#[test]
+fn telemetry_checksum() {
+    use lm36011::telemetry::{TelemetryEncoder, TelemetryFrame};
+
+    let bytes = TelemetryEncoder::new().encode(&driver());
+    assert!(TelemetryFrame::decode(&bytes).is_some());
+    for index in 2..TelemetryFrame::LEN {
+        let mut corrupted = bytes;
+        corrupted[index] ^= 0x01;
+        assert_eq!(TelemetryFrame::decode(&corrupted), None);
+    }
+    assert_eq!(
+        TelemetryFrame::decode(&bytes[..TelemetryFrame::LEN - 1]),
+        None
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn telemetry_stream_resync() {
+    use lm36011::telemetry::{StreamDecoder, TelemetryEncoder, TelemetryFrame};
+
+    let driver = driver();
+    let mut encoder = TelemetryEncoder::new();
+    let first = encoder.frame(&driver);
+    let second = encoder.frame(&driver);
+    let mut corrupted = encoder.encode(&driver);
+    corrupted[TelemetryFrame::LEN - 1] ^= 0xFF;
+
+    let mut decoder = StreamDecoder::new();
+    // garbage, including a stray sync byte, then a frame split across two chunks
+    decoder.push(&[0x00, 0xA5, 0x13, 0x37]);
+    decoder.push(&first.encode()[..5]);
+    assert_eq!(decoder.next_frame(), None);
+    decoder.push(&first.encode()[5..]);
+    decoder.push(&corrupted);
+    decoder.push(&second.encode());
+
+    assert_eq!(decoder.next_frame(), Some(first));
+    assert_eq!(decoder.next_frame(), Some(second));
+    assert_eq!(decoder.next_frame(), None);
+}
+
+#[test]
+fn read_faults() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x22)));
+    let faults = driver.read_faults().unwrap();
+    assert!(faults.vled_short && faults.uvlo && faults.is_fault());
+    assert_eq!(driver.read_faults().unwrap(), Faults::default());
+    check("read_faults", &driver);
+}
+
+#[test]
+fn clear_faults() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x04)));
+    // an unrelated read consumes the flags on the device
+    driver.read_status().unwrap();
+    assert!(driver.pending_faults().thermal_shutdown);
+    assert!(driver.read_faults().unwrap().thermal_shutdown);
+    assert_eq!(driver.pending_faults(), Faults::default());
+
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x04)));
+    driver.clear_faults().unwrap();
+    assert_eq!(driver.read_faults().unwrap(), Faults::default());
+    check("clear_faults", &driver);
+}
+
+#[test]
+fn flash_and_check() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x02)));
+    let faults = driver.flash_and_check(&mut NoDelay).unwrap();
+    // the stale UVLO flag is cleared before the flash
+    assert_eq!(faults, Faults::default());
+    check("flash_and_check", &driver);
+}
+
+#[test]
+fn fault_handler() {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    static SHORTS: AtomicU8 = AtomicU8::new(0);
+    fn count_shorts(faults: Faults) {
+        if faults.vled_short {
+            SHORTS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let mut driver: Driver =
+        LM36011::new(Recorder::new(FakeDevice::with_flags(0x20))).with_fault_handler(&count_shorts);
+    driver.read_status().unwrap();
+    driver.read_status().unwrap();
+    assert_eq!(SHORTS.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn driver_is_send() {
+    // e.g. an RTIC shared resource or a driver moved to another thread
+    fn assert_send<T: Send>() {}
+    assert_send::<Driver>();
+}
+
+#[test]
+fn monitor_faults() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x08)));
+    let faults = driver.monitor_faults(&mut NoDelay, 100, 3).unwrap();
+    assert!(faults.thermal_scaleback && !faults.is_fault());
+    check("monitor_faults", &driver);
+}
+
+#[test]
+fn fault_cutoff() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x04)));
+    driver.set_fault_cutoff(true);
+    driver.enable_torch().unwrap();
+    assert!(matches!(
+        driver.read_faults(),
+        Err(lm36011::LM36011Error::FaultShutdown)
+    ));
+    assert!(driver.is_cut_off());
+    assert!(driver.read_faults().unwrap().thermal_shutdown);
+    assert!(matches!(
+        driver.enable_torch(),
+        Err(lm36011::LM36011Error::FaultShutdown)
+    ));
+    driver.clear_faults().unwrap();
+    driver.enable_torch().unwrap();
+    check("fault_cutoff", &driver);
+}
+
+#[test]
+fn fault_history() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x02)));
+    driver.read_status().unwrap();
+    driver.read_status().unwrap();
+    let history = driver.fault_history();
+    assert!(history.uvlo && !history.flash_timeout);
+    driver.reset_fault_history();
+    assert_eq!(driver.fault_history(), Faults::default());
+}
+
+#[test]
+fn take_faults() {
+    let mut driver = LM36011::new(FakeDevice::with_flags(0x02));
+    driver.read_status().unwrap();
+    driver.bus_mut().registers[Register::FlagsRegister as usize] = 0x00;
+    driver.read_status().unwrap();
+    assert!(driver
+        .flag_register_flags
+        .contains(lm36011::FlagRegisterFlags::UVLO_FAULT));
+    let faults = driver.take_faults();
+    assert!(faults.uvlo && !faults.vled_short);
+    assert!(driver.flag_register_flags.is_empty());
+    assert_eq!(driver.take_faults(), Faults::default());
+}
+
+#[test]
+fn has_fault() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x02)));
+    assert!(driver.has_fault().unwrap());
+    assert!(driver.pending_faults().uvlo);
+    check("has_fault", &driver);
+
+    // a flash time-out is not a fault
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x01)));
+    assert!(!driver.has_fault().unwrap());
+}
+
+#[test]
+fn describe_faults() {
+    use lm36011::FlagRegisterFlags;
+
+    assert_eq!(
+        FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT.describe(),
+        "thermal shutdown"
+    );
+    assert_eq!(FlagRegisterFlags::empty().describe(), "none");
+    let faults = Faults::from_flags(FlagRegisterFlags::from_bits_truncate(0x26));
+    assert_eq!(faults.to_string(), "VLED short, thermal shutdown, UVLO");
+    assert_eq!(Faults::default().to_string(), "none");
+    assert_eq!(faults.flags().bits(), 0x26);
+}
+
+#[test]
+fn error_display() {
+    use lm36011::LM36011Error;
+
+    let error: LM36011Error<Infallible> = LM36011Error::TimeoutOutOfRange;
+    assert_eq!(error.to_string(), "flash time-out out of range");
+    let boxed: Box<dyn std::error::Error> = Box::new(error);
+    assert_eq!(boxed.to_string(), "flash time-out out of range");
+}
+
+#[test]
+fn error_source() {
+    use lm36011::LM36011Error;
+    use std::error::Error;
+
+    fn bus_call() -> Result<(), LM36011Error<std::fmt::Error>> {
+        Err(std::fmt::Error)?;
+        Ok(())
+    }
+
+    let error = bus_call().unwrap_err();
+    assert!(error.is_bus_error());
+    assert_eq!(error.to_string(), "I2C bus error");
+    assert!(error.source().unwrap().is::<std::fmt::Error>());
+    assert_eq!(error.into_inner(), Some(std::fmt::Error));
+
+    let error: LM36011Error<std::fmt::Error> = LM36011Error::InvalidInput;
+    assert!(!error.is_bus_error() && error.source().is_none());
+    assert_eq!(error.into_inner(), None);
+}
+
+#[test]
+fn error_codes() {
+    use lm36011::LM36011Error;
+
+    // the codes are a wire format, a reordering must not change them
+    let errors = [
+        (LM36011Error::I2CError(()), 1),
+        (LM36011Error::InvalidInput, 2),
+        (LM36011Error::CurrentOutOfRange, 3),
+        (LM36011Error::DeviceIDError, 4),
+        (LM36011Error::StrobePinError, 5),
+        (LM36011Error::TemperatureSensorError, 6),
+        (LM36011Error::TimeoutOutOfRange, 7),
+        (LM36011Error::InterlockEngaged, 8),
+        (LM36011Error::CurrentSenseError, 9),
+        (LM36011Error::StandbyVerifyError, 10),
+        (LM36011Error::FaultShutdown, 11),
+    ];
+    for (error, code) in errors {
+        assert_eq!(error.code(), code, "{error:?}");
+        let decoded = LM36011Error::from_code(code).unwrap();
+        assert_eq!(format!("{decoded:?}"), format!("{error:?}"));
+    }
+    assert!(LM36011Error::from_code(0).is_none());
+    assert!(LM36011Error::from_code(12).is_none());
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn log_register_traffic() {
+    use std::sync::Mutex;
+    use std::thread::{self, ThreadId};
+
+    /// Collects trace records with the thread that emitted them, as tests run in parallel.
+    struct Collector(Mutex<Vec<(ThreadId, String)>>);
+
+    impl log::Log for Collector {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let line = format!("{} {}", record.level(), record.args());
+            self.0.lock().unwrap().push((thread::current().id(), line));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static COLLECTOR: Collector = Collector(Mutex::new(Vec::new()));
+    log::set_logger(&COLLECTOR).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let mut driver = driver();
+    driver.set_register(Register::EnableRegister, 0x12).unwrap();
+    driver.get_register(Register::FlagsRegister).unwrap();
+    // the fire paths stay free of formatting
+    driver.write_current_and_fire(0x30).unwrap();
+    driver.fire_flash(&mut TriggerMode::<Pin>::I2cOnly).unwrap();
+
+    let thread = thread::current().id();
+    let lines: Vec<String> = COLLECTOR
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(id, _)| *id == thread)
+        .map(|(_, line)| line.clone())
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "TRACE LM36011 write Enable Register: 0x12",
+            "TRACE LM36011 read Flags Register: 0x00",
+        ]
+    );
+}
+
+#[cfg(feature = "ufmt")]
+#[test]
+fn ufmt_formatting() {
+    use lm36011::FlagRegisterFlags;
+
+    struct Buffer(String);
+
+    impl ufmt::uWrite for Buffer {
+        type Error = Infallible;
+
+        fn write_str(&mut self, s: &str) -> Result<(), Infallible> {
+            self.0.push_str(s);
+            Ok(())
+        }
+    }
+
+    let mut out = Buffer(String::new());
+    let flags = FlagRegisterFlags::from_bits_truncate(0x22);
+    ufmt::uwrite!(out, "{} | {:?} | ", Register::FlagsRegister, flags).unwrap();
+    ufmt::uwrite!(
+        out,
+        "{} | {:?}",
+        Faults::from_flags(flags),
+        Register::EnableRegister
+    )
+    .unwrap();
+    assert_eq!(
+        out.0,
+        "Flags Register | FlagRegisterFlags(0x22) | VLED short, UVLO | EnableRegister"
+    );
+}
+
+#[test]
+fn recover_from_uvlo() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x02)));
+    driver.set_torch_current_ma(100).unwrap();
+    driver.enable_torch().unwrap();
+    driver.bus_mut().clear();
+    // 2.8 V is below the default 2.9 V IVFM threshold
+    assert!(!driver.recover_from_uvlo(2800).unwrap());
+    assert_eq!(driver.get_mode().unwrap(), OperatingMode::Standby);
+    driver.enable_torch().unwrap();
+    assert!(driver.recover_from_uvlo(3700).unwrap());
+    assert_eq!(driver.get_mode().unwrap(), OperatingMode::Torch);
+    check("recover_from_uvlo", &driver);
+}
+
+#[test]
+fn self_test_led() {
+    let mut driver = driver();
+    driver.set_torch_current_ma(100).unwrap();
+    driver.bus_mut().clear();
+    assert_eq!(
+        driver.self_test_led(&mut NoDelay).unwrap(),
+        LedCheck::Healthy
+    );
+    check("self_test_led", &driver);
+}
+
+#[test]
+fn self_test_led_cut_off() {
+    // the stale thermal shutdown trips the cut-off before the torch is turned on
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x04)));
+    driver.set_fault_cutoff(true);
+    assert!(matches!(
+        driver.self_test_led(&mut NoDelay),
+        Err(lm36011::LM36011Error::FaultShutdown)
+    ));
+    assert!(driver.is_cut_off());
+    check("self_test_led_cut_off", &driver);
+}
+
+#[test]
+fn rearm_after_timeout() {
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x01)));
+    let mut trigger: TriggerMode = TriggerMode::Hybrid;
+    assert!(driver.rearm_after_timeout(&mut trigger, Some(500)).unwrap());
+    assert!(!driver.pending_faults().flash_timeout);
+    assert!(!driver.rearm_after_timeout(&mut trigger, None).unwrap());
+    check("rearm_after_timeout", &driver);
+}
+
+#[test]
+fn fault_stats() {
+    let driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x03)));
+    assert_eq!(driver.fault_stats(), None);
+
+    let mut driver = driver.with_fault_stats();
+    driver.read_faults().unwrap();
+    driver.read_faults().unwrap();
+    driver.clear_fault_counters();
+    let stats = driver.fault_stats().unwrap();
+    assert_eq!(
+        (stats.uvlo, stats.flash_timeout, stats.vled_short),
+        (1, 1, 0)
+    );
+
+    // the diagnostics record carries the time-outs too
+    let mut driver: Driver = LM36011::new(Recorder::new(FakeDevice::with_flags(0x01)));
+    let report = driver.diagnostics().unwrap();
+    assert_eq!(report.fault_counters.flash_timeout, 1);
+    let decoded = lm36011::diagnostics::Diagnostics::decode(&report.encode());
+    assert_eq!(decoded, Some(report));
+}
+
+#[test]
+fn fault_severity() {
+    use lm36011::{FaultSeverity, FlagRegisterFlags};
+
+    let severity = |flags| Faults::from_flags(flags).severity();
+    assert_eq!(severity(FlagRegisterFlags::empty()), None);
+    assert_eq!(
+        severity(FlagRegisterFlags::FLASH_TIMEOUT_FLAG),
+        Some(FaultSeverity::Info)
+    );
+    assert_eq!(
+        severity(FlagRegisterFlags::THERMAL_CURRENT_SCALE_BACK),
+        Some(FaultSeverity::Degraded)
+    );
+    assert_eq!(
+        severity(FlagRegisterFlags::THERMAL_SHUTDOWN_FAULT | FlagRegisterFlags::IVFM_TRIP),
+        Some(FaultSeverity::Critical)
+    );
+    assert!(FaultSeverity::Degraded > FaultSeverity::Info);
+}
+
+/// `FakeDevice` behind the `embedded-hal` 1.0 I2C trait.
+#[cfg(feature = "eh1")]
+struct Eh1Device(FakeDevice);
+
+#[cfg(feature = "eh1")]
+impl embedded_hal_1::i2c::ErrorType for Eh1Device {
+    type Error = Infallible;
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal_1::i2c::I2c for Eh1Device {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal_1::i2c::Operation<'_>],
+    ) -> Result<(), Infallible> {
+        use embedded_hal_1::i2c::Operation;
+        use lm36011::BusDevice;
+
+        let mut pointer: &[u8] = &[];
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    self.0.write(address, bytes)?;
+                    pointer = bytes;
+                }
+                Operation::Read(buffer) => self.0.write_read(address, pointer, buffer)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+#[test]
+fn eh1_i2c_device() {
+    use lm36011::eh1::I2cDevice;
+
+    let mut driver = LM36011::new(I2cDevice::new(Eh1Device(FakeDevice::new())));
+    driver.set_torch_current_ma(100).unwrap();
+    driver.enable_torch().unwrap();
+    let torch_ma = driver.get_torch_current_ma();
+    driver.torch_brightness_flags = LedTorchBrightnessFlags::empty();
+    driver.read_status().unwrap();
+    assert_eq!(driver.get_torch_current_ma(), torch_ma);
+    assert_eq!(driver.free().into_inner().0.registers[1] & 0x03, 0x02);
+}
+
+/// `FakeDevice` behind the async `embedded-hal-async` I2C trait, recording the transactions.
+#[cfg(feature = "async")]
+struct AsyncDevice(Recorder<FakeDevice, 64>);
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::ErrorType for AsyncDevice {
+    type Error = Infallible;
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for AsyncDevice {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal_async::i2c::Operation<'_>],
+    ) -> Result<(), Infallible> {
+        use embedded_hal_async::i2c::Operation;
+        use lm36011::BusDevice;
+
+        match operations {
+            [Operation::Write(bytes)] => self.0.write(address, bytes),
+            [Operation::Write(bytes), Operation::Read(buffer)] => {
+                self.0.write_read(address, bytes, buffer)
+            }
+            _ => unimplemented!("the driver only writes or writes then reads"),
+        }
+    }
+}
+
+/// Polls a future that never waits to completion.
+#[cfg(feature = "async")]
+fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    fn raw_waker() -> RawWaker {
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(|_| raw_waker(), |_| {}, |_| {}, |_| {});
+
+    let waker = unsafe { Waker::from_raw(raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = core::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_driver() {
+    use lm36011::asynch::LM36011Async;
+
+    let mut blocking = LM36011::new(Recorder::new(FakeDevice::with_flags(0x02)));
+    blocking.verify_device_id().unwrap();
+    blocking.set_torch_current_ma(100).unwrap();
+    blocking.enable_torch().unwrap();
+    blocking.set_thermal_scaleback(false).unwrap();
+    let blocking_faults = blocking.read_faults().unwrap();
+    blocking.read_status().unwrap();
+
+    let mut driver = LM36011Async::new(AsyncDevice(Recorder::new(FakeDevice::with_flags(0x02))));
+    block_on(async {
+        driver.verify_device_id().await.unwrap();
+        driver.set_torch_current_ma(100).await.unwrap();
+        driver.enable_torch().await.unwrap();
+        driver.set_thermal_scaleback(false).await.unwrap();
+        assert_eq!(driver.read_faults().await.unwrap(), blocking_faults);
+        driver.read_status().await.unwrap();
+    });
+
+    assert_eq!(driver.driver().enable_flags, blocking.enable_flags);
+    assert_eq!(
+        driver.driver().get_torch_current_ma(),
+        blocking.get_torch_current_ma()
+    );
+    assert_eq!(trace(&driver.free().0), trace(blocking.bus()));
+}
+
+/// An async delay that returns at once.
+#[cfg(feature = "async")]
+struct AsyncNoDelay;
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for AsyncNoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_monitor_faults() {
+    use lm36011::asynch::LM36011Async;
+
+    let mut blocking = LM36011::new(Recorder::new(FakeDevice::with_flags(0x02)));
+    let blocking_faults = blocking.monitor_faults(&mut NoDelay, 10, 3).unwrap();
+
+    let mut driver = LM36011Async::new(AsyncDevice(Recorder::new(FakeDevice::with_flags(0x02))));
+    let faults = block_on(driver.monitor_faults(&mut AsyncNoDelay, 10, 3)).unwrap();
+
+    assert!(faults.uvlo);
+    assert_eq!(faults, blocking_faults);
+    assert_eq!(trace(&driver.free().0), trace(blocking.bus()));
+}
+
+/// A trigger input whose awaited edge has always just happened.
+#[cfg(all(feature = "async", feature = "eh1"))]
+struct Edge;
+
+#[cfg(all(feature = "async", feature = "eh1"))]
+impl embedded_hal_1::digital::ErrorType for Edge {
+    type Error = Infallible;
+}
+
+#[cfg(all(feature = "async", feature = "eh1"))]
+impl embedded_hal_async::digital::Wait for Edge {
+    async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "async", feature = "eh1"))]
+#[test]
+fn await_trigger_and_fire() {
+    use lm36011::asynch::{LM36011Async, TriggerEdge};
+
+    // an armed STROBE input must not hold back the flash
+    let mut blocking: Driver = LM36011::new(Recorder::new(FakeDevice::new()));
+    blocking
+        .enable_flags
+        .insert(EnableRegisterFlags::STROBE_ENABLE);
+    block_on(blocking.await_trigger_and_fire(&mut Edge, TriggerEdge::Rising)).unwrap();
+
+    let mut driver = LM36011Async::new(AsyncDevice(Recorder::new(FakeDevice::new())));
+    driver
+        .driver_mut()
+        .enable_flags
+        .insert(EnableRegisterFlags::STROBE_ENABLE);
+    block_on(driver.await_trigger_and_fire(&mut Edge, TriggerEdge::Rising)).unwrap();
+
+    assert_eq!(driver.driver().enable_flags, blocking.enable_flags);
+    assert_eq!(trace(&driver.free().0), trace(blocking.bus()));
+    check("await_trigger_and_fire", &blocking);
+}
+
+/// A trigger input whose wait always fails.
+#[cfg(all(feature = "async", feature = "eh1"))]
+struct BrokenEdge;
+
+#[cfg(all(feature = "async", feature = "eh1"))]
+impl embedded_hal_1::digital::ErrorType for BrokenEdge {
+    type Error = embedded_hal_1::digital::ErrorKind;
+}
+
+#[cfg(all(feature = "async", feature = "eh1"))]
+impl embedded_hal_async::digital::Wait for BrokenEdge {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        Err(embedded_hal_1::digital::ErrorKind::Other)
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        Err(embedded_hal_1::digital::ErrorKind::Other)
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        Err(embedded_hal_1::digital::ErrorKind::Other)
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        Err(embedded_hal_1::digital::ErrorKind::Other)
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        Err(embedded_hal_1::digital::ErrorKind::Other)
+    }
+}
+
+#[cfg(all(feature = "async", feature = "eh1"))]
+#[test]
+fn await_trigger_failure_keeps_unlock() {
+    use lm36011::asynch::{LM36011Async, TriggerEdge};
+
+    let mut blocking: Driver = LM36011::new(Recorder::new(FakeDevice::new()));
+    blocking.set_interlock_threshold(Some(500));
+    let token = blocking.request_unlock(0, None);
+    blocking.unlock(token, 0).unwrap();
+    blocking.set_flash_current_ma(1200).unwrap();
+    assert!(matches!(
+        block_on(blocking.await_trigger_and_fire(&mut BrokenEdge, TriggerEdge::Rising)),
+        Err(lm36011::LM36011Error::StrobePinError)
+    ));
+    assert!(blocking.is_unlocked());
+    block_on(blocking.await_trigger_and_fire(&mut Edge, TriggerEdge::Rising)).unwrap();
+    assert!(!blocking.is_unlocked());
+
+    let mut driver = LM36011Async::new(AsyncDevice(Recorder::new(FakeDevice::new())));
+    driver.driver_mut().set_interlock_threshold(Some(500));
+    let token = driver.driver_mut().request_unlock(0, None);
+    driver.driver_mut().unlock(token, 0).unwrap();
+    block_on(driver.set_flash_current_ma(1200)).unwrap();
+    assert!(matches!(
+        block_on(driver.await_trigger_and_fire(&mut BrokenEdge, TriggerEdge::Rising)),
+        Err(lm36011::LM36011Error::StrobePinError)
+    ));
+    assert!(driver.driver().is_unlocked());
+    block_on(driver.await_trigger_and_fire(&mut Edge, TriggerEdge::Rising)).unwrap();
+    assert!(!driver.driver().is_unlocked());
+
+    assert_eq!(trace(&driver.free().0), trace(blocking.bus()));
+}
+
+#[cfg(all(feature = "eh1", not(feature = "eh0")))]
+#[test]
+fn eh1_without_eh0() {
+    // 1.0 buses are taken as is, without `eh1::I2cDevice`
+    let mut driver = LM36011::new(Eh1Device(FakeDevice::new()));
+    driver.set_torch_current_ma(100).unwrap();
+    let torch = driver.torch_brightness_flags.bits();
+    assert_eq!(driver.free().0.registers[4], torch);
+}
+
+#[test]
+fn shared_bus() {
+    use lm36011::shared::{BorrowedBus, RefCellBus};
+    use std::cell::RefCell;
+
+    let bus = RefCell::new(FakeDevice::new());
+    let mut first = LM36011::new(RefCellBus::new(&bus));
+    let mut second = LM36011::new(RefCellBus::new(&bus));
+    first.set_torch_current_ma(100).unwrap();
+    second.read_status().unwrap();
+    assert_eq!(second.torch_brightness_flags, first.torch_brightness_flags);
+
+    let mut device = bus.into_inner();
+    LM36011::new(BorrowedBus::new(&mut device))
+        .set_torch_level(0)
+        .unwrap();
+    assert_eq!(device.registers[4], 0);
+}
+
+/// `FakeDevice` behind the embedded-hal 0.2 `Transactional` trait, counting transactions.
+struct TransactionalDevice {
+    device: FakeDevice,
+    transactions: usize,
+}
+
+impl embedded_hal::blocking::i2c::Transactional for TransactionalDevice {
+    type Error = Infallible;
+
+    fn exec(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::blocking::i2c::Operation<'_>],
+    ) -> Result<(), Infallible> {
+        use embedded_hal::blocking::i2c::Operation;
+        use lm36011::BusDevice;
+
+        self.transactions += 1;
+        match operations {
+            [Operation::Write(bytes)] => self.device.write(address, bytes),
+            [Operation::Write(bytes), Operation::Read(buffer)] => {
+                self.device.write_read(address, bytes, buffer)
+            }
+            _ => unimplemented!("the driver only writes or writes then reads"),
+        }
+    }
+}
+
+#[test]
+fn transactional_bus() {
+    use lm36011::TransactionalBus;
+
+    let mut driver = LM36011::new(TransactionalBus::new(TransactionalDevice {
+        device: FakeDevice::new(),
+        transactions: 0,
+    }));
+    driver.set_torch_current_ma(100).unwrap();
+    driver.read_status().unwrap();
+    let bus = driver.free().into_inner();
+    assert_eq!(bus.transactions, 2);
+}
@@ -0,0 +1,1048 @@
+//! Wire-format tests against `embedded-hal-mock`.
+//!
+//! Each test spells out the exact I2C transactions a public method must issue, in order, and
+//! fails on any missing, extra or different byte. Unlike the golden traces, the expectations
+//! live next to the call, and every read is answered with a value chosen by the test.
+
+// the fire paths read the DWT cycle counter, which only exists on Cortex-M
+#![cfg(all(feature = "eh0", not(feature = "cycle-count")))]
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal_mock::eh0::digital::{Mock as PinMock, State, Transaction as PinTransaction};
+use embedded_hal_mock::eh0::i2c::{Mock, Transaction};
+use embedded_hal_mock::eh0::MockError;
+use lm36011::persist::Settings;
+use lm36011::presets::BoardPreset;
+use lm36011::{
+    ConfigurationRegisterFlags, CurrentCheck, CurrentSense, EnableRegisterFlags, FlashOutcome,
+    FlashTimeout, IvfmThreshold, LM36011Error, LedCheck, LedFlashBrightnessFlags,
+    LedTorchBrightnessFlags, LimitProfile, OperatingMode, RangePolicy, SoftStart, StrobeConfig,
+    StrobeTrigger, TorchRamp, TriggerMode, LM36011,
+};
+
+const ADDRESS: u8 = 0x64;
+
+/// Runs `operation` on a fresh driver whose bus expects exactly `transactions`.
+fn expect<T>(transactions: &[Transaction], operation: impl FnOnce(&mut LM36011<Mock>) -> T) -> T {
+    let mut driver = LM36011::new(Mock::new(transactions));
+    let result = operation(&mut driver);
+    driver.free().done();
+    result
+}
+
+fn write(bytes: &[u8]) -> Transaction {
+    Transaction::write(ADDRESS, bytes.to_vec())
+}
+
+fn read(register: u8, response: &[u8]) -> Transaction {
+    Transaction::write_read(ADDRESS, vec![register], response.to_vec())
+}
+
+struct NoDelay;
+
+impl DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
+
+impl DelayMs<u16> for NoDelay {
+    fn delay_ms(&mut self, _ms: u16) {}
+}
+
+#[test]
+fn get_device_id() {
+    let id = expect(&[read(0x06, &[0x01])], |driver| driver.get_device_id());
+    assert_eq!(id.unwrap(), 0x01);
+}
+
+#[test]
+fn read_status() {
+    expect(
+        &[read(0x01, &[0x32, 0x15, 0x40, 0x21, 0x00, 0x01])],
+        |driver| {
+            driver.read_status().unwrap();
+            assert_eq!(driver.enable_flags.bits(), 0x32);
+            assert_eq!(driver.torch_brightness_flags.bits(), 0x21);
+        },
+    );
+}
+
+#[test]
+fn verify_device_id() {
+    let matches = expect(
+        &[read(0x01, &[0x20, 0x15, 0x00, 0x00, 0x00, 0x01])],
+        |driver| driver.verify_device_id(),
+    );
+    assert!(matches.unwrap());
+}
+
+#[test]
+fn set_flash_current_hex() {
+    expect(&[write(&[0x03, 0xA0])], |driver| {
+        driver.set_flash_current_hex(0x20)
+    })
+    .unwrap();
+}
+
+#[test]
+fn set_current_ma() {
+    expect(&[write(&[0x03, 0xE5]), write(&[0x04, 0x21])], |driver| {
+        driver.set_flash_current_ma(1200)?;
+        driver.set_torch_current_ma(100)
+    })
+    .unwrap();
+}
+
+#[test]
+fn auto_standby() {
+    expect(
+        &[
+            write(&[0x01, 0x12]),
+            write(&[0x01, 0x10]),
+            write(&[0x04, 0x21]),
+            write(&[0x01, 0x12]),
+        ],
+        |driver| {
+            driver.set_auto_standby(true);
+            driver.enable_torch()?;
+            driver.set_torch_current_ma(0)?;
+            driver.set_torch_current_ma(100)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn auto_standby_does_not_restore_flash() {
+    // the flash is one-shot, a new current must not fire it again
+    expect(
+        &[
+            write(&[0x01, 0x13]),
+            write(&[0x01, 0x10]),
+            write(&[0x03, 0xE5]),
+        ],
+        |driver| {
+            driver.set_auto_standby(true);
+            driver.trigger_flash()?;
+            driver.set_flash_current_ma(0)?;
+            driver.set_flash_current_ma(1200)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn set_torch_level() {
+    expect(&[write(&[0x04, 0x3F])], |driver| {
+        driver.set_torch_level(0x3F)
+    })
+    .unwrap();
+}
+
+#[test]
+fn invalid_torch_level_is_not_written() {
+    let result = expect(&[], |driver| driver.set_torch_level(0x80));
+    assert!(matches!(result, Err(LM36011Error::InvalidInput)));
+}
+
+#[test]
+fn set_mode() {
+    expect(&[write(&[0x01, 0x11]), write(&[0x01, 0x12])], |driver| {
+        driver.set_mode(OperatingMode::IrDrive)?;
+        driver.set_mode(OperatingMode::Torch)
+    })
+    .unwrap();
+}
+
+#[test]
+fn get_mode() {
+    let mode = expect(&[read(0x01, &[0x33])], |driver| driver.get_mode());
+    assert_eq!(mode.unwrap(), OperatingMode::Flash);
+}
+
+#[test]
+fn enable_torch_and_disable_output() {
+    expect(&[write(&[0x01, 0x12]), write(&[0x01, 0x10])], |driver| {
+        driver.enable_torch()?;
+        driver.disable_output()
+    })
+    .unwrap();
+}
+
+#[test]
+fn trigger_flash() {
+    expect(&[write(&[0x01, 0x13])], |driver| driver.trigger_flash()).unwrap();
+}
+
+#[test]
+fn write_current_and_fire() {
+    expect(&[write(&[0x03, 0xB0]), write(&[0x01, 0x13])], |driver| {
+        // the STROBE input is disabled, so the flash starts with the write
+        driver
+            .enable_flags
+            .insert(EnableRegisterFlags::STROBE_ENABLE);
+        driver.write_current_and_fire(0x30)
+    })
+    .unwrap();
+}
+
+#[test]
+fn interlock_allows_one_flash() {
+    let result = expect(&[write(&[0x03, 0xE5]), write(&[0x01, 0x13])], |driver| {
+        driver.set_interlock_threshold(Some(500));
+        let token = driver.request_unlock(0, None);
+        driver.unlock(token, 0).unwrap();
+        driver.set_flash_current_ma(1200).unwrap();
+        driver.trigger_flash().unwrap();
+        // the next flash at the same current needs another unlock
+        assert!(matches!(
+            driver.write_current_and_fire(0x65),
+            Err(LM36011Error::InterlockEngaged)
+        ));
+        assert!(matches!(
+            driver.set_mode(OperatingMode::Flash),
+            Err(LM36011Error::InterlockEngaged)
+        ));
+        driver.trigger_flash()
+    });
+    assert!(matches!(result, Err(LM36011Error::InterlockEngaged)));
+}
+
+#[test]
+fn hybrid_flash() {
+    let mut trigger: TriggerMode = TriggerMode::Hybrid;
+    expect(
+        &[
+            write(&[0x01, 0x17]),
+            write(&[0x01, 0x13]),
+            write(&[0x01, 0x10]),
+        ],
+        |driver| {
+            driver.arm_flash(&mut trigger)?;
+            driver.fire_flash(&mut trigger)?;
+            driver.end_flash(&mut trigger)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn strobe_flash() {
+    let pin = PinMock::new(&[
+        PinTransaction::set(State::Low),
+        PinTransaction::set(State::High),
+        PinTransaction::set(State::Low),
+    ]);
+    let mut trigger = TriggerMode::HardwareStrobe { pin };
+    // firing and ending the flash only toggle the pin
+    expect(&[write(&[0x01, 0x17])], |driver| {
+        driver.arm_flash(&mut trigger)?;
+        driver.fire_flash(&mut trigger)?;
+        driver.end_flash(&mut trigger)
+    })
+    .unwrap();
+    let TriggerMode::HardwareStrobe { mut pin } = trigger else {
+        unreachable!()
+    };
+    pin.done();
+}
+
+#[test]
+fn flash_once() {
+    expect(
+        &[
+            write(&[0x02, 0x19]),
+            write(&[0x01, 0x13]),
+            write(&[0x01, 0x10]),
+        ],
+        |driver| driver.flash_once(1000u32, &mut NoDelay),
+    )
+    .unwrap();
+}
+
+#[test]
+fn flash_once_beyond_timeout_is_not_written() {
+    let result = expect(&[], |driver| driver.flash_once(2000u32, &mut NoDelay));
+    assert!(matches!(result, Err(LM36011Error::TimeoutOutOfRange)));
+}
+
+/// Delay that remembers the last wait.
+#[derive(Default)]
+struct LastDelay(u16);
+
+impl DelayMs<u16> for LastDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.0 = ms;
+    }
+}
+
+#[test]
+fn flash_once_clamped_to_timeout_limit() {
+    let mut delay = LastDelay::default();
+    expect(
+        &[
+            write(&[0x02, 0x1F]),
+            write(&[0x01, 0x13]),
+            write(&[0x01, 0x10]),
+        ],
+        |driver| {
+            driver.set_range_policy(RangePolicy::ClampToLimit);
+            driver.flash_once(2000u32, &mut delay)
+        },
+    )
+    .unwrap();
+    assert_eq!(delay.0, 1600);
+}
+
+#[test]
+fn currents_clamped_to_limits() {
+    let mut driver: LM36011<Mock, 800> =
+        LM36011::new_limited(Mock::new(&[write(&[0x03, 0xC3]), write(&[0x04, 0x7F])]));
+    assert!(matches!(
+        driver.set_flash_current_ma(1200),
+        Err(LM36011Error::CurrentOutOfRange)
+    ));
+
+    // the highest steps within MAX_FLASH_MA and the torch range
+    driver.set_range_policy(RangePolicy::ClampToLimit);
+    driver.set_flash_current_ma(1200).unwrap();
+    driver.set_torch_current_ma(500).unwrap();
+    assert_eq!(driver.get_flash_current_ma(), 796);
+    driver.free().done();
+}
+
+#[test]
+fn currents_clamped_to_profile() {
+    static PROFILES: [LimitProfile; 1] = [LimitProfile {
+        name: "Indoor",
+        max_flash_ma: 500,
+        max_torch_ma: 100,
+    }];
+    let mut driver = LM36011::new(Mock::new(&[write(&[0x03, 0xA9]), write(&[0x04, 0x21])]))
+        .with_profiles(&PROFILES);
+    driver.set_range_policy(RangePolicy::ClampToLimit);
+    driver.set_flash_current_ma(1000).unwrap();
+    driver.set_torch_current_ma(300).unwrap();
+    driver.free().done();
+}
+
+#[test]
+fn set_profile_lowers_currents() {
+    static PROFILES: [LimitProfile; 2] = [
+        LimitProfile {
+            name: "Full",
+            max_flash_ma: 1500,
+            max_torch_ma: 376,
+        },
+        LimitProfile {
+            name: "Thermal-restricted",
+            max_flash_ma: 500,
+            max_torch_ma: 100,
+        },
+    ];
+    let mut driver = LM36011::new(Mock::new(&[
+        write(&[0x03, 0xFF]),
+        write(&[0x04, 0x65]),
+        write(&[0x03, 0xA9]),
+        write(&[0x04, 0x21]),
+        write(&[0x01, 0x13]),
+    ]))
+    .with_profiles(&PROFILES);
+    driver.set_flash_current_ma(1500).unwrap();
+    driver.set_torch_current_ma(300).unwrap();
+    driver.set_profile("Thermal-restricted").unwrap();
+    driver.trigger_flash().unwrap();
+    driver.free().done();
+}
+
+#[test]
+fn enable_torch_checks_torch_limit() {
+    static PROFILES: [LimitProfile; 1] = [LimitProfile {
+        name: "Indoor",
+        max_flash_ma: 500,
+        max_torch_ma: 100,
+    }];
+    let mut driver = LM36011::new(Mock::new(&[write(&[0x04, 0x21]), write(&[0x01, 0x12])]))
+        .with_profiles(&PROFILES);
+    // a code above the profile, e.g. from the cache, is not lit
+    driver.torch_brightness_flags = LedTorchBrightnessFlags::from_bits_truncate(0x65);
+    assert!(matches!(
+        driver.enable_torch(),
+        Err(LM36011Error::CurrentOutOfRange)
+    ));
+    driver.set_range_policy(RangePolicy::ClampToLimit);
+    driver.enable_torch().unwrap();
+    driver.free().done();
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn current_plan_clamped_to_limit() {
+    expect(&[], |driver| {
+        assert!(matches!(
+            driver.plan_flash_current(2000.0),
+            Err(LM36011Error::CurrentOutOfRange)
+        ));
+
+        // the error is reported against the original request
+        driver.set_range_policy(RangePolicy::ClampToLimit);
+        let plan = driver.plan_flash_current(2000.0).unwrap();
+        assert_eq!(plan.code, 0x7F);
+        assert!((plan.actual_ma - 1500.0).abs() < 1.0);
+        assert!((plan.error_ma - (plan.actual_ma - 2000.0)).abs() < 0.01);
+    });
+}
+
+#[test]
+fn plan_exposure() {
+    expect(&[], |driver| {
+        let plan = driver.plan_exposure(100_000, 1000).unwrap();
+        assert_eq!(plan.timeout, FlashTimeout::Ms120);
+        assert_eq!((plan.code, plan.current_ma), (70, 831));
+        assert_eq!(plan.energy_ma_ms, 99_798);
+
+        // 500 mA for 80 ms lies between codes 41 and 42, nearer 42
+        let plan = driver.plan_exposure(40_000, 1000).unwrap();
+        assert_eq!((plan.code, plan.timeout), (42, FlashTimeout::Ms80));
+        // 25 mA for 40 ms lies between codes 1 and 2, nearer 1
+        let plan = driver.plan_exposure(1000, 1500).unwrap();
+        assert_eq!((plan.code, plan.current_ma), (1, 22));
+    });
+}
+
+#[test]
+fn plan_exposure_boundary_codes() {
+    expect(&[], |driver| {
+        // below the lowest step
+        let plan = driver.plan_exposure(400, 1500).unwrap();
+        assert_eq!((plan.code, plan.timeout), (0, FlashTimeout::Ms40));
+        // exactly the highest step for 40 ms
+        let plan = driver.plan_exposure(59_999, 1500).unwrap();
+        assert_eq!((plan.code, plan.timeout), (0x7F, FlashTimeout::Ms40));
+        assert_eq!(plan.energy_ma_ms, 59_999);
+        // one mA·ms more needs the next time-out
+        let plan = driver.plan_exposure(60_000, 1500).unwrap();
+        assert_eq!(plan.timeout, FlashTimeout::Ms80);
+    });
+}
+
+#[test]
+fn plan_exposure_out_of_range() {
+    expect(&[], |driver| {
+        assert!(matches!(
+            driver.plan_exposure(3_000_000, 1500),
+            Err(LM36011Error::CurrentOutOfRange)
+        ));
+        // below the lowest flash current
+        assert!(matches!(
+            driver.plan_exposure(1000, 10),
+            Err(LM36011Error::CurrentOutOfRange)
+        ));
+
+        driver.set_range_policy(RangePolicy::ClampToLimit);
+        let plan = driver.plan_exposure(3_000_000, 1500).unwrap();
+        assert_eq!((plan.code, plan.timeout), (0x7F, FlashTimeout::Ms1600));
+        assert_eq!(plan.energy_ma_ms, 2_399_960);
+    });
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn plan_current() {
+    expect(&[], |driver| {
+        let plan = driver.plan_flash_current(500.0).unwrap();
+        assert_eq!(plan.code, 41);
+        assert!((plan.actual_ma - 491.625).abs() < 0.01);
+        assert!((plan.error_ma + 8.375).abs() < 0.01);
+
+        let plan = driver.plan_torch_current(376.0).unwrap();
+        assert_eq!(plan.code, 0x7F);
+        assert!((plan.actual_ma - 375.78).abs() < 0.01);
+
+        assert!(matches!(
+            driver.plan_flash_current(1500.5),
+            Err(LM36011Error::CurrentOutOfRange)
+        ));
+        assert!(matches!(
+            driver.plan_torch_current(-1.0),
+            Err(LM36011Error::CurrentOutOfRange)
+        ));
+    });
+}
+
+#[test]
+fn soft_start() {
+    expect(
+        &[
+            write(&[0x04, 0x00]),
+            write(&[0x01, 0x12]),
+            write(&[0x04, 0x10]),
+            write(&[0x04, 0x21]),
+            // with the LED on, the IR ramp starts from the torch level
+            write(&[0x04, 0x21]),
+            write(&[0x01, 0x11]),
+            write(&[0x04, 0x19]),
+            write(&[0x04, 0x10]),
+        ],
+        |driver| {
+            driver.soft_start_torch(100, SoftStart::new(20u32, 2), &mut NoDelay)?;
+            driver.soft_start_ir(50, SoftStart::new(20u32, 2), &mut NoDelay)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn soft_start_after_flash() {
+    // the ended flash left the LED off, so the ramp starts from the bottom
+    expect(
+        &[
+            write(&[0x04, 0x21]),
+            write(&[0x01, 0x13]),
+            write(&[0x04, 0x00]),
+            write(&[0x01, 0x12]),
+            write(&[0x04, 0x10]),
+            write(&[0x04, 0x21]),
+        ],
+        |driver| {
+            driver.set_torch_current_ma(100)?;
+            driver.trigger_flash()?;
+            driver.soft_start_torch(100, SoftStart::new(20u32, 2), &mut NoDelay)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn set_flash_timeout() {
+    expect(&[write(&[0x02, 0x19])], |driver| {
+        driver.set_flash_timeout(FlashTimeout::Ms1000)
+    })
+    .unwrap();
+}
+
+#[test]
+fn set_ivfm_threshold() {
+    expect(&[write(&[0x02, 0xB5])], |driver| {
+        driver.set_ivfm_threshold(IvfmThreshold::V3_4)
+    })
+    .unwrap();
+}
+
+#[test]
+fn set_torch_ramp() {
+    expect(&[write(&[0x02, 0x14])], |driver| {
+        driver.set_torch_ramp(TorchRamp::Off)
+    })
+    .unwrap();
+}
+
+#[test]
+fn configure_strobe() {
+    let strobe = StrobeConfig {
+        enabled: true,
+        trigger: StrobeTrigger::Edge,
+    };
+    expect(&[write(&[0x01, 0x1C])], |driver| {
+        driver.configure_strobe(strobe)
+    })
+    .unwrap();
+}
+
+#[test]
+fn configure_strobe_after_flash() {
+    let strobe = StrobeConfig {
+        enabled: true,
+        trigger: StrobeTrigger::Edge,
+    };
+    // armed in standby, flash mode waiting for the edge is entered explicitly
+    expect(
+        &[
+            write(&[0x01, 0x13]),
+            write(&[0x01, 0x1C]),
+            write(&[0x01, 0x1F]),
+        ],
+        |driver| {
+            driver.trigger_flash()?;
+            driver.configure_strobe(strobe)?;
+            driver.set_mode(OperatingMode::Flash)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn ivfm_enable() {
+    expect(&[write(&[0x01, 0x00]), write(&[0x01, 0x10])], |driver| {
+        driver.disable_ivfm()?;
+        driver.enable_ivfm()
+    })
+    .unwrap();
+}
+
+#[test]
+fn ivfm_enable_after_flash() {
+    // the flash has ended in standby, so the write must not fire it again
+    expect(&[write(&[0x01, 0x13]), write(&[0x01, 0x10])], |driver| {
+        driver.trigger_flash()?;
+        driver.enable_ivfm()
+    })
+    .unwrap();
+}
+
+#[test]
+fn set_thermal_scaleback() {
+    expect(&[read(0x03, &[0x80]), write(&[0x03, 0x00])], |driver| {
+        driver.set_thermal_scaleback(false)
+    })
+    .unwrap();
+}
+
+#[test]
+fn apply_settings() {
+    let settings = Settings {
+        enable: EnableRegisterFlags::from_bits_truncate(0x12),
+        config: ConfigurationRegisterFlags::from_bits_truncate(0x19),
+        flash_brightness: LedFlashBrightnessFlags::from_bits_truncate(0xA0),
+        torch_brightness: LedTorchBrightnessFlags::from_bits_truncate(0x21),
+    };
+    expect(&[write(&[0x01, 0x12, 0x19, 0xA0, 0x21])], |driver| {
+        driver.apply_settings(&settings)
+    })
+    .unwrap();
+}
+
+#[test]
+fn apply_config() {
+    expect(
+        &[
+            read(0x01, &[0x12, 0x15, 0x80, 0x21, 0x00, 0x01]),
+            write(&[0x01, 0x11, 0x15, 0x80, 0x21]),
+        ],
+        |driver| {
+            let mut config = driver.read_config()?;
+            config.mode = OperatingMode::IrDrive;
+            driver.apply_config(&config)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn configure_commit() {
+    expect(&[write(&[0x01, 0x12, 0x19, 0x80, 0x32])], |driver| {
+        driver
+            .configure()
+            .mode(OperatingMode::Torch)
+            .torch_current_ma(150)
+            .flash_timeout(FlashTimeout::Ms1000)
+            .commit()
+    })
+    .unwrap();
+}
+
+#[test]
+fn configure_commit_after_flash() {
+    // the ended flash is committed as standby
+    expect(
+        &[write(&[0x01, 0x13]), write(&[0x01, 0x10, 0x75, 0x80, 0x00])],
+        |driver| {
+            driver.trigger_flash()?;
+            driver.configure().ivfm(IvfmThreshold::V3_2).commit()
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn read_config_during_flash() {
+    expect(
+        &[
+            read(0x01, &[0x13, 0x15, 0x80, 0x00, 0x00, 0x01]),
+            write(&[0x01, 0x10, 0x15, 0x80, 0x00]),
+        ],
+        |driver| {
+            let config = driver.read_config()?;
+            assert_eq!(config.mode, OperatingMode::Standby);
+            driver.apply_config(&config)
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn builder() {
+    let driver = LM36011::builder(Mock::new(&[write(&[0x01, 0x10, 0xB5, 0xA1, 0x00])]))
+        .flash_current_ma(400)
+        .ivfm(IvfmThreshold::V3_4)
+        .build()
+        .unwrap();
+    driver.free().done();
+}
+
+#[test]
+fn apply_preset() {
+    let preset = BoardPreset {
+        torch_ma: 100,
+        flash_ma: 1200,
+        timeout: FlashTimeout::Ms1000,
+        max_flash_ma: 1500,
+        max_torch_ma: 376,
+    };
+    expect(
+        &[
+            write(&[0x03, 0xE5]),
+            write(&[0x04, 0x21]),
+            write(&[0x02, 0x19]),
+        ],
+        |driver| driver.apply_preset(&preset),
+    )
+    .unwrap();
+}
+
+#[test]
+fn shutdown() {
+    expect(&[write(&[0x01, 0x00]), read(0x01, &[0x00])], |driver| {
+        driver.shutdown(true)
+    })
+    .unwrap();
+}
+
+#[test]
+fn read_faults() {
+    let faults = expect(&[read(0x05, &[0x22])], |driver| driver.read_faults()).unwrap();
+    assert!(faults.vled_short && faults.uvlo);
+}
+
+#[test]
+fn clear_faults() {
+    expect(&[read(0x05, &[0x04])], |driver| {
+        driver.clear_faults()?;
+        assert!(!driver.pending_faults().is_fault());
+        Ok::<_, LM36011Error<MockError>>(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn has_fault() {
+    let fault = expect(&[read(0x05, &[0x02])], |driver| driver.has_fault());
+    assert!(fault.unwrap());
+}
+
+#[test]
+fn monitor_faults_cut_off() {
+    // the thermal shutdown puts the device in standby and ends the monitoring
+    let result = expect(
+        &[
+            read(0x05, &[0x00]),
+            read(0x05, &[0x04]),
+            write(&[0x01, 0x10]),
+        ],
+        |driver| {
+            driver.set_fault_cutoff(true);
+            driver.monitor_faults(&mut NoDelay, 100, 3)
+        },
+    );
+    assert!(matches!(result, Err(LM36011Error::FaultShutdown)));
+}
+
+#[test]
+fn apply_settings_after_flash() {
+    // settings saved right after an I2C flash are restored in standby
+    let saved = expect(&[write(&[0x01, 0x13])], |driver| {
+        driver.trigger_flash().unwrap();
+        driver.settings().encode()
+    });
+    let settings = Settings::decode(&saved).unwrap();
+    expect(&[write(&[0x01, 0x10, 0x15, 0x80, 0x00])], |driver| {
+        driver.apply_settings(&settings)
+    })
+    .unwrap();
+}
+
+#[test]
+fn apply_settings_cut_off_keeps_cache() {
+    let settings = Settings {
+        enable: EnableRegisterFlags::from_bits_truncate(0x12),
+        config: ConfigurationRegisterFlags::from_bits_truncate(0x19),
+        flash_brightness: LedFlashBrightnessFlags::from_bits_truncate(0xA0),
+        torch_brightness: LedTorchBrightnessFlags::from_bits_truncate(0x21),
+    };
+    // a rejected apply must not leave the torch mode behind for the next Enable write
+    expect(
+        &[
+            read(0x05, &[0x04]),
+            write(&[0x01, 0x10]),
+            read(0x05, &[0x00]),
+            write(&[0x01, 0x00]),
+        ],
+        |driver| {
+            driver.set_fault_cutoff(true);
+            assert!(driver.read_faults().is_err());
+            let cached = driver.settings();
+            assert!(matches!(
+                driver.apply_settings(&settings),
+                Err(LM36011Error::FaultShutdown)
+            ));
+            assert_eq!(driver.settings(), cached);
+            driver.clear_faults().unwrap();
+            driver.disable_ivfm().unwrap();
+        },
+    );
+}
+
+#[test]
+fn led_connectivity_check_cut_off() {
+    // the cut-off tripped by the test pulse leaves the device in standby
+    let result = expect(
+        &[
+            read(0x05, &[0x00]),
+            write(&[0x02, 0x01, 0x80]),
+            write(&[0x01, 0x13]),
+            read(0x05, &[0x04]),
+            write(&[0x01, 0x10]),
+            write(&[0x02, 0x15, 0x80]),
+        ],
+        |driver| {
+            driver.set_fault_cutoff(true);
+            driver.led_connectivity_check(&mut NoDelay)
+        },
+    );
+    assert!(matches!(result, Err(LM36011Error::FaultShutdown)));
+}
+
+#[test]
+fn led_connectivity_check_after_flash() {
+    // the ended flash is restored as standby, not fired again
+    let result = expect(
+        &[
+            write(&[0x01, 0x13]),
+            read(0x05, &[0x00]),
+            write(&[0x02, 0x01, 0x80]),
+            write(&[0x01, 0x13]),
+            read(0x05, &[0x00]),
+            write(&[0x02, 0x15, 0x80]),
+            write(&[0x01, 0x10]),
+        ],
+        |driver| {
+            driver.trigger_flash()?;
+            driver.led_connectivity_check(&mut NoDelay)
+        },
+    );
+    assert_eq!(result.unwrap(), LedCheck::Healthy);
+}
+
+#[test]
+fn self_test_led_cut_off() {
+    // the VLED short trips the cut-off, only the torch current is restored
+    let result = expect(
+        &[
+            write(&[0x04, 0x21]),
+            read(0x05, &[0x00]),
+            write(&[0x04, 0x00]),
+            write(&[0x01, 0x12]),
+            read(0x05, &[0x20]),
+            write(&[0x01, 0x10]),
+            write(&[0x04, 0x21]),
+        ],
+        |driver| {
+            driver.set_torch_current_ma(100)?;
+            driver.set_fault_cutoff(true);
+            driver.self_test_led(&mut NoDelay)
+        },
+    );
+    assert!(matches!(result, Err(LM36011Error::FaultShutdown)));
+}
+
+#[test]
+fn self_test_led_after_flash() {
+    // the ended flash is restored as standby, not fired again
+    let result = expect(
+        &[
+            write(&[0x01, 0x13]),
+            read(0x05, &[0x00]),
+            write(&[0x04, 0x00]),
+            write(&[0x01, 0x12]),
+            read(0x05, &[0x00]),
+            write(&[0x01, 0x10]),
+            write(&[0x04, 0x00]),
+        ],
+        |driver| {
+            driver.trigger_flash()?;
+            driver.self_test_led(&mut NoDelay)
+        },
+    );
+    assert_eq!(result.unwrap(), LedCheck::Healthy);
+}
+
+#[test]
+fn flash_and_check() {
+    let faults = expect(
+        &[
+            read(0x05, &[0x00]),
+            write(&[0x01, 0x13]),
+            read(0x05, &[0x20]),
+        ],
+        |driver| driver.flash_and_check(&mut NoDelay),
+    )
+    .unwrap();
+    assert!(faults.vled_short && faults.is_fault());
+}
+
+#[test]
+fn recover_from_uvlo() {
+    let restored = expect(
+        &[
+            read(0x05, &[0x02]),
+            write(&[0x01, 0x10, 0x15, 0x80, 0x00]),
+            write(&[0x01, 0x12]),
+        ],
+        |driver| {
+            driver.enable_flags.insert(EnableRegisterFlags::MODE_TORCH);
+            driver.recover_from_uvlo(3000)
+        },
+    );
+    assert!(restored.unwrap());
+}
+
+#[test]
+fn recover_from_uvlo_after_flash() {
+    let restored = expect(
+        &[
+            write(&[0x01, 0x13]),
+            read(0x05, &[0x02]),
+            write(&[0x01, 0x10, 0x15, 0x80, 0x00]),
+            write(&[0x01, 0x10]),
+        ],
+        |driver| {
+            driver.trigger_flash()?;
+            driver.recover_from_uvlo(3000)
+        },
+    );
+    assert!(restored.unwrap());
+}
+
+struct Sense(u16);
+
+impl CurrentSense for Sense {
+    type Error = ();
+
+    fn read_ma(&mut self) -> Result<u16, ()> {
+        Ok(self.0)
+    }
+}
+
+#[test]
+fn verify_current_does_not_touch_the_bus() {
+    let check = expect(&[], |driver| driver.verify_current(&mut Sense(2), 10));
+    assert!(matches!(
+        check.unwrap(),
+        CurrentCheck::WithinTolerance { expected_ma: 0, .. }
+    ));
+}
+
+#[test]
+fn rearm_after_timeout() {
+    let mut trigger: TriggerMode = TriggerMode::I2cOnly;
+    let rearmed = expect(&[read(0x05, &[0x01]), write(&[0x01, 0x10])], |driver| {
+        driver.rearm_after_timeout(&mut trigger, None)
+    });
+    assert!(rearmed.unwrap());
+}
+
+#[test]
+fn confirm_flash_fired() {
+    let outcome = expect(
+        &[read(0x01, &[0x10, 0x15, 0x80, 0x00, 0x04, 0x01])],
+        |driver| driver.confirm_flash_fired(),
+    );
+    assert!(matches!(outcome.unwrap(), FlashOutcome::Fault(_)));
+
+    let outcome = expect(
+        &[read(0x01, &[0x13, 0x15, 0x80, 0x00, 0x00, 0x01])],
+        |driver| driver.confirm_flash_fired(),
+    );
+    assert_eq!(outcome.unwrap(), FlashOutcome::InProgress);
+}
+
+#[test]
+fn diagnostics() {
+    let report = expect(
+        &[
+            read(0x06, &[0x01]),
+            read(0x01, &[0x10, 0x15, 0x80, 0x00, 0x02, 0x01]),
+        ],
+        |driver| driver.diagnostics(),
+    )
+    .unwrap();
+    assert_eq!(report.fault_counters.uvlo, 1);
+}
+
+#[test]
+fn bus_error_is_reported() {
+    let result = expect(
+        &[Transaction::write(ADDRESS, vec![0x06, 0x80])
+            .with_error(MockError::Io(std::io::ErrorKind::Other))],
+        |driver| driver.software_reset(),
+    );
+    assert!(result.unwrap_err().is_bus_error());
+}
+
+#[cfg(feature = "heapless")]
+#[test]
+fn queue_drain() {
+    use lm36011::queue::{Command, CommandQueue};
+
+    let mut queue: CommandQueue<4> = CommandQueue::new();
+    let (mut sender, mut receiver) = queue.split();
+    sender.enqueue(Command::TorchOn(100)).unwrap();
+    sender.enqueue(Command::FlashCurrent(2000)).unwrap();
+    sender.enqueue(Command::Off).unwrap();
+
+    let mut driver = LM36011::new(Mock::new(&[
+        write(&[0x04, 0x21]),
+        write(&[0x01, 0x12]),
+        write(&[0x01, 0x10]),
+    ]));
+    // the failing command is dropped, the one after it waits for the next call
+    assert!(matches!(
+        receiver.drain(&mut driver),
+        Err(LM36011Error::CurrentOutOfRange)
+    ));
+    assert_eq!(receiver.drain(&mut driver).unwrap(), 1);
+    assert_eq!(receiver.drain(&mut driver).unwrap(), 0);
+    driver.free().done();
+}
+
+#[cfg(feature = "rtic")]
+#[test]
+fn rtic_operations() {
+    use lm36011::rtic::SharedLM36011;
+    use rtic_core::Exclusive;
+
+    let faults = expect(&[write(&[0x01, 0x13]), read(0x05, &[0x40])], |driver| {
+        let mut shared = Exclusive(driver);
+        shared.trigger_flash()?;
+        shared.read_faults()
+    })
+    .unwrap();
+    assert!(faults.ivfm_trip);
+}
+
+#[cfg(feature = "rtic")]
+#[test]
+fn rtic_write_cached_writes_enable_last() {
+    use lm36011::rtic::SharedLM36011;
+    use rtic_core::Exclusive;
+
+    expect(
+        &[
+            write(&[0x02, 0x15]),
+            write(&[0x03, 0x80]),
+            write(&[0x04, 0x00]),
+            write(&[0x01, 0x10]),
+        ],
+        |driver| Exclusive(driver).write_cached(),
+    )
+    .unwrap();
+}